@@ -3,18 +3,56 @@ pub mod tree;
 pub use mbr::MBR;
 pub use tree::*;
 
+/// Makes an MBR: `mbr![X = [min; max], Y = [min; max], ...]`.
+///
+/// A single point on an axis can be written as `X = point`,
+/// which is the same as `X = [point; point]`.
+/// Both forms can be mixed in one invocation.
 #[macro_export]
 macro_rules! mbr {
+    (@munch [$($bounds:expr,)*]) => {
+        $crate::mbr::MBR::new(
+            vec![$($bounds),*]
+        )
+    };
+
     (
-        $($_axis_name:ident = [$min_bound:expr; $max_bound:expr]),+
+        @munch [$($bounds:expr,)*]
+        $_axis_name:ident = [$min_bound:expr; $max_bound:expr]
+        $(, $($rest:tt)*)?
     ) => {
-        $crate::mbr::MBR::new(
-            vec![$(
+        $crate::mbr!(
+            @munch [
+                $($bounds,)*
                 $crate::mbr::Bounds::new(
                     $min_bound,
                     $max_bound
-                )
-            ),+]
+                ),
+            ]
+            $($($rest)*)?
+        )
+    };
+
+    (
+        @munch [$($bounds:expr,)*]
+        $_axis_name:ident = $point:expr
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::mbr!(
+            @munch [
+                $($bounds,)*
+                {
+                    let point = $point;
+                    $crate::mbr::Bounds::new(point.clone(), point)
+                },
+            ]
+            $($($rest)*)?
         )
     };
+
+    (
+        $($axes:tt)+
+    ) => {
+        $crate::mbr!(@munch [] $($axes)+)
+    };
 }
@@ -0,0 +1,125 @@
+use {
+    super::{mbr, CoordTrait, ObjSpace, RecordId, MBR},
+    std::{cmp::Ordering, fmt::Debug},
+};
+
+/// Algorithm of choosing the subtree for a new record, see `LRTree::set_choose_subtree`.
+pub trait ChooseSubtree<CoordT: CoordTrait, ObjectT: Clone>: Debug + Send + Sync {
+    /// Chooses one of the `candidates` (the children of the same node)
+    /// to insert the record with the `inserting` MBR into.
+    fn choose(
+        &self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        candidates: &[RecordId],
+        inserting: &MBR<CoordT>,
+    ) -> RecordId;
+}
+
+/// The candidate with the minimal volume enlargement, tie-broken by the volume
+/// (see `LRTree::set_margin_tie_break`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeastEnlargement;
+
+/// The candidate with the minimal overlap enlargement (as in the R*-tree)
+/// on the level above the leaves, `LeastEnlargement` on the upper levels.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeastOverlap;
+
+impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ChooseSubtree<CoordT, ObjectT>
+    for LeastEnlargement
+{
+    fn choose(
+        &self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        candidates: &[RecordId],
+        inserting: &MBR<CoordT>,
+    ) -> RecordId {
+        *candidates
+            .iter()
+            .map(|child_id| {
+                let delta = mbr::cost_delta(
+                    obj_space.get_mbr(*child_id),
+                    inserting,
+                    obj_space.margin_tie_break,
+                );
+
+                crate::debug_log!("{}, delta for {:?} = {:?}", inserting, child_id, delta);
+
+                (child_id, delta)
+            })
+            .min_by(|lhs, rhs| {
+                let (&lhs_id, lhs_delta) = lhs;
+                let (&rhs_id, rhs_delta) = rhs;
+
+                let ord = lhs_delta
+                    .partial_cmp(rhs_delta)
+                    .expect("cmp result is expected");
+
+                match ord {
+                    Ordering::Equal => obj_space
+                        .get_mbr(lhs_id)
+                        .cost(obj_space.margin_tie_break)
+                        .partial_cmp(&obj_space.get_mbr(rhs_id).cost(obj_space.margin_tie_break))
+                        .expect("cmp result is expected"),
+                    _ => ord,
+                }
+            })
+            .map(|(id, _)| id)
+            .unwrap()
+    }
+}
+
+impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ChooseSubtree<CoordT, ObjectT> for LeastOverlap {
+    fn choose(
+        &self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        candidates: &[RecordId],
+        inserting: &MBR<CoordT>,
+    ) -> RecordId {
+        if !matches![candidates.first(), Some(RecordId::Leaf(_))] {
+            return LeastEnlargement.choose(obj_space, candidates, inserting);
+        }
+
+        let overlap = |child_id: RecordId, child_mbr: &MBR<CoordT>| -> f64 {
+            candidates
+                .iter()
+                .filter(|&&sibling_id| sibling_id != child_id)
+                .filter_map(|&sibling_id| {
+                    mbr::intersection(child_mbr, obj_space.get_mbr(sibling_id))
+                })
+                .map(|overlap| overlap.volume_f64())
+                .sum()
+        };
+
+        *candidates
+            .iter()
+            .map(|child_id| {
+                let child_mbr = obj_space.get_mbr(*child_id);
+                let expanded_mbr = mbr::common_mbr(child_mbr, inserting);
+
+                let overlap_delta =
+                    overlap(*child_id, &expanded_mbr) - overlap(*child_id, child_mbr);
+                let delta = mbr::cost_delta(child_mbr, inserting, obj_space.margin_tie_break);
+
+                crate::debug_log!(
+                    "{}, overlap delta for {:?} = {:?}, delta = {:?}",
+                    inserting,
+                    child_id,
+                    overlap_delta,
+                    delta
+                );
+
+                (
+                    child_id,
+                    (
+                        overlap_delta,
+                        delta,
+                        child_mbr.cost(obj_space.margin_tie_break),
+                    ),
+                )
+            })
+            .min_by(|(_, lhs), (_, rhs)| lhs.partial_cmp(rhs).expect("cmp result is expected"))
+            .map(|(id, _)| id)
+            .unwrap()
+    }
+}
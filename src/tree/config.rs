@@ -0,0 +1,128 @@
+use {
+    super::{
+        CoordTrait, LRTree, ObjSpace, DEFAULT_DIMENSION, DEFAULT_MAX_RECORDS, DEFAULT_MIN_RECORDS,
+    },
+    std::{
+        error::Error,
+        fmt::{self, Debug, Display},
+    },
+};
+
+/// Error of `TreeConfig::build`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The dimension is 0.
+    ZeroDimension,
+
+    /// `min_records` is less than 2.
+    MinRecordsTooSmall { min_records: usize },
+
+    /// `min_records` is greater than a half of `max_records` (rounded up).
+    MinRecordsTooLarge {
+        min_records: usize,
+        max_records: usize,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroDimension => write!(f, "the dimension must be greater than 0"),
+            Self::MinRecordsTooSmall { min_records } => {
+                write!(f, "min_records must be at least 2, got {}", min_records)
+            }
+            Self::MinRecordsTooLarge {
+                min_records,
+                max_records,
+            } => write!(
+                f,
+                "min_records ({}) must not exceed a half of max_records ({})",
+                min_records, max_records
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Parameters of a new `ObjSpace`.
+///
+/// By default the dimension is 2, `min_records` is 2 and `max_records` is 8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeConfig {
+    dimension: usize,
+    min_records: usize,
+    max_records: usize,
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        Self {
+            dimension: DEFAULT_DIMENSION,
+            min_records: DEFAULT_MIN_RECORDS,
+            max_records: DEFAULT_MAX_RECORDS,
+        }
+    }
+}
+
+impl TreeConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn dimension(mut self, dimension: usize) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    pub fn min_records(mut self, min_records: usize) -> Self {
+        self.min_records = min_records;
+        self
+    }
+
+    pub fn max_records(mut self, max_records: usize) -> Self {
+        self.max_records = max_records;
+        self
+    }
+
+    /// Checks the parameters and makes an empty `ObjSpace`.
+    pub fn build<CoordT, ObjectT>(&self) -> Result<ObjSpace<CoordT, ObjectT>, ConfigError>
+    where
+        CoordT: CoordTrait,
+        ObjectT: Debug + Clone,
+    {
+        if self.dimension == 0 {
+            return Err(ConfigError::ZeroDimension);
+        }
+
+        if self.min_records < 2 {
+            return Err(ConfigError::MinRecordsTooSmall {
+                min_records: self.min_records,
+            });
+        }
+
+        if self.min_records > (self.max_records as f64 / 2.0).ceil() as usize {
+            return Err(ConfigError::MinRecordsTooLarge {
+                min_records: self.min_records,
+                max_records: self.max_records,
+            });
+        }
+
+        Ok(ObjSpace::new(
+            self.dimension,
+            self.min_records,
+            self.max_records,
+        ))
+    }
+}
+
+impl<CoordT, ObjectT> LRTree<CoordT, ObjectT>
+where
+    CoordT: CoordTrait,
+    ObjectT: Debug + Clone,
+{
+    /// Makes an empty tree with the parameters checked by `TreeConfig::build`.
+    pub fn with_config(config: TreeConfig) -> Result<Self, ConfigError> {
+        config.build().map(Self::with_obj_space)
+    }
+}
@@ -0,0 +1,153 @@
+use {
+    super::{Bounds, CoordTrait, LRTree, ObjSpace, INSERT_MANY_ALPHA, MBR},
+    std::{
+        error::Error,
+        fmt::{self, Display},
+        io::Read,
+        str::FromStr,
+    },
+};
+
+/// Error of `LRTree::from_csv`.
+///
+/// Rows are numbered from 1.
+#[derive(Debug)]
+pub enum CsvImportError {
+    /// The CSV can't be read.
+    Csv(csv::Error),
+
+    /// The row has an unexpected number of columns.
+    ColumnsNum {
+        row: u64,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// The column value can't be parsed.
+    Value {
+        row: u64,
+        column: usize,
+        value: String,
+    },
+
+    /// The min bound is greater than the max bound.
+    Bounds { row: u64, axis: usize },
+}
+
+impl Display for CsvImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv(error) => write!(f, "unable to read CSV: {}", error),
+            Self::ColumnsNum {
+                row,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "row {}: expected {} columns, found {}",
+                row, expected, actual
+            ),
+            Self::Value { row, column, value } => write!(
+                f,
+                "row {}, column {}: unable to parse \"{}\"",
+                row, column, value
+            ),
+            Self::Bounds { row, axis } => write!(
+                f,
+                "row {}, axis {}: a min bound must be less than a max bound",
+                row, axis
+            ),
+        }
+    }
+}
+
+impl Error for CsvImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Csv(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for CsvImportError {
+    fn from(error: csv::Error) -> Self {
+        Self::Csv(error)
+    }
+}
+
+impl<CoordT: CoordTrait + FromStr> LRTree<CoordT, usize> {
+    /// Makes a tree of the rectangles from the CSV without a header.
+    ///
+    /// Every row is `index, min_0, max_0, ..., min_n, max_n`,
+    /// the index is used as the object.
+    /// The tree is built by the static builder like `insert_many` on an empty tree.
+    pub fn from_csv<R: Read>(
+        reader: R,
+        dimension: usize,
+        min_records: usize,
+        max_records: usize,
+    ) -> Result<Self, CsvImportError> {
+        crate::debug_log!("import lr-tree from CSV");
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let mut data = vec![];
+        for record in reader.records() {
+            let record = record?;
+            let row = record.position().map_or(0, |position| position.line());
+
+            if record.len() != 2 * dimension + 1 {
+                return Err(CsvImportError::ColumnsNum {
+                    row,
+                    expected: 2 * dimension + 1,
+                    actual: record.len(),
+                });
+            }
+
+            let parse_error = |column: usize| CsvImportError::Value {
+                row,
+                column,
+                value: record[column].to_string(),
+            };
+
+            let object = record[0].parse::<usize>().map_err(|_| parse_error(0))?;
+
+            let mut bounds = Vec::with_capacity(dimension);
+            for axis in 0..dimension {
+                let (min_column, max_column) = (2 * axis + 1, 2 * axis + 2);
+
+                let min = record[min_column]
+                    .parse::<CoordT>()
+                    .map_err(|_| parse_error(min_column))?;
+                let max = record[max_column]
+                    .parse::<CoordT>()
+                    .map_err(|_| parse_error(max_column))?;
+
+                if !min.le(&max) {
+                    return Err(CsvImportError::Bounds { row, axis });
+                }
+
+                bounds.push(Bounds::new(min, max));
+            }
+
+            data.push((object, MBR::new(bounds)));
+        }
+
+        let tree = Self::with_obj_space(ObjSpace::with_data(
+            dimension,
+            min_records,
+            max_records,
+            data,
+        ));
+        tree.rebuild(INSERT_MANY_ALPHA);
+
+        crate::debug_log!("[COMPLETED] import lr-tree from CSV");
+
+        Ok(tree)
+    }
+}
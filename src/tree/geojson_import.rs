@@ -0,0 +1,152 @@
+use {
+    super::{LRTree, ObjSpace, INSERT_MANY_ALPHA, MBR},
+    serde_json::Value,
+    std::{
+        error::Error,
+        fmt::{self, Display},
+    },
+};
+
+/// Error of `LRTree::from_geojson`.
+///
+/// Features are numbered from 0.
+#[derive(Debug)]
+pub enum GeoJsonImportError {
+    /// The input is not a valid JSON.
+    Json(serde_json::Error),
+
+    /// The input is not a GeoJSON `FeatureCollection`.
+    NotFeatureCollection,
+
+    /// The feature is not a JSON object.
+    Feature { feature: usize },
+
+    /// The feature geometry is malformed.
+    Geometry { feature: usize },
+}
+
+impl Display for GeoJsonImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(error) => write!(f, "unable to parse JSON: {}", error),
+            Self::NotFeatureCollection => write!(f, "GeoJSON FeatureCollection is expected"),
+            Self::Feature { feature } => write!(f, "feature {}: JSON object is expected", feature),
+            Self::Geometry { feature } => {
+                write!(f, "feature {}: malformed geometry", feature)
+            }
+        }
+    }
+}
+
+impl Error for GeoJsonImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for GeoJsonImportError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl LRTree<f64, Value> {
+    /// Makes a 2D tree of the bounding boxes of the GeoJSON `FeatureCollection` features.
+    ///
+    /// The feature `properties` are used as the objects.
+    /// Features without a geometry (or with an empty one) are skipped with a warning.
+    /// The tree is built by the static builder like `insert_many` on an empty tree.
+    pub fn from_geojson(
+        json: &str,
+        min_records: usize,
+        max_records: usize,
+    ) -> Result<Self, GeoJsonImportError> {
+        crate::debug_log!("import lr-tree from GeoJSON");
+
+        let mut collection = serde_json::from_str::<Value>(json)?;
+        if collection["type"] != "FeatureCollection" {
+            return Err(GeoJsonImportError::NotFeatureCollection);
+        }
+
+        let features = match collection["features"].take() {
+            Value::Array(features) => features,
+            _ => return Err(GeoJsonImportError::NotFeatureCollection),
+        };
+
+        let mut data = vec![];
+        let mut skipped_num = 0;
+        for (i, mut feature) in features.into_iter().enumerate() {
+            if !feature.is_object() {
+                return Err(GeoJsonImportError::Feature { feature: i });
+            }
+
+            let mut bbox = None;
+            if !feature["geometry"].is_null() {
+                add_geometry_to_bbox(&feature["geometry"], &mut bbox)
+                    .ok_or(GeoJsonImportError::Geometry { feature: i })?;
+            }
+
+            match bbox {
+                Some([(min_x, max_x), (min_y, max_y)]) => data.push((
+                    feature["properties"].take(),
+                    MBR::from([(min_x, max_x), (min_y, max_y)]),
+                )),
+                None => skipped_num += 1,
+            }
+        }
+
+        if skipped_num > 0 {
+            log::warn! {
+                target: env!("CARGO_PKG_NAME"),
+                "{} GeoJSON features without geometry are skipped",
+                skipped_num
+            };
+        }
+
+        let tree = Self::with_obj_space(ObjSpace::with_data(2, min_records, max_records, data));
+        tree.rebuild(INSERT_MANY_ALPHA);
+
+        crate::debug_log!("[COMPLETED] import lr-tree from GeoJSON");
+
+        Ok(tree)
+    }
+}
+
+/// Extends the `bbox` by all the positions of the geometry.
+///
+/// Returns `None` if the geometry is malformed.
+fn add_geometry_to_bbox(geometry: &Value, bbox: &mut Option<[(f64, f64); 2]>) -> Option<()> {
+    match geometry["type"].as_str()? {
+        "GeometryCollection" => geometry["geometries"]
+            .as_array()?
+            .iter()
+            .try_for_each(|geometry| add_geometry_to_bbox(geometry, bbox)),
+        _ => add_coordinates_to_bbox(&geometry["coordinates"], bbox),
+    }
+}
+
+/// Extends the `bbox` by a position or by nested arrays of positions.
+fn add_coordinates_to_bbox(coordinates: &Value, bbox: &mut Option<[(f64, f64); 2]>) -> Option<()> {
+    let coordinates = coordinates.as_array()?;
+
+    match coordinates.first() {
+        Some(Value::Number(_)) => {
+            let x = coordinates.first()?.as_f64()?;
+            let y = coordinates.get(1)?.as_f64()?;
+
+            let [(min_x, max_x), (min_y, max_y)] = bbox.get_or_insert([(x, x), (y, y)]);
+            *min_x = min_x.min(x);
+            *max_x = max_x.max(x);
+            *min_y = min_y.min(y);
+            *max_y = max_y.max(y);
+
+            Some(())
+        }
+        _ => coordinates
+            .iter()
+            .try_for_each(|coordinates| add_coordinates_to_bbox(coordinates, bbox)),
+    }
+}
@@ -0,0 +1,67 @@
+//! The `RwLock` guarding the `ObjSpace`.
+//!
+//! It is `std::sync::RwLock` by default and `parking_lot::RwLock`
+//! with the `parking_lot` feature. The std lock panics if it is poisoned,
+//! the `parking_lot` one is never poisoned.
+
+#[cfg(not(feature = "parking_lot"))]
+pub use std::sync::{RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "parking_lot")]
+pub use parking_lot::{RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::TryLockError;
+
+#[derive(Debug)]
+pub(crate) struct RwLock<T> {
+    #[cfg(not(feature = "parking_lot"))]
+    inner: std::sync::RwLock<T>,
+
+    #[cfg(feature = "parking_lot")]
+    inner: parking_lot::RwLock<T>,
+}
+
+impl<T> RwLock<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: value.into(),
+        }
+    }
+
+    #[cfg(not(feature = "parking_lot"))]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.read().unwrap()
+    }
+
+    #[cfg(feature = "parking_lot")]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.read()
+    }
+
+    /// Returns `None` if the lock is held for writing.
+    #[cfg(not(feature = "parking_lot"))]
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        match self.inner.try_read() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::WouldBlock) => None,
+            Err(TryLockError::Poisoned(error)) => panic!("{}", error),
+        }
+    }
+
+    /// Returns `None` if the lock is held for writing.
+    #[cfg(feature = "parking_lot")]
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.inner.try_read()
+    }
+
+    #[cfg(not(feature = "parking_lot"))]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.inner.write().unwrap()
+    }
+
+    #[cfg(feature = "parking_lot")]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.inner.write()
+    }
+}
@@ -1,17 +1,42 @@
 use {
     num::{Num, NumCast},
     std::{
-        cmp::{Ordering, PartialOrd},
+        cmp::PartialOrd,
         fmt::{self, Debug, Display},
-        mem::MaybeUninit,
+        ops::Sub,
     },
 };
 
+#[cfg(feature = "simd")]
+use std::any::Any;
+
+#[cfg(feature = "geo")]
+use geo_types::{Coord, CoordNum, Rect};
+
+#[cfg(feature = "serde")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(not(feature = "simd"))]
 pub trait CoordTrait: Default + Debug + Num + NumCast + PartialOrd<Self> + Clone {}
 
+#[cfg(not(feature = "simd"))]
 impl<T> CoordTrait for T where T: Default + Debug + Num + NumCast + PartialOrd<Self> + Clone {}
 
+/// With the `simd` feature `'static` lets `intersects` dispatch the `f32` and `f64` MBRs
+/// to the `simd` module.
+#[cfg(feature = "simd")]
+pub trait CoordTrait: Default + Debug + Num + NumCast + PartialOrd<Self> + Clone + 'static {}
+
+#[cfg(feature = "simd")]
+impl<T> CoordTrait for T where
+    T: Default + Debug + Num + NumCast + PartialOrd<Self> + Clone + 'static
+{
+}
+
+/// `repr(C)` keeps `min` before `max`, so the bounds of an MBR are the contiguous
+/// `[min, max, min, max, ...]` coordinates loaded by the `simd` module.
 #[derive(Debug)]
+#[repr(C)]
 pub struct Bounds<CoordT> {
     pub min: CoordT,
     pub max: CoordT,
@@ -40,6 +65,42 @@ impl<CoordT: CoordTrait> Bounds<CoordT> {
     pub fn length(&self) -> CoordT {
         self.max.clone() - self.min.clone()
     }
+
+    /// Checks if `other` is fully inside `self` (bounds are inclusive).
+    pub fn contains(&self, other: &Bounds<CoordT>) -> bool {
+        self.min <= other.min && other.max <= self.max
+    }
+
+    /// Length of the overlap of two bounds, zero if they are disjoint.
+    pub fn overlap_length(&self, other: &Bounds<CoordT>) -> CoordT {
+        let min = if self.min > other.min {
+            &self.min
+        } else {
+            &other.min
+        };
+
+        let max = if self.max < other.max {
+            &self.max
+        } else {
+            &other.max
+        };
+
+        if min < max {
+            max.clone() - min.clone()
+        } else {
+            CoordT::zero()
+        }
+    }
+
+    /// Middle of the bounds.
+    ///
+    /// Computed as `min + (max - min) / 2`,
+    /// so for integer coordinates it is rounded towards `min` (and it never overflows).
+    pub fn midpoint(&self) -> CoordT {
+        let two = CoordT::one() + CoordT::one();
+
+        self.min.clone() + self.length() / two
+    }
 }
 
 impl<CoordT: CoordTrait> Clone for Bounds<CoordT> {
@@ -59,6 +120,39 @@ impl<CoordT: PartialEq> PartialEq for Bounds<CoordT> {
 
 impl<CoordT: PartialEq + Eq> Eq for Bounds<CoordT> {}
 
+/// Max dimension of an MBR whose corner points can be enumerated by `MBR::corner_points`.
+pub const MAX_CORNER_POINTS_DIMENSION: usize = 16;
+
+/// Measure of an MBR used by the tree algorithms, see `MBR::cost`.
+///
+/// The costs are compared by the volume first, the margin only breaks the ties
+/// (e.g. the points and the lines all have zero volume).
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Cost {
+    pub volume: f64,
+    pub margin: f64,
+}
+
+impl Cost {
+    pub fn abs(self) -> Self {
+        Self {
+            volume: self.volume.abs(),
+            margin: self.margin.abs(),
+        }
+    }
+}
+
+impl Sub for Cost {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            volume: self.volume - rhs.volume,
+            margin: self.margin - rhs.margin,
+        }
+    }
+}
+
 /// Minimum bounding rectangle
 #[derive(Debug)]
 pub struct MBR<CoordT> {
@@ -72,6 +166,20 @@ impl<CoordT: CoordTrait> MBR<CoordT> {
         unsafe { Self::new_unchecked(bounds) }
     }
 
+    /// Makes an MBR from `(min, max)` pairs, one pair per axis.
+    ///
+    /// Unlike the `mbr!` macro, the dimension can be known only at runtime.
+    pub fn from_bounds_iter<I: IntoIterator<Item = (CoordT, CoordT)>>(iter: I) -> Self {
+        let bounds = iter
+            .into_iter()
+            .map(|(min, max)| Bounds::new(min, max))
+            .collect::<Vec<_>>();
+
+        assert!(!bounds.is_empty(), "MBR can't be zero-dimension");
+
+        unsafe { Self::new_unchecked(bounds) }
+    }
+
     /// # Safety
     ///
     /// `bounds` must be not empty.
@@ -87,9 +195,17 @@ impl<CoordT: CoordTrait> MBR<CoordT> {
     /// # Notes
     /// * `common_mbr`: for undefined MBR and any other MBR returns the other one.
     /// * `intersects`: undefined MBR intersects with any other MBR.
+    /// * `intersection`: returns `None`.
+    /// * `contains`: undefined MBR contains nothing (even another undefined MBR),
+    ///   but it is contained by any other MBR.
     /// * `dimension`: returns `0`.
     /// * `bounds`: panics.
-    /// * `volume`: returns `0`.
+    /// * `iter_bounds`: yields nothing.
+    /// * `volume`, `volume_f64`: returns `0`.
+    /// * `margin`: returns `0`.
+    /// * `cost`: returns the zero `Cost`.
+    /// * `surface_area`: returns `0`.
+    /// * `corner_points`: returns an empty `Vec`.
     pub unsafe fn undefined() -> Self {
         Self::new_unchecked(vec![])
     }
@@ -106,6 +222,15 @@ impl<CoordT: CoordTrait> MBR<CoordT> {
         &self.bounds[axis_index]
     }
 
+    pub fn iter_bounds(&self) -> impl Iterator<Item = &Bounds<CoordT>> {
+        self.bounds.iter()
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub(crate) fn bounds_slice(&self) -> &[Bounds<CoordT>] {
+        &self.bounds
+    }
+
     pub fn volume(&self) -> CoordT {
         let init_volume = self
             .bounds
@@ -118,6 +243,370 @@ impl<CoordT: CoordTrait> MBR<CoordT> {
             .skip(1)
             .fold(init_volume, |acc, bounds| acc * bounds.length())
     }
+
+    /// Sum of the axes lengths.
+    pub fn margin(&self) -> CoordT {
+        self.bounds
+            .iter()
+            .fold(CoordT::zero(), |acc, bounds| acc + bounds.length())
+    }
+
+    /// Measure of the MBR boundary: `2 * sum(product of the other axes lengths)` over all axes.
+    ///
+    /// It is the perimeter of a 2D rectangle and the surface area of a 3D box.
+    /// A 1D MBR has two boundary points, so its surface area is `2`.
+    pub fn surface_area(&self) -> CoordT {
+        let two = CoordT::one() + CoordT::one();
+
+        let sum = (0..self.dimension()).fold(CoordT::zero(), |sum, axis| {
+            let face = self
+                .bounds
+                .iter()
+                .enumerate()
+                .filter(|&(other_axis, _)| other_axis != axis)
+                .fold(CoordT::one(), |acc, (_, bounds)| acc * bounds.length());
+
+            sum + face
+        });
+
+        two * sum
+    }
+
+    /// All `2^dimension` corners of the MBR: every combination of per-axis `min`/`max`.
+    ///
+    /// The number of corners grows exponentially,
+    /// so this fn panics if the dimension is greater than `MAX_CORNER_POINTS_DIMENSION`.
+    pub fn corner_points(&self) -> Vec<Vec<CoordT>> {
+        let dimension = self.dimension();
+        assert!(
+            dimension <= MAX_CORNER_POINTS_DIMENSION,
+            "too many corner points: dimension = {}",
+            dimension
+        );
+
+        if self.is_undefined() {
+            return vec![];
+        }
+
+        (0..1usize << dimension)
+            .map(|corner| {
+                self.bounds
+                    .iter()
+                    .enumerate()
+                    .map(|(axis, bounds)| {
+                        if corner & (1 << axis) == 0 {
+                            bounds.min.clone()
+                        } else {
+                            bounds.max.clone()
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Pads every axis by `delta`: it is subtracted from `min` and added to `max`.
+    ///
+    /// A negative `delta` shrinks the MBR.
+    /// It is the caller's responsibility to keep `min <= max` in such a case
+    /// (see `Bounds::new_unchecked`).
+    pub fn expand_by(&mut self, delta: &CoordT) {
+        for bounds in self.bounds.iter_mut() {
+            bounds.min = bounds.min.clone() - delta.clone();
+            bounds.max = bounds.max.clone() + delta.clone();
+        }
+    }
+
+    /// Cuts the MBR into two parts along the `axis` at the `value`.
+    ///
+    /// The first part has `[min; value]` bounds on the `axis`, the second one has `[value; max]`.
+    /// Other axes are the same as in the original MBR.
+    pub fn split_at(&self, axis: usize, value: CoordT) -> (MBR<CoordT>, MBR<CoordT>) {
+        let bounds = self.bounds(axis);
+        assert!(
+            bounds.is_in_bound(&value),
+            "split value {:?} is out of the axis bounds [{:?}; {:?}]",
+            value,
+            bounds.min,
+            bounds.max
+        );
+
+        let mut lhs = self.clone();
+        let mut rhs = self.clone();
+
+        lhs.bounds[axis] = Bounds::new(bounds.min.clone(), value.clone());
+        rhs.bounds[axis] = Bounds::new(value, bounds.max.clone());
+
+        (lhs, rhs)
+    }
+
+    /// Scales the MBR about its center by the `factor`: `center ± (length / 2) * factor` per axis.
+    ///
+    /// The computation is performed in `f64`.
+    /// For integer coordinates the new bounds are rounded outwards:
+    /// `min` is rounded down and `max` is rounded up.
+    pub fn scale(&mut self, factor: f64) {
+        assert!(factor >= 0.0, "scale factor must be non-negative");
+
+        let to_f64 = |value: &CoordT| {
+            value
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64")
+        };
+
+        let from_f64 = |value: f64| {
+            <CoordT as NumCast>::from(value).expect("f64 is expected to be convertible to CoordT")
+        };
+
+        let is_integer = from_f64(0.5) == CoordT::zero();
+
+        for bounds in self.bounds.iter_mut() {
+            let min = to_f64(&bounds.min);
+            let max = to_f64(&bounds.max);
+
+            let center = (min + max) / 2.0;
+            let half_length = (max - min) / 2.0 * factor;
+
+            let mut new_min = center - half_length;
+            let mut new_max = center + half_length;
+
+            if is_integer {
+                new_min = new_min.floor();
+                new_max = new_max.ceil();
+            }
+
+            bounds.min = from_f64(new_min);
+            bounds.max = from_f64(new_max);
+        }
+    }
+
+    /// Shifts the MBR: `offset[i]` is added to both bounds of the axis `i`.
+    pub fn translate(&mut self, offset: &[CoordT]) {
+        assert_eq!(offset.len(), self.dimension(), "unexpected dimension");
+
+        for (bounds, delta) in self.bounds.iter_mut().zip(offset.iter()) {
+            bounds.min = bounds.min.clone() + delta.clone();
+            bounds.max = bounds.max.clone() + delta.clone();
+        }
+    }
+
+    /// Checks if every bound of `self` differs from the corresponding bound of `other`
+    /// by no more than `epsilon`.
+    ///
+    /// MBRs of different dimensions are never equal.
+    pub fn approx_eq(&self, other: &MBR<CoordT>, epsilon: CoordT) -> bool {
+        let is_close = |lhs: &CoordT, rhs: &CoordT| {
+            let diff = if lhs > rhs {
+                lhs.clone() - rhs.clone()
+            } else {
+                rhs.clone() - lhs.clone()
+            };
+
+            diff <= epsilon
+        };
+
+        self.dimension() == other.dimension()
+            && self
+                .bounds
+                .iter()
+                .zip(other.bounds.iter())
+                .all(|(lhs, rhs)| is_close(&lhs.min, &rhs.min) && is_close(&lhs.max, &rhs.max))
+    }
+
+    /// Checks if `other` is fully inside `self` (bounds are inclusive).
+    ///
+    /// If dimensions differ, only the first `min(dimension)` axes are compared, like in `intersects`.
+    pub fn contains(&self, other: &MBR<CoordT>) -> bool {
+        if self.is_undefined() {
+            return false;
+        }
+
+        self.bounds
+            .iter()
+            .zip(other.bounds.iter())
+            .all(|(self_bound, other_bound)| self_bound.contains(other_bound))
+    }
+
+    /// Checks if the `point` is inside the MBR (bounds are inclusive, like in `Bounds::is_in_bound`).
+    ///
+    /// Returns `false` if the point dimension differs from the MBR one.
+    pub fn contains_point(&self, point: &[CoordT]) -> bool {
+        if point.len() != self.dimension() {
+            return false;
+        }
+
+        self.bounds
+            .iter()
+            .zip(point.iter())
+            .all(|(bounds, value)| bounds.is_in_bound(value))
+    }
+
+    /// Checks if the ray `origin + t * dir` (`t >= 0`) crosses the MBR (bounds are inclusive).
+    ///
+    /// Uses the slab method. A zero `dir` component means the ray is parallel to that axis,
+    /// so it crosses the MBR only if the `origin` is within the axis bounds.
+    pub fn intersects_ray(&self, origin: &[CoordT], dir: &[CoordT]) -> bool {
+        assert_eq!(origin.len(), self.dimension(), "unexpected dimension");
+        assert_eq!(dir.len(), self.dimension(), "unexpected dimension");
+
+        let to_f64 = |value: &CoordT| {
+            value
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64")
+        };
+
+        let mut t_enter = 0.0f64;
+        let mut t_exit = f64::INFINITY;
+
+        for ((bounds, origin), dir) in self.bounds.iter().zip(origin.iter()).zip(dir.iter()) {
+            if dir.is_zero() {
+                if !bounds.is_in_bound(origin) {
+                    return false;
+                }
+
+                continue;
+            }
+
+            let origin = to_f64(origin);
+            let dir = to_f64(dir);
+
+            let t_min = (to_f64(&bounds.min) - origin) / dir;
+            let t_max = (to_f64(&bounds.max) - origin) / dir;
+
+            t_enter = t_enter.max(t_min.min(t_max));
+            t_exit = t_exit.min(t_min.max(t_max));
+
+            if t_enter > t_exit {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Moves the `point` to the nearest point inside the MBR.
+    ///
+    /// A point which is already inside the MBR stays untouched.
+    pub fn clamp_point(&self, point: &mut [CoordT]) {
+        assert_eq!(point.len(), self.dimension(), "unexpected dimension");
+
+        for (bounds, value) in self.bounds.iter().zip(point.iter_mut()) {
+            if *value < bounds.min {
+                *value = bounds.min.clone();
+            } else if *value > bounds.max {
+                *value = bounds.max.clone();
+            }
+        }
+    }
+
+    /// Like `volume`, but the edge lengths are multiplied as `f64`, so it can't overflow.
+    pub fn volume_f64(&self) -> f64 {
+        if self.is_undefined() {
+            return 0.0;
+        }
+
+        self.bounds
+            .iter()
+            .map(|bounds| {
+                bounds
+                    .length()
+                    .to_f64()
+                    .expect("CoordT is expected to be convertible to f64")
+            })
+            .product()
+    }
+
+    /// `volume_f64` and, if `margin_tie_break` is set, `margin` to compare the MBRs of the same volume.
+    pub fn cost(&self, margin_tie_break: bool) -> Cost {
+        // Summed as `f64` like `volume_f64`, so it can't overflow
+        let margin = if margin_tie_break {
+            self.bounds
+                .iter()
+                .map(|bounds| {
+                    bounds
+                        .length()
+                        .to_f64()
+                        .expect("CoordT is expected to be convertible to f64")
+                })
+                .sum()
+        } else {
+            0.0
+        };
+
+        Cost {
+            volume: self.volume_f64(),
+            margin,
+        }
+    }
+
+    /// Geometric center of the MBR: the midpoint of every axis (see `Bounds::midpoint`).
+    ///
+    /// Returns an empty `Vec` for an undefined MBR.
+    pub fn center(&self) -> Vec<CoordT> {
+        self.bounds.iter().map(Bounds::midpoint).collect()
+    }
+
+    /// Ratio of the longest axis length to the shortest one.
+    ///
+    /// # Notes
+    /// * An MBR with a zero-length axis (and a non-zero one) has an infinite aspect ratio.
+    /// * A point MBR (all axes have zero length) has a `NaN` aspect ratio.
+    /// * An undefined MBR has a `NaN` aspect ratio.
+    pub fn aspect_ratio(&self) -> f64 {
+        let (shortest, longest) = self
+            .bounds
+            .iter()
+            .map(|bounds| {
+                bounds
+                    .length()
+                    .to_f64()
+                    .expect("CoordT is expected to be convertible to f64")
+            })
+            .fold((f64::NAN, f64::NAN), |(shortest, longest), length| {
+                (shortest.min(length), longest.max(length))
+            });
+
+        longest / shortest
+    }
+
+    /// Well-Known Text representation of the MBR.
+    ///
+    /// * 2D: `POLYGON((...))` with the four corners, the first corner closes the ring.
+    /// * 3D: `POLYGON Z((...))` with the rectangle going through the min and the max corners,
+    ///   so its 3D extent is the MBR.
+    /// * Other dimensions and an undefined MBR: `POLYGON EMPTY`.
+    #[cfg(feature = "wkt")]
+    pub fn to_wkt(&self) -> String {
+        let to_f64 = |value: &CoordT| {
+            value
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64")
+        };
+
+        let mins = self.bounds.iter().map(|bounds| to_f64(&bounds.min));
+        let maxs = self.bounds.iter().map(|bounds| to_f64(&bounds.max));
+        let (mins, maxs) = (mins.collect::<Vec<_>>(), maxs.collect::<Vec<_>>());
+
+        match self.dimension() {
+            2 => format!(
+                "POLYGON(({x0} {y0}, {x1} {y0}, {x1} {y1}, {x0} {y1}, {x0} {y0}))",
+                x0 = mins[0],
+                y0 = mins[1],
+                x1 = maxs[0],
+                y1 = maxs[1],
+            ),
+            3 => format!(
+                "POLYGON Z(({x0} {y0} {z0}, {x1} {y0} {z0}, {x1} {y1} {z1}, {x0} {y1} {z1}, {x0} {y0} {z0}))",
+                x0 = mins[0],
+                y0 = mins[1],
+                z0 = mins[2],
+                x1 = maxs[0],
+                y1 = maxs[1],
+                z1 = maxs[2],
+            ),
+            _ => "POLYGON EMPTY".to_string(),
+        }
+    }
 }
 
 impl<CoordT: CoordTrait> Clone for MBR<CoordT> {
@@ -128,6 +617,42 @@ impl<CoordT: CoordTrait> Clone for MBR<CoordT> {
     }
 }
 
+/// Makes an MBR from `(min, max)` pairs, one pair per axis.
+impl<CoordT: CoordTrait, const N: usize> From<[(CoordT, CoordT); N]> for MBR<CoordT> {
+    fn from(bounds: [(CoordT, CoordT); N]) -> Self {
+        debug_assert!(N > 0, "MBR can't be zero-dimension");
+
+        Self::new(
+            IntoIterator::into_iter(bounds)
+                .map(|(min, max)| Bounds::new(min, max))
+                .collect(),
+        )
+    }
+}
+
+/// `geo::Rect` is a 2D MBR: `min()` and `max()` are mapped onto the X and Y bounds.
+#[cfg(feature = "geo")]
+impl<CoordT: CoordTrait + CoordNum> From<Rect<CoordT>> for MBR<CoordT> {
+    fn from(rect: Rect<CoordT>) -> Self {
+        let (min, max) = (rect.min(), rect.max());
+
+        Self::new(vec![Bounds::new(min.x, max.x), Bounds::new(min.y, max.y)])
+    }
+}
+
+/// # Panics
+/// Panics if the MBR is not 2D.
+#[cfg(feature = "geo")]
+impl<CoordT: CoordTrait + CoordNum> From<MBR<CoordT>> for Rect<CoordT> {
+    fn from(mbr: MBR<CoordT>) -> Self {
+        assert_eq!(mbr.dimension(), 2, "unexpected dimension");
+
+        let (x, y) = (mbr.bounds(0), mbr.bounds(1));
+
+        Rect::new(Coord { x: x.min, y: y.min }, Coord { x: x.max, y: y.max })
+    }
+}
+
 impl<CoordT: PartialEq> PartialEq for MBR<CoordT> {
     fn eq(&self, rhs: &Self) -> bool {
         self.bounds == rhs.bounds
@@ -151,77 +676,152 @@ impl<CoordT: CoordTrait> Display for MBR<CoordT> {
     }
 }
 
-pub fn intersects<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> bool {
-    if lhs as *const _ == rhs as *const _ {
-        return true;
+/// Serialized as a `[min, max]` pair.
+#[cfg(feature = "serde")]
+impl<CoordT: Serialize> Serialize for Bounds<CoordT> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.min, &self.max).serialize(serializer)
     }
+}
 
-    let min_dim = std::cmp::min(lhs.dimension(), rhs.dimension());
+/// Rejects bounds with `min > max`.
+#[cfg(feature = "serde")]
+impl<'de, CoordT: CoordTrait + Deserialize<'de>> Deserialize<'de> for Bounds<CoordT> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (min, max) = <(CoordT, CoordT)>::deserialize(deserializer)?;
 
-    let mut intersected_axis = 0usize;
-    for (self_bound, other_bound) in lhs.bounds.iter().zip(rhs.bounds.iter()) {
-        if self_bound.is_in_bound(&other_bound.min)
-            || self_bound.is_in_bound(&other_bound.max)
-            || other_bound.is_in_bound(&self_bound.min)
-        {
-            intersected_axis += 1;
+        if !min.le(&max) {
+            return Err(D::Error::custom(
+                "a min bound must be less than a max bound",
+            ));
         }
+
+        Ok(unsafe { Self::new_unchecked(min, max) })
     }
+}
 
-    intersected_axis == min_dim
+/// Serialized as a list of `[min, max]` pairs, one pair per axis.
+///
+/// An undefined MBR is serialized as an empty list, which can't be deserialized.
+#[cfg(feature = "serde")]
+impl<CoordT: Serialize> Serialize for MBR<CoordT> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.bounds.serialize(serializer)
+    }
 }
 
-pub fn common_mbr<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> MBR<CoordT> {
-    if lhs as *const _ == rhs as *const _ {
-        return lhs.clone();
+/// Rejects an empty list of bounds.
+#[cfg(feature = "serde")]
+impl<'de, CoordT: CoordTrait + Deserialize<'de>> Deserialize<'de> for MBR<CoordT> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bounds = Vec::<Bounds<CoordT>>::deserialize(deserializer)?;
+
+        if bounds.is_empty() {
+            return Err(D::Error::custom("MBR can't be zero-dimension"));
+        }
+
+        Ok(unsafe { Self::new_unchecked(bounds) })
     }
+}
 
-    let lhs_dim = lhs.dimension();
-    let rhs_dim = rhs.dimension();
+/// Serde functions for the MBRs which can be undefined, like the ones of the empty tree nodes.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_maybe_undefined {
+    use super::*;
+
+    pub fn serialize<CoordT, S>(mbr: &MBR<CoordT>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        CoordT: Serialize,
+        S: Serializer,
+    {
+        mbr.bounds.serialize(serializer)
+    }
 
-    let lhs_bounds;
-    let rhs_bounds;
+    pub fn deserialize<'de, CoordT, D>(deserializer: D) -> Result<MBR<CoordT>, D::Error>
+    where
+        CoordT: CoordTrait + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let bounds = Vec::<Bounds<CoordT>>::deserialize(deserializer)?;
 
-    let mut bounds_ext = MaybeUninit::<Vec<Bounds<CoordT>>>::uninit();
+        Ok(unsafe { MBR::new_unchecked(bounds) })
+    }
+}
 
-    match lhs_dim.cmp(&rhs_dim) {
-        Ordering::Equal => {
-            lhs_bounds = &lhs.bounds;
-            rhs_bounds = &rhs.bounds;
+/// With the `simd` feature the `f32` and `f64` MBRs are checked by the `simd` module.
+pub fn intersects<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> bool {
+    #[cfg(feature = "simd")]
+    {
+        let (lhs_any, rhs_any): (&dyn Any, &dyn Any) = (lhs, rhs);
+
+        if let (Some(lhs), Some(rhs)) = (
+            lhs_any.downcast_ref::<MBR<f32>>(),
+            rhs_any.downcast_ref::<MBR<f32>>(),
+        ) {
+            return super::simd::intersects_f32(lhs, rhs);
         }
-        Ordering::Less => {
-            unsafe {
-                bounds_ext
-                    .as_mut_ptr()
-                    .write(extend_bounds(&lhs.bounds, &rhs.bounds))
-            }
 
-            lhs_bounds = unsafe { &*bounds_ext.as_ptr() };
-            rhs_bounds = &rhs.bounds;
+        if let (Some(lhs), Some(rhs)) = (
+            lhs_any.downcast_ref::<MBR<f64>>(),
+            rhs_any.downcast_ref::<MBR<f64>>(),
+        ) {
+            return super::simd::intersects_f64(lhs, rhs);
         }
-        Ordering::Greater => {
-            unsafe {
-                bounds_ext
-                    .as_mut_ptr()
-                    .write(extend_bounds(&rhs.bounds, &lhs.bounds))
-            }
+    }
 
-            lhs_bounds = &lhs.bounds;
-            rhs_bounds = unsafe { &*bounds_ext.as_ptr() };
-        }
+    intersects_scalar(lhs, rhs)
+}
+
+/// `intersects` without the SIMD dispatch.
+pub(crate) fn intersects_scalar<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> bool {
+    if lhs as *const _ == rhs as *const _ {
+        return true;
+    }
+
+    lhs.bounds
+        .iter()
+        .zip(rhs.bounds.iter())
+        .all(|(self_bound, other_bound)| {
+            self_bound.min <= other_bound.max && other_bound.min <= self_bound.max
+        })
+}
+
+/// Checks if interiors of two MBRs intersect, i.e. they overlap by a positive length on every axis.
+///
+/// Unlike `intersects`, MBRs that only touch each other at a boundary don't intersect strictly.
+/// Axes and undefined MBRs are handled like in `intersects`.
+pub fn intersects_strict<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> bool {
+    lhs.bounds
+        .iter()
+        .zip(rhs.bounds.iter())
+        .all(|(lhs, rhs)| lhs.min < rhs.max && rhs.min < lhs.max)
+}
+
+/// Overlap region of two MBRs.
+///
+/// Returns `None` if MBRs don't intersect or if any of them is undefined.
+/// Touching MBRs have a zero-volume intersection.
+/// If dimensions differ, the result has `min(dimension)` axes.
+pub fn intersection<CoordT: CoordTrait>(
+    lhs: &MBR<CoordT>,
+    rhs: &MBR<CoordT>,
+) -> Option<MBR<CoordT>> {
+    if lhs.is_undefined() || rhs.is_undefined() || !intersects(lhs, rhs) {
+        return None;
     }
 
-    let bounds = lhs_bounds
+    let bounds = lhs
+        .bounds
         .iter()
-        .zip(rhs_bounds)
+        .zip(rhs.bounds.iter())
         .map(|(lhs, rhs)| {
-            let min = if lhs.min < rhs.min {
+            let min = if lhs.min > rhs.min {
                 lhs.min.clone()
             } else {
                 rhs.min.clone()
             };
 
-            let max = if lhs.max > rhs.max {
+            let max = if lhs.max < rhs.max {
                 lhs.max.clone()
             } else {
                 rhs.max.clone()
@@ -231,42 +831,86 @@ pub fn common_mbr<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> M
         })
         .collect::<Vec<_>>();
 
-    unsafe { MBR::new_unchecked(bounds) }
+    Some(MBR::new(bounds))
 }
 
-fn extend_bounds<CoordT: CoordTrait>(
-    src_bounds: &[Bounds<CoordT>],
-    target_bounds: &[Bounds<CoordT>],
-) -> Vec<Bounds<CoordT>> {
-    debug_assert!(!target_bounds.is_empty());
-    debug_assert!(src_bounds.len() < target_bounds.len());
-
-    let bounds_diff = target_bounds.len() - src_bounds.len();
-    let mut bounds = src_bounds.to_vec();
-
-    let Bounds { min, max } = target_bounds[0].clone();
+/// Volume of the overlap region of two MBRs (see `intersection`).
+///
+/// Returns zero if MBRs don't intersect.
+pub fn overlap_volume<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> CoordT {
+    intersection(lhs, rhs)
+        .map(|overlap| overlap.volume())
+        .unwrap_or_else(CoordT::zero)
+}
 
-    let (min, max) = target_bounds
+/// Minimum Euclidean distance between two MBRs.
+///
+/// Returns `0.0` if MBRs intersect.
+/// If dimensions differ, only the first `min(dimension)` axes are compared.
+pub fn min_distance<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> f64 {
+    lhs.bounds
         .iter()
-        .fold((min, max), |(mut min, mut max), bounds| {
-            if bounds.min.lt(&min) {
-                min = bounds.min.clone();
-            }
+        .zip(rhs.bounds.iter())
+        .map(|(lhs, rhs)| {
+            let gap = if lhs.max < rhs.min {
+                rhs.min.clone() - lhs.max.clone()
+            } else if rhs.max < lhs.min {
+                lhs.min.clone() - rhs.max.clone()
+            } else {
+                return 0.0;
+            };
 
-            if bounds.max.gt(&max) {
-                max = bounds.max.clone();
-            }
+            let gap = gap
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64");
 
-            (min, max)
-        });
+            gap * gap
+        })
+        .sum::<f64>()
+        .sqrt()
+}
 
-    // This bounds are invalid and will be replaced by common_mbr fn.
-    let bounds_ext = unsafe { Bounds::new_unchecked(max, min) };
-    for _ in 0..bounds_diff {
-        bounds.push(bounds_ext.clone());
+/// Minimum bounding rectangle of both MBRs.
+///
+/// If dimensions differ, the MBR with the lesser dimension is treated as
+/// unbounded on the axes it doesn't have: the result has the greater dimension
+/// and takes these extra axes from the MBR with the greater dimension as is.
+pub fn common_mbr<CoordT: CoordTrait>(lhs: &MBR<CoordT>, rhs: &MBR<CoordT>) -> MBR<CoordT> {
+    if lhs as *const _ == rhs as *const _ {
+        return lhs.clone();
     }
 
-    bounds
+    let (longer, shorter) = if lhs.dimension() >= rhs.dimension() {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+
+    let bounds = longer
+        .bounds
+        .iter()
+        .enumerate()
+        .map(|(axis, longer)| match shorter.bounds.get(axis) {
+            Some(shorter) => {
+                let min = if longer.min < shorter.min {
+                    longer.min.clone()
+                } else {
+                    shorter.min.clone()
+                };
+
+                let max = if longer.max > shorter.max {
+                    longer.max.clone()
+                } else {
+                    shorter.max.clone()
+                };
+
+                Bounds::new(min, max)
+            }
+            None => longer.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    unsafe { MBR::new_unchecked(bounds) }
 }
 
 pub fn common_mbr_from_iter<'a, I, CoordT>(iter: I) -> MBR<CoordT>
@@ -285,6 +929,31 @@ pub fn mbr_delta<CoordT: CoordTrait>(src: &MBR<CoordT>, addition: &MBR<CoordT>)
     common.volume() - src.volume()
 }
 
+/// Like `mbr_delta`, but measures the enlargement by `volume_f64`.
+pub fn mbr_delta_f64<CoordT: CoordTrait>(src: &MBR<CoordT>, addition: &MBR<CoordT>) -> f64 {
+    let common = common_mbr(src, addition);
+
+    common.volume_f64() - src.volume_f64()
+}
+
+/// Like `mbr_delta`, but measures the enlargement by `MBR::cost`.
+pub fn cost_delta<CoordT: CoordTrait>(
+    src: &MBR<CoordT>,
+    addition: &MBR<CoordT>,
+    margin_tie_break: bool,
+) -> Cost {
+    let common = common_mbr(src, addition);
+
+    common.cost(margin_tie_break) - src.cost(margin_tie_break)
+}
+
+/// Like `mbr_delta`, but measures the enlargement by `margin` instead of `volume`.
+pub fn margin_delta<CoordT: CoordTrait>(src: &MBR<CoordT>, addition: &MBR<CoordT>) -> CoordT {
+    let common = common_mbr(src, addition);
+
+    common.margin() - src.margin()
+}
+
 #[cfg(test)]
 mod test {
     use crate::{mbr, mbr::MBR};
@@ -313,6 +982,63 @@ mod test {
         assert_eq!(mbr.bounds[1].max, -1);
     }
 
+    #[test]
+    fn test_new_point_mbr() {
+        let point = mbr![X = 3, Y = 7];
+
+        assert_eq!(
+            point,
+            mbr! {
+                X = [3; 3],
+                Y = [7; 7]
+            }
+        );
+        assert_eq!(point.volume(), 0);
+
+        let x = 5;
+        let point = mbr![X = x - 1];
+
+        assert_eq!(point, mbr![X = [4; 4]]);
+    }
+
+    #[test]
+    fn test_new_mixed_mbr() {
+        let mbr = mbr! {
+            X = 3,
+            Y = [0; 10],
+            Z = -2
+        };
+
+        assert_eq!(mbr.dimension(), 3);
+        assert_eq!(mbr.bounds(0).min, 3);
+        assert_eq!(mbr.bounds(0).max, 3);
+        assert_eq!(mbr.bounds(1).min, 0);
+        assert_eq!(mbr.bounds(1).max, 10);
+        assert_eq!(mbr.bounds(2).min, -2);
+        assert_eq!(mbr.bounds(2).max, -2);
+    }
+
+    #[test]
+    fn test_mbr_from_bounds_iter() {
+        let dimension = 3;
+        let mbr = MBR::from_bounds_iter((0..dimension).map(|axis| (axis * 10, axis * 10 + 5)));
+
+        assert_eq!(
+            mbr,
+            mbr! {
+                X = [ 0;  5],
+                Y = [10; 15],
+                Z = [20; 25]
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_panic_mbr_from_empty_bounds_iter() {
+        MBR::<i32>::from_bounds_iter(vec![]);
+    }
+
     #[test]
     fn test_undefined() {
         let undefined = unsafe { MBR::<u32>::undefined() };
@@ -346,48 +1072,727 @@ mod test {
         assert_eq!(undefined.dimension(), 0);
     }
 
+    #[test]
+    fn test_mbr_iter_bounds() {
+        let mbr = mbr! {
+            X = [  0; 10],
+            Y = [-10; -1],
+            Z = [  3;  3]
+        };
+
+        let lengths = mbr
+            .iter_bounds()
+            .map(|bounds| bounds.length())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lengths, vec![10, 9, 0]);
+
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        assert_eq!(undefined.iter_bounds().count(), 0);
+    }
+
     #[test]
     #[should_panic]
     fn test_panic_mbr_bounds() {
         let mbr = mbr! {
-            X = [0; 10]
+            X = [0; 10]
+        };
+
+        mbr.bounds(mbr.dimension());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_panic_undefined_bounds() {
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        undefined.bounds(0);
+    }
+
+    #[test]
+    fn test_bounds_length() {
+        let bounds = mbr::Bounds::new(-4, 4);
+        assert_eq!(bounds.length(), 8);
+    }
+
+    #[test]
+    fn test_bounds_midpoint() {
+        assert_eq!(mbr::Bounds::new(-4, 4).midpoint(), 0);
+        assert_eq!(mbr::Bounds::new(2, 8).midpoint(), 5);
+        assert_eq!(mbr::Bounds::new(-3, 0).midpoint(), -2);
+        assert_eq!(mbr::Bounds::new(1.0, 2.0).midpoint(), 1.5);
+    }
+
+    #[test]
+    fn test_bounds_contains() {
+        let bounds = mbr::Bounds::new(0, 10);
+
+        // Disjoint
+        assert!(!bounds.contains(&mbr::Bounds::new(11, 20)));
+        assert!(!mbr::Bounds::new(11, 20).contains(&bounds));
+
+        // Touching
+        assert!(!bounds.contains(&mbr::Bounds::new(10, 20)));
+        assert!(!mbr::Bounds::new(10, 20).contains(&bounds));
+
+        // Nested
+        assert!(bounds.contains(&mbr::Bounds::new(2, 5)));
+        assert!(bounds.contains(&mbr::Bounds::new(0, 10)));
+        assert!(bounds.contains(&mbr::Bounds::new(10, 10)));
+        assert!(!mbr::Bounds::new(2, 5).contains(&bounds));
+
+        // Partially overlapping
+        assert!(!bounds.contains(&mbr::Bounds::new(5, 15)));
+        assert!(!mbr::Bounds::new(5, 15).contains(&bounds));
+    }
+
+    #[test]
+    fn test_bounds_overlap_length() {
+        let bounds = mbr::Bounds::new(0, 10);
+
+        // Disjoint
+        assert_eq!(bounds.overlap_length(&mbr::Bounds::new(11, 20)), 0);
+        assert_eq!(mbr::Bounds::new(11, 20).overlap_length(&bounds), 0);
+
+        // Touching
+        assert_eq!(bounds.overlap_length(&mbr::Bounds::new(10, 20)), 0);
+        assert_eq!(mbr::Bounds::new(10, 20).overlap_length(&bounds), 0);
+
+        // Nested
+        assert_eq!(bounds.overlap_length(&mbr::Bounds::new(2, 5)), 3);
+        assert_eq!(mbr::Bounds::new(2, 5).overlap_length(&bounds), 3);
+
+        // Partially overlapping
+        assert_eq!(bounds.overlap_length(&mbr::Bounds::new(5, 15)), 5);
+        assert_eq!(mbr::Bounds::new(-5, 5).overlap_length(&bounds), 5);
+
+        // Unsigned coordinates must not underflow
+        assert_eq!(
+            mbr::Bounds::new(0u32, 1).overlap_length(&mbr::Bounds::new(5, 6)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_mbr_volume() {
+        let mbr = mbr! {
+            X = [-4; 4]
+        };
+
+        assert_eq!(mbr.volume(), 8);
+
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7]
+        };
+
+        assert_eq!(mbr.volume(), 32);
+
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        assert_eq!(undefined.volume(), 0);
+    }
+
+    #[test]
+    fn test_mbr_approx_eq() {
+        let mbr = mbr! {
+            X = [0.0; 10.0],
+            Y = [-3.0; 8.0]
+        };
+
+        let close_mbr = mbr! {
+            X = [1e-9; 10.0],
+            Y = [-3.0; 8.0 - 1e-9]
+        };
+
+        assert!(mbr.approx_eq(&close_mbr, 1e-6));
+        assert!(close_mbr.approx_eq(&mbr, 1e-6));
+        assert!(!mbr.approx_eq(&close_mbr, 1e-12));
+        assert!(!close_mbr.approx_eq(&mbr, 1e-12));
+        assert!(mbr.approx_eq(&mbr, 0.0));
+
+        let mbr_1d = mbr! {
+            X = [0.0; 10.0]
+        };
+
+        assert!(!mbr.approx_eq(&mbr_1d, 1.0));
+        assert!(!mbr_1d.approx_eq(&mbr, 1.0));
+    }
+
+    #[test]
+    fn test_mbr_contains() {
+        let outer = mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        };
+
+        let inner = mbr! {
+            X = [2; 5],
+            Y = [3; 9]
+        };
+
+        assert!(outer.contains(&inner));
+        assert!(!inner.contains(&outer));
+        assert!(outer.contains(&outer));
+
+        let touching_inner = mbr! {
+            X = [0; 10],
+            Y = [5; 10]
+        };
+
+        assert!(outer.contains(&touching_inner));
+
+        let partial = mbr! {
+            X = [5; 15],
+            Y = [2;  8]
+        };
+
+        assert!(!outer.contains(&partial));
+        assert!(!partial.contains(&outer));
+
+        let disjoint = mbr! {
+            X = [20; 30],
+            Y = [20; 30]
+        };
+
+        assert!(!outer.contains(&disjoint));
+
+        let inner_1d = mbr! {
+            X = [1; 9]
+        };
+
+        assert!(outer.contains(&inner_1d));
+        assert!(!inner_1d.contains(&outer));
+    }
+
+    #[test]
+    fn test_mbr_contains_undefined() {
+        let undefined = unsafe { MBR::undefined() };
+        let undefined_1 = unsafe { MBR::undefined() };
+
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        assert!(mbr.contains(&undefined));
+        assert!(!undefined.contains(&mbr));
+        assert!(!undefined.contains(&undefined_1));
+    }
+
+    #[test]
+    fn test_mbr_contains_point() {
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [0; 10],
+            Z = [-5; 5]
+        };
+
+        assert!(mbr.contains_point(&[5, 5, 0]));
+        assert!(!mbr.contains_point(&[5, 11, 0]));
+        assert!(!mbr.contains_point(&[-1, 5, 0]));
+
+        // Boundaries
+        assert!(mbr.contains_point(&[0, 0, -5]));
+        assert!(mbr.contains_point(&[10, 10, 5]));
+        assert!(mbr.contains_point(&[0, 10, 0]));
+        assert!(!mbr.contains_point(&[10, 10, 6]));
+
+        // Dimension mismatch
+        assert!(!mbr.contains_point(&[5, 5]));
+        assert!(!mbr.contains_point(&[5, 5, 0, 0]));
+
+        let undefined = unsafe { MBR::<i32>::undefined() };
+
+        assert!(!undefined.contains_point(&[0]));
+    }
+
+    #[test]
+    fn test_mbr_intersects_ray() {
+        let mbr = mbr! {
+            X = [10; 20],
+            Y = [10; 20]
+        };
+
+        assert!(mbr.intersects_ray(&[0, 15], &[1, 0]));
+        assert!(mbr.intersects_ray(&[0, 0], &[1, 1]));
+        assert!(mbr.intersects_ray(&[0, 0], &[2, 1]));
+        assert!(mbr.intersects_ray(&[15, 15], &[-1, 3]));
+        assert!(!mbr.intersects_ray(&[0, 15], &[-1, 0]));
+        assert!(!mbr.intersects_ray(&[0, 0], &[1, 3]));
+
+        // Parallel to an axis
+        assert!(mbr.intersects_ray(&[15, 0], &[0, 1]));
+        assert!(!mbr.intersects_ray(&[25, 0], &[0, 1]));
+
+        // Boundaries
+        assert!(mbr.intersects_ray(&[0, 20], &[1, 0]));
+        assert!(mbr.intersects_ray(&[0, 30], &[1, -1]));
+        assert!(!mbr.intersects_ray(&[0, 21], &[1, 0]));
+
+        // Zero direction
+        assert!(mbr.intersects_ray(&[12, 12], &[0, 0]));
+        assert!(!mbr.intersects_ray(&[0, 0], &[0, 0]));
+
+        let mbr = mbr! {
+            X = [0.0; 1.0],
+            Y = [0.0; 1.0],
+            Z = [0.0; 1.0]
+        };
+
+        assert!(mbr.intersects_ray(&[-1.0, -1.0, -1.0], &[1.0, 1.0, 1.0]));
+        assert!(mbr.intersects_ray(&[0.5, 0.5, 5.0], &[0.0, 0.0, -1.0]));
+        assert!(!mbr.intersects_ray(&[0.5, 0.5, 5.0], &[0.0, 0.1, 1.0]));
+    }
+
+    #[test]
+    fn test_mbr_clamp_point() {
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [0; 10],
+            Z = [-5; 5]
+        };
+
+        let mut point = [15, -3, 0];
+        mbr.clamp_point(&mut point);
+
+        assert_eq!(point, [10, 0, 0]);
+
+        let mut point = [-1, 20, 6];
+        mbr.clamp_point(&mut point);
+
+        assert_eq!(point, [0, 10, 5]);
+
+        let mut point = [3, 10, -5];
+        mbr.clamp_point(&mut point);
+
+        assert_eq!(point, [3, 10, -5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_panic_mbr_clamp_point() {
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        };
+
+        mbr.clamp_point(&mut [0]);
+    }
+
+    #[test]
+    fn test_mbr_center() {
+        let mbr = mbr! {
+            X = [ 0; 10],
+            Y = [-6; -2]
+        };
+
+        assert_eq!(mbr.center(), vec![5, -4]);
+
+        // Integer coordinates are rounded towards the min bound
+        let mbr = mbr! {
+            X = [-3; 0],
+            Y = [ 1; 4]
+        };
+
+        assert_eq!(mbr.center(), vec![-2, 2]);
+
+        let mbr = mbr! {
+            X = [0.0; 1.0],
+            Y = [2.0; 5.0]
+        };
+
+        assert_eq!(mbr.center(), vec![0.5, 3.5]);
+
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        assert!(undefined.center().is_empty());
+    }
+
+    #[test]
+    fn test_mbr_volume_f64() {
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7]
+        };
+
+        assert_eq!(mbr.volume_f64(), 32.0);
+
+        let length = i32::MAX / 3;
+        let mbr = mbr! {
+            X = [0; length],
+            Y = [0; length],
+            Z = [0; length]
+        };
+
+        assert_eq!(mbr.volume_f64(), (length as f64).powi(3));
+
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        assert_eq!(undefined.volume_f64(), 0.0);
+    }
+
+    #[test]
+    fn test_mbr_cost() {
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7]
+        };
+
+        assert_eq!(
+            mbr.cost(true),
+            mbr::Cost {
+                volume: 32.0,
+                margin: 12.0
+            }
+        );
+        assert_eq!(
+            mbr.cost(false),
+            mbr::Cost {
+                volume: 32.0,
+                margin: 0.0
+            }
+        );
+
+        let line = mbr! {
+            X = [0; 8],
+            Y = [3; 3]
+        };
+
+        assert_eq!(
+            line.cost(true),
+            mbr::Cost {
+                volume: 0.0,
+                margin: 8.0
+            }
+        );
+
+        let point = mbr! {
+            X = [1; 1],
+            Y = [3; 3]
+        };
+
+        assert_eq!(point.cost(true), mbr::Cost::default());
+        assert!(point.cost(true) < line.cost(true));
+    }
+
+    #[test]
+    fn test_mbr_margin() {
+        let mbr = mbr! {
+            X = [-4; 4]
+        };
+
+        assert_eq!(mbr.margin(), 8);
+
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7]
+        };
+
+        assert_eq!(mbr.margin(), 12);
+
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7],
+            Z = [-1; 1]
+        };
+
+        assert_eq!(mbr.margin(), 14);
+
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7],
+            Z = [-1; 1],
+            W = [5; 5]
+        };
+
+        assert_eq!(mbr.margin(), 14);
+
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        assert_eq!(undefined.margin(), 0);
+    }
+
+    #[test]
+    fn test_mbr_expand_by() {
+        let mut mbr = mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        };
+
+        let neighbor = mbr! {
+            X = [12; 20],
+            Y = [ 0; 10]
+        };
+
+        assert!(!mbr::intersects(&mbr, &neighbor));
+
+        mbr.expand_by(&2);
+
+        assert_eq!(
+            mbr,
+            mbr! {
+                X = [-2; 12],
+                Y = [-2; 12]
+            }
+        );
+        assert!(mbr::intersects(&mbr, &neighbor));
+
+        mbr.expand_by(&-2);
+
+        assert_eq!(
+            mbr,
+            mbr! {
+                X = [0; 10],
+                Y = [0; 10]
+            }
+        );
+
+        let mut mbr = mbr! {
+            X = [0.5; 1.0]
+        };
+
+        mbr.expand_by(&0.25);
+
+        assert_eq!(mbr, mbr![X = [0.25; 1.25]]);
+    }
+
+    #[test]
+    fn test_mbr_split_at() {
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let (lhs, rhs) = mbr.split_at(1, 2);
+
+        assert_eq!(
+            lhs,
+            mbr! {
+                X = [ 0; 10],
+                Y = [-3; 2]
+            }
+        );
+
+        assert_eq!(
+            rhs,
+            mbr! {
+                X = [0; 10],
+                Y = [2; 8]
+            }
+        );
+
+        assert_eq!(mbr::common_mbr(&lhs, &rhs), mbr);
+
+        let (lhs, rhs) = mbr.split_at(0, 0);
+
+        assert_eq!(lhs.bounds(0).length(), 0);
+        assert_eq!(rhs, mbr);
+        assert_eq!(mbr::common_mbr(&lhs, &rhs), mbr);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_panic_mbr_split_at() {
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        mbr.split_at(0, 11);
+    }
+
+    #[test]
+    fn test_mbr_scale() {
+        let original = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let mut mbr = original.clone();
+        mbr.scale(1.0);
+
+        assert_eq!(mbr, original);
+
+        let mut mbr = mbr![X = [0; 10]];
+        mbr.scale(2.0);
+
+        assert_eq!(mbr, mbr![X = [-5; 15]]);
+
+        // Integer bounds are rounded outwards
+        let mut mbr = mbr![X = [0; 3]];
+        mbr.scale(0.5);
+
+        assert_eq!(mbr, mbr![X = [0; 3]]);
+
+        let mut mbr = mbr! {
+            X = [0.0; 10.0],
+            Y = [1.0; 2.0]
+        };
+        mbr.scale(0.5);
+
+        assert_eq!(
+            mbr,
+            mbr! {
+                X = [2.5; 7.5],
+                Y = [1.25; 1.75]
+            }
+        );
+    }
+
+    #[test]
+    fn test_mbr_translate() {
+        let mut mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let center = mbr.center();
+        let offset = [5, -7];
+
+        mbr.translate(&offset);
+
+        assert_eq!(
+            mbr,
+            mbr! {
+                X = [  5; 15],
+                Y = [-10; 1]
+            }
+        );
+
+        let expected_center = center
+            .iter()
+            .zip(offset.iter())
+            .map(|(coord, delta)| coord + delta)
+            .collect::<Vec<_>>();
+
+        assert_eq!(mbr.center(), expected_center);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_panic_mbr_translate() {
+        let mut mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        mbr.translate(&[1]);
+    }
+
+    #[test]
+    fn test_mbr_surface_area() {
+        let mbr = mbr! {
+            X = [0; 8],
+            Y = [3; 7]
+        };
+
+        assert_eq!(mbr.surface_area(), 24);
+
+        let mbr = mbr! {
+            X = [0; 2],
+            Y = [0; 3],
+            Z = [0; 4]
         };
 
-        mbr.bounds(mbr.dimension());
+        assert_eq!(mbr.surface_area(), 2 * (2 * 3 + 2 * 4 + 3 * 4));
+
+        let mbr = mbr! {
+            X = [0.0; 0.5],
+            Y = [0.0; 0.5],
+            Z = [0.0; 0.5]
+        };
+
+        assert_eq!(mbr.surface_area(), 1.5);
+
+        let undefined = unsafe { MBR::<u32>::undefined() };
+
+        assert_eq!(undefined.surface_area(), 0);
     }
 
     #[test]
-    #[should_panic]
-    fn test_panic_undefined_bounds() {
+    fn test_mbr_corner_points() {
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let corners = mbr.corner_points();
+
+        assert_eq!(corners.len(), 4);
+        for corner in &[[0, -3], [10, -3], [0, 8], [10, 8]] {
+            assert!(corners.contains(&corner.to_vec()));
+        }
+
+        let mbr = mbr! {
+            X = [0; 1],
+            Y = [0; 2],
+            Z = [0; 3]
+        };
+
+        let corners = mbr.corner_points();
+
+        assert_eq!(corners.len(), 8);
+        for corner in &corners {
+            assert!(mbr.contains_point(corner));
+        }
+
+        for x in &[0, 1] {
+            for y in &[0, 2] {
+                for z in &[0, 3] {
+                    assert!(corners.contains(&vec![*x, *y, *z]));
+                }
+            }
+        }
+
         let undefined = unsafe { MBR::<u32>::undefined() };
 
-        undefined.bounds(0);
+        assert!(undefined.corner_points().is_empty());
     }
 
     #[test]
-    fn test_bounds_length() {
-        let bounds = mbr::Bounds::new(-4, 4);
-        assert_eq!(bounds.length(), 8);
+    #[should_panic]
+    fn test_panic_mbr_corner_points() {
+        let mbr = make_n_dim_mbr(mbr::MAX_CORNER_POINTS_DIMENSION + 1, 0, 1);
+
+        mbr.corner_points();
     }
 
     #[test]
-    fn test_mbr_volume() {
+    fn test_mbr_aspect_ratio() {
         let mbr = mbr! {
-            X = [-4; 4]
+            X = [0; 10],
+            Y = [0; 2]
         };
 
-        assert_eq!(mbr.volume(), 8);
+        assert_eq!(mbr.aspect_ratio(), 5.0);
 
         let mbr = mbr! {
-            X = [0; 8],
-            Y = [3; 7]
+            X = [0; 2],
+            Y = [0; 2],
+            Z = [0; 8]
         };
 
-        assert_eq!(mbr.volume(), 32);
+        assert_eq!(mbr.aspect_ratio(), 4.0);
+
+        let line = mbr! {
+            X = [0; 10],
+            Y = [3; 3]
+        };
+
+        assert!(line.aspect_ratio().is_infinite());
+
+        let point = mbr! {
+            X = [3; 3],
+            Y = [3; 3]
+        };
+
+        assert!(point.aspect_ratio().is_nan());
 
         let undefined = unsafe { MBR::<u32>::undefined() };
 
-        assert_eq!(undefined.volume(), 0);
+        assert!(undefined.aspect_ratio().is_nan());
     }
 
     #[test]
@@ -458,6 +1863,68 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_1d_mbr_intersects_strict() {
+        let mbr_0 = mbr! {
+            X = [0; 4]
+        };
+
+        let mbr_1 = mbr! {
+            X = [4; 9]
+        };
+
+        assert!(mbr::intersects(&mbr_0, &mbr_1));
+        assert!(!mbr::intersects_strict(&mbr_0, &mbr_1));
+        assert!(!mbr::intersects_strict(&mbr_1, &mbr_0));
+
+        let mbr_1 = mbr! {
+            X = [3; 9]
+        };
+
+        assert!(mbr::intersects_strict(&mbr_0, &mbr_1));
+        assert!(mbr::intersects_strict(&mbr_1, &mbr_0));
+
+        let mbr_1 = mbr! {
+            X = [1; 2]
+        };
+
+        assert!(mbr::intersects_strict(&mbr_0, &mbr_1));
+        assert!(mbr::intersects_strict(&mbr_1, &mbr_0));
+        assert!(mbr::intersects_strict(&mbr_0, &mbr_0));
+    }
+
+    #[test]
+    fn test_2d_mbr_intersects_strict() {
+        let mbr_0 = mbr! {
+            X = [0; 4],
+            Y = [0; 4]
+        };
+
+        let corner = mbr! {
+            X = [4; 9],
+            Y = [4; 9]
+        };
+
+        assert!(mbr::intersects(&mbr_0, &corner));
+        assert!(!mbr::intersects_strict(&mbr_0, &corner));
+
+        let edge = mbr! {
+            X = [2; 3],
+            Y = [4; 9]
+        };
+
+        assert!(mbr::intersects(&mbr_0, &edge));
+        assert!(!mbr::intersects_strict(&mbr_0, &edge));
+
+        let overlapping = mbr! {
+            X = [2; 3],
+            Y = [3; 9]
+        };
+
+        assert!(mbr::intersects_strict(&mbr_0, &overlapping));
+        assert!(mbr::intersects_strict(&overlapping, &mbr_0));
+    }
+
     #[test]
     fn test_mbr_cross_intersects_with() {
         let mbr_0 = mbr! {
@@ -474,6 +1941,33 @@ mod test {
         assert!(mbr::intersects(&mbr_1, &mbr_0));
     }
 
+    #[test]
+    fn test_mbr_inside_intersects() {
+        let outer = mbr! {
+            X = [-10; 10],
+            Y = [-10; 10],
+            Z = [-10; 10]
+        };
+
+        let inner = mbr! {
+            X = [-1; 1],
+            Y = [ 2; 3],
+            Z = [-5; 5]
+        };
+
+        assert!(mbr::intersects(&inner, &outer));
+        assert!(mbr::intersects(&outer, &inner));
+
+        let point = mbr! {
+            X = [0; 0],
+            Y = [0; 0],
+            Z = [0; 0]
+        };
+
+        assert!(mbr::intersects(&point, &outer));
+        assert!(mbr::intersects(&outer, &point));
+    }
+
     #[test]
     fn test_mbr_intersects_undefined() {
         let undefined = unsafe { MBR::undefined() };
@@ -491,6 +1985,147 @@ mod test {
         assert!(mbr::intersects(&undefined_1, &undefined));
     }
 
+    #[test]
+    fn test_intersection() {
+        let mbr_0 = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let mbr_1 = mbr! {
+            X = [-5; 4],
+            Y = [-7; -1]
+        };
+
+        let expected = mbr! {
+            X = [ 0; 4],
+            Y = [-3; -1]
+        };
+
+        assert_eq!(mbr::intersection(&mbr_0, &mbr_1), Some(expected.clone()));
+        assert_eq!(mbr::intersection(&mbr_1, &mbr_0), Some(expected));
+
+        let inner = mbr! {
+            X = [2; 3],
+            Y = [0; 1]
+        };
+
+        assert_eq!(mbr::intersection(&mbr_0, &inner), Some(inner.clone()));
+
+        let disjoint = mbr! {
+            X = [11; 20],
+            Y = [ 0;  1]
+        };
+
+        assert_eq!(mbr::intersection(&mbr_0, &disjoint), None);
+    }
+
+    #[test]
+    fn test_intersection_touching() {
+        let mbr_0 = mbr! {
+            X = [0; 4],
+            Y = [0; 4]
+        };
+
+        let mbr_1 = mbr! {
+            X = [4; 9],
+            Y = [2; 6]
+        };
+
+        let intersection = mbr::intersection(&mbr_0, &mbr_1).unwrap();
+
+        assert_eq!(
+            intersection,
+            mbr! {
+                X = [4; 4],
+                Y = [2; 4]
+            }
+        );
+        assert_eq!(intersection.volume(), 0);
+    }
+
+    #[test]
+    fn test_intersection_undefined() {
+        let undefined = unsafe { MBR::undefined() };
+
+        let mbr = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        assert_eq!(mbr::intersection(&mbr, &undefined), None);
+        assert_eq!(mbr::intersection(&undefined, &mbr), None);
+    }
+
+    #[test]
+    fn test_overlap_volume() {
+        let mbr_0 = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let mbr_1 = mbr! {
+            X = [-5; 4],
+            Y = [-7; -1]
+        };
+
+        assert_eq!(mbr::overlap_volume(&mbr_0, &mbr_1), 8);
+        assert_eq!(mbr::overlap_volume(&mbr_1, &mbr_0), 8);
+        assert_eq!(mbr::overlap_volume(&mbr_0, &mbr_0), mbr_0.volume());
+
+        let touching = mbr! {
+            X = [10; 20],
+            Y = [-3; 8]
+        };
+
+        assert_eq!(mbr::overlap_volume(&mbr_0, &touching), 0);
+
+        let disjoint = mbr! {
+            X = [11; 20],
+            Y = [ 0;  1]
+        };
+
+        assert_eq!(mbr::overlap_volume(&mbr_0, &disjoint), 0);
+    }
+
+    #[test]
+    fn test_min_distance() {
+        let mbr_0 = mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        };
+
+        let mbr_1 = mbr! {
+            X = [13; 20],
+            Y = [14; 20]
+        };
+
+        assert_eq!(mbr::min_distance(&mbr_0, &mbr_1), 5.0);
+        assert_eq!(mbr::min_distance(&mbr_1, &mbr_0), 5.0);
+
+        let mbr_1 = mbr! {
+            X = [-7; -3],
+            Y = [ 2;  8]
+        };
+
+        assert_eq!(mbr::min_distance(&mbr_0, &mbr_1), 3.0);
+        assert_eq!(mbr::min_distance(&mbr_1, &mbr_0), 3.0);
+
+        let mbr_1 = mbr! {
+            X = [10; 20],
+            Y = [ 5;  6]
+        };
+
+        assert_eq!(mbr::min_distance(&mbr_0, &mbr_1), 0.0);
+        assert_eq!(mbr::min_distance(&mbr_0, &mbr_0), 0.0);
+
+        let mbr_1d = mbr! {
+            X = [12; 20]
+        };
+
+        assert_eq!(mbr::min_distance(&mbr_0, &mbr_1d), 2.0);
+    }
+
     #[test]
     fn test_common_mbr() {
         let mbr_0 = mbr! {
@@ -510,6 +2145,29 @@ mod test {
         assert_eq!(common.bounds[1].max, 8);
     }
 
+    #[test]
+    fn test_common_mbr_mismatched_dimensions() {
+        let mbr_2d = mbr! {
+            X = [0; 10],
+            Y = [-3; 8]
+        };
+
+        let mbr_3d = mbr! {
+            X = [-5; 4],
+            Y = [-7; -1],
+            Z = [100; 200]
+        };
+
+        let expected = mbr! {
+            X = [-5; 10],
+            Y = [-7; 8],
+            Z = [100; 200]
+        };
+
+        assert_eq!(mbr::common_mbr(&mbr_2d, &mbr_3d), expected);
+        assert_eq!(mbr::common_mbr(&mbr_3d, &mbr_2d), expected);
+    }
+
     #[test]
     fn test_common_mbr_undefined() {
         let undefined = unsafe { MBR::undefined() };
@@ -583,6 +2241,175 @@ mod test {
         assert_eq!(common.bounds[1].max, 9);
     }
 
+    #[test]
+    fn test_mbr_delta() {
+        let src = mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        };
+
+        let addition = mbr! {
+            X = [5; 12],
+            Y = [2; 4]
+        };
+
+        assert_eq!(mbr::mbr_delta(&src, &addition), 20);
+        assert_eq!(mbr::margin_delta(&src, &addition), 2);
+
+        let inner = mbr! {
+            X = [1; 2],
+            Y = [1; 2]
+        };
+
+        assert_eq!(mbr::mbr_delta(&src, &inner), 0);
+        assert_eq!(mbr::margin_delta(&src, &inner), 0);
+    }
+
+    #[test]
+    fn test_margin_delta_flat() {
+        // Volume doesn't change for flat MBRs, but margin does
+        let src = mbr! {
+            X = [0; 10],
+            Y = [0; 0]
+        };
+
+        let addition = mbr! {
+            X = [15; 20],
+            Y = [ 0;  0]
+        };
+
+        assert_eq!(mbr::mbr_delta(&src, &addition), 0);
+        assert_eq!(mbr::margin_delta(&src, &addition), 10);
+    }
+
+    #[test]
+    fn test_cost_delta() {
+        let line = mbr! {
+            X = [0; 10],
+            Y = [0; 0]
+        };
+
+        let square = mbr! {
+            X = [0; 1],
+            Y = [0; 1]
+        };
+
+        let far_point = mbr! {
+            X = [100; 100],
+            Y = [  0;   0]
+        };
+
+        let near_point = mbr! {
+            X = [11; 11],
+            Y = [ 0;  0]
+        };
+
+        // The volume enlargement wins over any margin enlargement
+        let line_delta = mbr::cost_delta(&line, &far_point, true);
+        let square_delta = mbr::cost_delta(&square, &near_point, true);
+
+        assert_eq!(line_delta.volume, 0.0);
+        assert_eq!(line_delta.margin, 90.0);
+        assert_eq!(square_delta.volume, 10.0);
+        assert!(line_delta < square_delta);
+
+        // The margin breaks the tie of the equal volumes only if it is enabled
+        assert!(mbr::cost_delta(&line, &near_point, true) < line_delta);
+        assert_eq!(
+            mbr::cost_delta(&line, &near_point, false),
+            mbr::cost_delta(&line, &far_point, false)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_mbr_serde() {
+        let mbr: MBR<i64> = mbr! {
+            X = [ 0; 10],
+            Y = [-5;  5],
+            Z = [ 7;  7]
+        };
+
+        let json = serde_json::to_string(&mbr).unwrap();
+        assert_eq!(json, "[[0,10],[-5,5],[7,7]]");
+
+        let deserialized: MBR<i64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, mbr);
+        assert_eq!(deserialized.bounds(1), &mbr::Bounds::new(-5, 5));
+
+        assert!(serde_json::from_str::<MBR<i64>>("[]").is_err());
+        assert!(serde_json::from_str::<MBR<i64>>("[[10,0]]").is_err());
+        assert!(serde_json::from_str::<MBR<i64>>("[[0,10,20]]").is_err());
+    }
+
+    #[test]
+    fn test_mbr_from_array() {
+        assert_eq!(
+            MBR::from([(0, 10), (3, 7)]),
+            mbr! {
+                X = [0; 10],
+                Y = [3;  7]
+            }
+        );
+
+        assert_eq!(MBR::from([(-1.5, 2.0)]), mbr! { X = [-1.5; 2.0] });
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn test_mbr_to_wkt() {
+        let mbr = mbr! {
+            X = [-1; 10],
+            Y = [ 2;  5]
+        };
+        assert_eq!(mbr.to_wkt(), "POLYGON((-1 2, 10 2, 10 5, -1 5, -1 2))");
+
+        let mbr = mbr! {
+            X = [0.5; 1.0],
+            Y = [0.0; 2.0]
+        };
+        assert_eq!(mbr.to_wkt(), "POLYGON((0.5 0, 1 0, 1 2, 0.5 2, 0.5 0))");
+
+        let mbr = mbr! {
+            X = [0; 1],
+            Y = [2; 3],
+            Z = [4; 5]
+        };
+        assert_eq!(
+            mbr.to_wkt(),
+            "POLYGON Z((0 2 4, 1 2 4, 1 3 5, 0 3 5, 0 2 4))"
+        );
+
+        assert_eq!(mbr! { X = [0; 1] }.to_wkt(), "POLYGON EMPTY");
+        assert_eq!(unsafe { MBR::<i32>::undefined() }.to_wkt(), "POLYGON EMPTY");
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_mbr_geo_rect() {
+        use geo_types::{coord, Rect};
+
+        let rect = Rect::new(coord! { x: 10.5, y: -3.0 }, coord! { x: -1.0, y: 7.25 });
+
+        let mbr = MBR::from(rect);
+        assert_eq!(
+            mbr,
+            mbr! {
+                X = [-1.0; 10.5],
+                Y = [-3.0; 7.25]
+            }
+        );
+
+        assert_eq!(Rect::from(mbr), rect);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    #[should_panic(expected = "unexpected dimension")]
+    fn test_panic_mbr_3d_to_geo_rect() {
+        let _ = geo_types::Rect::from(mbr! { X = [0; 1], Y = [0; 1], Z = [0; 1] });
+    }
+
     fn test_mbr_dimension_intersects_with(
         src_mbr: &mbr::MBR<i32>,
         mut test_mbr: mbr::MBR<i32>,
@@ -0,0 +1,92 @@
+use super::{CoordTrait, MBR};
+
+/// Distance used by the nearest neighbors search.
+pub trait Metric<CoordT: CoordTrait> {
+    /// Minimal distance from the `point` to the `mbr` under this metric.
+    ///
+    /// It must be 0 if the `point` is inside the `mbr`.
+    fn distance(&self, point: &[CoordT], mbr: &MBR<CoordT>) -> f64;
+}
+
+/// L2 distance, the same as `mbr::min_distance`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Euclidean;
+
+/// L1 distance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Manhattan;
+
+/// L∞ distance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chebyshev;
+
+impl<CoordT: CoordTrait> Metric<CoordT> for Euclidean {
+    fn distance(&self, point: &[CoordT], mbr: &MBR<CoordT>) -> f64 {
+        axis_gaps(point, mbr)
+            .map(|gap| gap * gap)
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+impl<CoordT: CoordTrait> Metric<CoordT> for Manhattan {
+    fn distance(&self, point: &[CoordT], mbr: &MBR<CoordT>) -> f64 {
+        axis_gaps(point, mbr).sum()
+    }
+}
+
+impl<CoordT: CoordTrait> Metric<CoordT> for Chebyshev {
+    fn distance(&self, point: &[CoordT], mbr: &MBR<CoordT>) -> f64 {
+        axis_gaps(point, mbr).fold(0.0, f64::max)
+    }
+}
+
+/// Distances from the `point` to the `mbr` bounds along each axis.
+fn axis_gaps<'a, CoordT: CoordTrait>(
+    point: &'a [CoordT],
+    mbr: &'a MBR<CoordT>,
+) -> impl Iterator<Item = f64> + 'a {
+    mbr.iter_bounds().zip(point.iter()).map(|(bounds, value)| {
+        let gap = if *value < bounds.min {
+            bounds.min.clone() - value.clone()
+        } else if bounds.max < *value {
+            value.clone() - bounds.max.clone()
+        } else {
+            return 0.0;
+        };
+
+        gap.to_f64()
+            .expect("CoordT is expected to be convertible to f64")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::mbr};
+
+    #[test]
+    fn test_metric_distance() {
+        let mbr = mbr! {
+            X = [10; 20],
+            Y = [10; 20]
+        };
+
+        assert_eq!(Euclidean.distance(&[15, 15], &mbr), 0.0);
+        assert_eq!(Manhattan.distance(&[15, 15], &mbr), 0.0);
+        assert_eq!(Chebyshev.distance(&[15, 15], &mbr), 0.0);
+
+        assert_eq!(Euclidean.distance(&[15, 0], &mbr), 10.0);
+        assert_eq!(Manhattan.distance(&[15, 0], &mbr), 10.0);
+        assert_eq!(Chebyshev.distance(&[15, 0], &mbr), 10.0);
+
+        assert_eq!(Euclidean.distance(&[7, 24], &mbr), 5.0);
+        assert_eq!(Manhattan.distance(&[7, 24], &mbr), 7.0);
+        assert_eq!(Chebyshev.distance(&[7, 24], &mbr), 4.0);
+
+        let point = mbr! { X = 7, Y = 24 };
+        assert_eq!(
+            Euclidean.distance(&[7, 24], &mbr),
+            mbr::min_distance(&point, &mbr)
+        );
+    }
+}
@@ -1,6 +1,17 @@
+pub mod choose_subtree;
+mod config;
+#[cfg(feature = "csv")]
+mod csv_import;
+#[cfg(feature = "geojson")]
+mod geojson_import;
+mod lock;
 pub mod mbr;
+pub mod metric;
 mod node;
 mod obj_space;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod split;
 pub mod visitor;
 
 #[cfg(test)]
@@ -9,29 +20,80 @@ mod test;
 #[cfg(test)]
 mod proptest;
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "bincode")]
+use {
+    serde::de::DeserializeOwned,
+    std::{
+        fs::File,
+        io::{self, BufReader, BufWriter, Write},
+        path::Path,
+    },
+};
+
 use std::{
     cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
     env,
-    fmt::Debug,
-    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    error::Error,
+    fmt::{self, Debug, Display},
+    iter::FromIterator,
+    ops::ControlFlow,
+    sync::Arc,
 };
 
+use lock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
 pub use crate::tree::{
+    choose_subtree::{ChooseSubtree, LeastEnlargement, LeastOverlap},
     mbr::{Bounds, CoordTrait, MBR},
-    visitor::Visitor,
+    metric::{Chebyshev, Euclidean, Manhattan, Metric},
+    split::{LinearSplit, QuadraticSplit, SplitStrategy},
+    visitor::{dot::DotVisitor, DepthVisitor, Visitor},
 };
+pub use config::{ConfigError, TreeConfig};
+#[cfg(feature = "csv")]
+pub use csv_import::CsvImportError;
+#[cfg(feature = "geojson")]
+pub use geojson_import::GeoJsonImportError;
 pub use node::{Node, NodeId, RecordId, RecordIdKind};
 pub use obj_space::ObjSpace;
+#[cfg(feature = "svg")]
+pub use visitor::svg::SvgVisitor;
 
 pub type InternalNode<CoordT> = Node<CoordT, NodeChildren>;
 pub type DataNode<CoordT, ObjectT> = Node<CoordT, ObjectT>;
 
 type NodeChildren = Vec<RecordId>;
 type NodeGroup<'ids, CoordT> = (&'ids mut [RecordId], MBR<CoordT>);
+type SplitGroups<CoordT, ObjectT> = for<'ids> fn(
+    &ObjSpace<CoordT, ObjectT>,
+    f32,
+    usize,
+    usize,
+    &'ids mut [RecordId],
+) -> Vec<NodeGroup<'ids, CoordT>>;
+
+/// Alpha of the static build used by `LRTree::insert_many` on an empty tree.
+const INSERT_MANY_ALPHA: f32 = 0.25;
+
+/// Dimension of an empty tree made by `collect` and of the default `TreeConfig`.
+const DEFAULT_DIMENSION: usize = 2;
+
+/// `min_records` of a tree made by `collect` and of the default `TreeConfig`.
+const DEFAULT_MIN_RECORDS: usize = 2;
+
+/// `max_records` of a tree made by `collect` and of the default `TreeConfig`.
+const DEFAULT_MAX_RECORDS: usize = 8;
 
 macro_rules! obj_space {
     () => {
-        RwLockWriteGuard<ObjSpace<CoordT, ObjectT>>
+        ObjSpace<CoordT, ObjectT>
     };
 }
 
@@ -86,6 +148,199 @@ pub trait InsertHandler<CoordT: CoordTrait, ObjectT: Clone> {
     fn after_insert(&mut self, _: &ObjSpace<CoordT, ObjectT>, _: NodeId) {}
 }
 
+struct DefaultInsertHandler;
+
+impl<CoordT: CoordTrait, ObjectT: Clone> InsertHandler<CoordT, ObjectT> for DefaultInsertHandler {}
+
+/// Error of the capped insertion: the tree already contains `cap` objects or more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapExceeded {
+    pub cap: usize,
+    pub len: usize,
+}
+
+impl Display for CapExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unable to insert: the tree contains {} objects (cap = {})",
+            self.len, self.cap
+        )
+    }
+}
+
+impl Error for CapExceeded {}
+
+/// What to do with an overflowed node during the insertion, see `LRTree::set_insert_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InsertPolicy {
+    /// Split the node at once.
+    #[default]
+    Split,
+
+    /// Take the `count` children farthest from the node center out and insert them again
+    /// (as in the R*-tree). It happens once per tree level during one insertion,
+    /// the next overflow on the same level splits the node. The root is always split.
+    ForcedReinsert { count: usize },
+}
+
+/// Error of the checked insertion, see `LRTree::try_insert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError {
+    DimensionMismatch { expected: usize, got: usize },
+}
+
+impl Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::DimensionMismatch { expected, got } => write!(
+                f,
+                "unexpected dimension: the tree dimension is {}, the MBR dimension is {}",
+                expected, got
+            ),
+        }
+    }
+}
+
+impl Error for InsertError {}
+
+/// Lazy search of the objects intersecting an area, see `LRTree::search_iter`.
+///
+/// The read lock on the tree is held until the iterator is dropped.
+pub struct SearchIter<'a, CoordT: CoordTrait, ObjectT: Debug + Clone> {
+    obj_space: RwLockReadGuard<'a, ObjSpace<CoordT, ObjectT>>,
+    area: MBR<CoordT>,
+    stack: Vec<RecordId>,
+}
+
+impl<'a, CoordT: CoordTrait, ObjectT: Debug + Clone> Iterator for SearchIter<'a, CoordT, ObjectT> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(record_id) = self.stack.pop() {
+            if let RecordId::Data(id) = record_id {
+                return Some(id);
+            }
+
+            let obj_space = &self.obj_space;
+            let area = &self.area;
+
+            // Reversed, so the children are yielded in the same order as by `search`.
+            self.stack.extend(
+                obj_space
+                    .get_node(record_id)
+                    .payload
+                    .iter()
+                    .rev()
+                    .filter(filter_intersections!(area in obj_space)),
+            );
+        }
+
+        None
+    }
+}
+
+/// Lazy nearest neighbors search, see `LRTree::nearest_iter`.
+///
+/// The read lock on the tree is held until the iterator is dropped.
+pub struct NearestIter<'a, CoordT, ObjectT, M = Euclidean>
+where
+    CoordT: CoordTrait,
+    ObjectT: Debug + Clone,
+    M: Metric<CoordT>,
+{
+    obj_space: RwLockReadGuard<'a, ObjSpace<CoordT, ObjectT>>,
+    point: Vec<CoordT>,
+    metric: M,
+    queue: BinaryHeap<NearestCandidate>,
+}
+
+impl<'a, CoordT, ObjectT, M> Iterator for NearestIter<'a, CoordT, ObjectT, M>
+where
+    CoordT: CoordTrait,
+    ObjectT: Debug + Clone,
+    M: Metric<CoordT>,
+{
+    type Item = (NodeId, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Records are popped in ascending order of distance,
+        // so every queued branch is not nearer than the popped object.
+        while let Some(NearestCandidate {
+            distance,
+            record_id,
+        }) = self.queue.pop()
+        {
+            if let RecordId::Data(id) = record_id {
+                return Some((id, distance));
+            }
+
+            let obj_space = &self.obj_space;
+            let point = &self.point;
+            let metric = &self.metric;
+
+            self.queue.extend(
+                obj_space
+                    .get_node(record_id)
+                    .payload
+                    .iter()
+                    .map(|&child_id| NearestCandidate {
+                        distance: metric.distance(point, obj_space.get_mbr(child_id)),
+                        record_id: child_id,
+                    }),
+            );
+        }
+
+        None
+    }
+}
+
+/// Entry of the nearest neighbors queue, the nearest record goes first.
+#[derive(Debug)]
+struct NearestCandidate {
+    distance: f64,
+    record_id: RecordId,
+}
+
+impl PartialEq for NearestCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for NearestCandidate {}
+
+impl PartialOrd for NearestCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NearestCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so the `BinaryHeap` pops the nearest record first.
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.record_id.cmp(&self.record_id))
+    }
+}
+
+/// R-tree storing the objects in the `ObjSpace` guarded by a `RwLock`.
+///
+/// The lock is `std::sync::RwLock` by default, so a panic while it is held
+/// poisons the tree. With the `parking_lot` feature `parking_lot::RwLock` is used,
+/// which is never poisoned.
+///
+/// # Concurrency
+/// Every mutation (`insert`, `remove`, `update`, `rebuild`, ...) holds the write lock
+/// for the whole operation including the tree fix-up, and every query holds the read lock
+/// until it is completed. So the queries always see a consistent tree,
+/// but they wait while a mutation is running. `try_search` doesn't wait.
+///
+/// The visits (`visit`, `visit_from`, ...) hold the read lock while the visitor is called,
+/// so the visitor must not modify the tree.
 #[derive(Debug)]
 pub struct LRTree<CoordT: CoordTrait, ObjectT: Debug + Clone> {
     obj_space: RwLock<ObjSpace<CoordT, ObjectT>>,
@@ -110,8 +365,62 @@ where
     }
 
     pub fn rebuild(&self, alpha: f32) {
-        let mut obj_space = self.obj_space.write().unwrap();
+        let mut obj_space = self.obj_space.write();
+
+        Self::rebuild_obj_space(&mut obj_space, alpha, Self::split_groups);
+    }
+
+    /// Like `rebuild`, but the groups of the objects are split in parallel with rayon.
+    ///
+    /// The resulting tree is the same as after `rebuild` with the same `alpha`.
+    #[cfg(feature = "rayon")]
+    pub fn par_rebuild(&self, alpha: f32)
+    where
+        CoordT: Send + Sync,
+        ObjectT: Send + Sync,
+    {
+        let mut obj_space = self.obj_space.write();
+
+        Self::rebuild_obj_space(&mut obj_space, alpha, Self::par_split_groups);
+    }
+
+    /// Rebuilds the tree with the Sort-Tile-Recursive packing.
+    ///
+    /// The objects are tiled by their centers axis by axis and packed into full nodes,
+    /// then the same is done for the nodes on every level up to the root.
+    pub fn rebuild_str(&self) {
+        let mut obj_space = self.obj_space.write();
+
+        debug_log!("rebuild lr-tree with STR");
+
+        if obj_space.is_empty() {
+            debug_log!("lr-tree is empty");
+            return;
+        }
+
+        obj_space.clear_tree_structure();
+
+        let mut ids = obj_space.iter_data_ids().collect::<Vec<_>>();
+        let mut kind = RecordIdKind::Leaf;
+
+        while ids.len() > obj_space.max_records {
+            ids = Self::str_pack(&mut obj_space, ids, kind);
+            kind = RecordIdKind::Internal;
+        }
+
+        obj_space.root_id.set_kind(kind);
+
+        let root_id = obj_space.root_id;
+        bind!([obj_space] root_id => set(ids));
+
+        debug_log!("rebuild lr-tree with STR -- COMPLETED");
+    }
 
+    fn rebuild_obj_space(
+        obj_space: &mut obj_space![],
+        alpha: f32,
+        split_groups: SplitGroups<CoordT, ObjectT>,
+    ) {
         debug_log!("rebuild lr-tree");
 
         if obj_space.is_empty() {
@@ -137,7 +446,14 @@ where
 
         let root_id = obj_space.root_id;
 
-        Self::build_node(&mut *obj_space, alpha, root_id, level - 1, unbinded_ids);
+        Self::build_node(
+            &mut *obj_space,
+            alpha,
+            split_groups,
+            root_id,
+            level - 1,
+            unbinded_ids,
+        );
 
         let root_mbr = mbr::common_mbr_from_iter(
             obj_space
@@ -152,206 +468,1465 @@ where
         debug_log!("rebuild lr-tree -- COMPLETED");
     }
 
-    pub fn lock_obj_space(&self) -> RwLockReadGuard<ObjSpace<CoordT, ObjectT>> {
-        self.obj_space.read().unwrap()
+    /// Number of the objects in the tree.
+    ///
+    /// Objects marked as removed are not counted.
+    pub fn len(&self) -> usize {
+        self.obj_space.read().data_num()
     }
 
-    pub fn lock_obj_space_write(&self) -> RwLockWriteGuard<ObjSpace<CoordT, ObjectT>> {
-        self.obj_space.write().unwrap()
+    pub fn is_empty(&self) -> bool {
+        self.obj_space.read().is_empty()
     }
 
-    pub fn access_object<H, R>(&self, record_id: NodeId, mut handler: H) -> R
-    where
-        H: FnMut(&ObjectT, &MBR<CoordT>) -> R,
-    {
-        let obj_space = self.obj_space.read().unwrap();
-        let node = obj_space.get_data(record_id);
-
-        debug_log!("access object #{}: {:?}", record_id, node.payload);
+    /// Number of the tree levels except the data one: 0 for an empty tree, 1 for a single leaf.
+    pub fn height(&self) -> usize {
+        let obj_space = self.obj_space.read();
 
-        handler(&node.payload, &node.mbr)
+        Self::height_obj_space(&obj_space)
     }
 
-    pub fn access_object_mut<H>(&self, record_id: NodeId, mut handler: H)
-    where
-        H: FnMut(&mut ObjectT, &mut MBR<CoordT>),
-    {
-        let mut obj_space = self.obj_space.write().unwrap();
-        let node = obj_space.get_data_mut(record_id);
+    fn height_obj_space(obj_space: &ObjSpace<CoordT, ObjectT>) -> usize {
+        if obj_space.is_empty() {
+            return 0;
+        }
 
-        handler(&mut node.payload, &mut node.mbr)
-    }
+        let mut height = 1;
+        let mut node_id = obj_space.root_id;
 
-    pub fn visit<V: Visitor<CoordT, ObjectT>>(&self, visitor: &mut V) {
-        if self.obj_space.read().unwrap().is_empty() {
-            return;
+        while let RecordId::Internal(_) = node_id {
+            node_id = obj_space.get_node(node_id).payload[0];
+            height += 1;
         }
 
-        self.visit_helper(visitor, self.obj_space.read().unwrap().root_id);
+        height
     }
 
-    pub fn search(&self, area: &MBR<CoordT>) -> Vec<NodeId> {
-        let obj_space = self.obj_space.read().unwrap();
-        let mut result = vec![];
+    /// Number of the nodes above the object: equals `height` for every object in a balanced tree.
+    ///
+    /// Returns `None` if the object is removed
+    /// and `Some(0)` if it is not bound to the tree structure yet (e.g. before `rebuild`).
+    pub fn depth_of(&self, id: NodeId) -> Option<usize> {
+        let obj_space = self.obj_space.read();
 
-        debug_log!("search in area {}", area);
+        if obj_space.is_removed(&id) {
+            return None;
+        }
 
-        let root_id = obj_space.root_id;
-        Self::search_helper(&obj_space, root_id, area, &mut |_, &rec_id| {
-            result.push(rec_id.as_node_id())
-        });
+        let mut depth = 0;
+        let mut parent_id = obj_space.get_data(id).parent_id;
 
-        debug_log!("search result in area {} -- {:?}", area, result);
+        while !matches![parent_id, RecordId::Root] {
+            parent_id = obj_space.get_node(parent_id).parent_id;
+            depth += 1;
+        }
 
-        result
+        Some(depth)
     }
 
-    pub fn search_access_obj_space<Handler>(
-        obj_space: &ObjSpace<CoordT, ObjectT>,
-        area: &MBR<CoordT>,
-        mut handler: Handler,
-    ) where
-        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId),
-    {
-        debug_log!("search access in area {}", area);
+    /// Sets the strategy of choosing the subtree for the new objects, `LeastEnlargement` by default.
+    ///
+    /// The existing tree structure is kept, the strategy affects only the further insertions.
+    pub fn set_choose_subtree(
+        &self,
+        choose_subtree: impl ChooseSubtree<CoordT, ObjectT> + 'static,
+    ) {
+        debug_log!("set choose subtree strategy: {:?}", choose_subtree);
 
-        let root_id = obj_space.root_id;
-        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
-            handler(&*obj_space, rec_id.as_node_id());
-        });
+        self.obj_space.write().choose_subtree = Arc::new(choose_subtree);
+    }
 
-        debug_log!("search access in area {} -- COMPLETED", area);
+    /// Policy of handling the overflowed nodes during the insertion.
+    pub fn insert_policy(&self) -> InsertPolicy {
+        self.obj_space.read().insert_policy
     }
 
-    pub fn search_access<H>(&self, area: &MBR<CoordT>, handler: H)
-    where
-        H: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId),
-    {
-        let obj_space = self.obj_space.read().unwrap();
+    /// Sets the policy of handling the overflowed nodes during the insertion.
+    ///
+    /// # Panics
+    /// Panics if `InsertPolicy::ForcedReinsert` count is zero
+    /// or leaves less than `min_records` children in the overflowed node.
+    pub fn set_insert_policy(&self, insert_policy: InsertPolicy) {
+        let mut obj_space = self.obj_space.write();
 
-        Self::search_access_obj_space(&*obj_space, area, handler);
+        debug_log!("set insert policy: {:?}", insert_policy);
 
-        // debug_log!("search access in area {}", area);
+        if let InsertPolicy::ForcedReinsert { count } = insert_policy {
+            assert!(count > 0);
+            assert!(count <= obj_space.max_records + 1 - obj_space.min_records);
+        }
 
-        // let root_id = obj_space.root_id;
-        // Self::search_helper(
-        //     &obj_space,
-        //     root_id,
-        //     area,
-        //     &mut |obj_space, &rec_id| {
-        //         handler(&*obj_space, rec_id.as_node_id());
-        //     }
-        // );
+        obj_space.insert_policy = insert_policy;
+    }
 
-        // debug_log!("search access in area {} -- COMPLETED", area);
+    /// Sets the algorithm of splitting the overflowed nodes, `LinearSplit` by default.
+    ///
+    /// The existing tree structure is kept, the strategy affects only the further insertions.
+    pub fn set_split_strategy(
+        &self,
+        split_strategy: impl SplitStrategy<CoordT, ObjectT> + 'static,
+    ) {
+        debug_log!("set split strategy: {:?}", split_strategy);
+
+        self.obj_space.write().split_strategy = Arc::new(split_strategy);
     }
 
-    pub fn retain<P>(&self, area: &MBR<CoordT>, mut predicate: P)
-    where
-        P: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId) -> bool,
-    {
-        let mut obj_space = self.obj_space.write().unwrap();
-        let mut remove_list = vec![];
+    /// Whether the MBRs of the same volume are compared by the margin, on by default.
+    pub fn margin_tie_break(&self) -> bool {
+        self.obj_space.read().margin_tie_break
+    }
 
-        debug_log!("retain in area {}", area);
+    /// Sets whether the MBRs of the same volume are compared by the margin
+    /// when the subtree is chosen and when a node is split.
+    ///
+    /// The volume is always compared first. Flat MBRs (e.g. points) have zero volume,
+    /// so without the tie-break they all look the same and can be packed poorly.
+    ///
+    /// The existing tree structure is kept, the option affects only the further insertions.
+    pub fn set_margin_tie_break(&self, margin_tie_break: bool) {
+        debug_log!("set margin tie-break: {}", margin_tie_break);
+
+        self.obj_space.write().margin_tie_break = margin_tie_break;
+    }
 
-        let root_id = obj_space.root_id;
-        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
-            let data_id = rec_id.as_node_id();
+    /// Removes all the objects and the tree structure.
+    ///
+    /// The dimension and the records limits are kept, so the tree can be reused as a new one.
+    ///
+    /// **The object IDs start from scratch**: an ID got before `clear` refers to
+    /// the object inserted with the same ID after it, not to an absent object.
+    /// Drop all the old IDs, or `remove` the objects one by one to keep the IDs unique.
+    pub fn clear(&self) {
+        debug_log!("clear lr-tree");
+
+        self.obj_space.write().clear();
+    }
 
-            if !predicate(&*obj_space, data_id) {
-                remove_list.push(data_id);
-            }
-        });
+    /// Dumps the MBRs of the live objects as a GeoJSON `FeatureCollection`.
+    ///
+    /// Every object is a `Polygon` feature with the object's ID in the `id` property.
+    /// Only 2D trees can be exported, for other trees the collection is empty.
+    #[cfg(feature = "geojson")]
+    pub fn export_geojson(&self) -> String {
+        let obj_space = self.obj_space.read();
+
+        let features = if obj_space.dimension == 2 {
+            obj_space
+                .iter()
+                .map(|(id, _, mbr)| {
+                    let to_f64 = |value: &CoordT| {
+                        value
+                            .to_f64()
+                            .expect("CoordT is expected to be convertible to f64")
+                    };
+
+                    let (x, y) = (mbr.bounds(0), mbr.bounds(1));
+                    let (min_x, max_x) = (to_f64(&x.min), to_f64(&x.max));
+                    let (min_y, max_y) = (to_f64(&y.min), to_f64(&y.max));
+
+                    serde_json::json!({
+                        "type": "Feature",
+                        "geometry": {
+                            "type": "Polygon",
+                            "coordinates": [[
+                                [min_x, min_y],
+                                [max_x, min_y],
+                                [max_x, max_y],
+                                [min_x, max_y],
+                                [min_x, min_y]
+                            ]]
+                        },
+                        "properties": { "id": id }
+                    })
+                })
+                .collect()
+        } else {
+            vec![]
+        };
 
-        obj_space.mark_as_removed(remove_list.into_iter());
+        serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features
+        })
+        .to_string()
+    }
 
-        debug_log!("retain in area {} -- COMPLETED", area);
+    pub fn lock_obj_space(&self) -> RwLockReadGuard<ObjSpace<CoordT, ObjectT>> {
+        self.obj_space.read()
     }
 
-    pub fn retain_mut<P>(&self, area: &MBR<CoordT>, mut predicate: P)
-    where
-        P: FnMut(&mut ObjSpace<CoordT, ObjectT>, NodeId) -> bool,
-    {
-        let mut obj_space = self.obj_space.write().unwrap();
-        let mut remove_list = vec![];
+    pub fn lock_obj_space_write(&self) -> RwLockWriteGuard<ObjSpace<CoordT, ObjectT>> {
+        self.obj_space.write()
+    }
 
-        debug_log!("retain mut in area {}", area);
+    /// Runs `f` under a single write lock.
+    ///
+    /// Several modifications (`ObjSpace::insert`, `ObjSpace::remove`, `mark_as_removed`, etc.)
+    /// made by `f` are seen by the concurrent queries at once.
+    pub fn transaction<R>(&self, f: impl FnOnce(&mut ObjSpace<CoordT, ObjectT>) -> R) -> R {
+        let mut obj_space = self.obj_space.write();
 
-        let root_id = obj_space.root_id;
-        Self::search_helper_mut(&mut obj_space, root_id, area, &mut |obj_space, &rec_id| {
-            let data_id = rec_id.as_node_id();
+        debug_log!("transaction");
 
-            if !predicate(&mut *obj_space, data_id) {
-                remove_list.push(data_id);
-            }
-        });
+        let result = f(&mut obj_space);
 
-        obj_space.mark_as_removed(remove_list.into_iter());
+        debug_log!("[COMPLETED] transaction");
 
-        debug_log!("retain mut in area {} -- COMPLETED", area);
+        result
     }
 
-    pub fn insert(&self, object: ObjectT, mbr: MBR<CoordT>) -> NodeId {
-        struct DefaultHelper;
+    pub fn access_object<H, R>(&self, record_id: NodeId, mut handler: H) -> R
+    where
+        H: FnMut(&ObjectT, &MBR<CoordT>) -> R,
+    {
+        let obj_space = self.obj_space.read();
+        let node = obj_space.get_data(record_id);
 
-        impl<CoordT: CoordTrait, ObjectT: Clone> InsertHandler<CoordT, ObjectT> for DefaultHelper {}
+        debug_log!("access object #{}: {:?}", record_id, node.payload);
 
-        self.insert_transaction(object, mbr, &mut DefaultHelper)
+        handler(&node.payload, &node.mbr)
     }
 
-    pub fn insert_transaction(
-        &self,
-        object: ObjectT,
-        mbr: MBR<CoordT>,
-        helper: &mut impl InsertHandler<CoordT, ObjectT>,
-    ) -> NodeId {
-        let mut obj_space = self.obj_space.write().unwrap();
-        assert_eq!(mbr.dimension(), obj_space.dimension, "unexpected dimension");
+    /// Like `access_object`, but the payload can be changed under the write lock.
+    ///
+    /// The MBR is read-only: changing it without fixing the tree up would corrupt the tree.
+    /// Use `update` to move the object.
+    pub fn access_object_mut<H, R>(&self, record_id: NodeId, mut handler: H) -> R
+    where
+        H: FnMut(&mut ObjectT, &MBR<CoordT>) -> R,
+    {
+        let mut obj_space = self.obj_space.write();
+        let node = obj_space.get_data_mut(record_id);
 
-        let new_object_id = RecordId::Data(obj_space.make_data_node(object, mbr));
-        let new_object_node_id = new_object_id.as_node_id();
+        debug_log!("access object #{} mutably: {:?}", record_id, node.payload);
 
-        helper.before_insert(&*obj_space, new_object_node_id);
+        handler(&mut node.payload, &node.mbr)
+    }
 
-        Self::insert_helper(&mut obj_space, new_object_id, |node_id, _| {
-            matches![node_id, RecordId::Leaf(_)]
-        });
+    /// Replaces the object's payload, its MBR and the tree structure stay untouched.
+    ///
+    /// Returns the old payload or `None` if the object is removed.
+    pub fn set_payload(&self, id: NodeId, object: ObjectT) -> Option<ObjectT> {
+        let mut obj_space = self.obj_space.write();
 
-        helper.after_insert(&*obj_space, new_object_node_id);
+        if obj_space.is_removed(&id) {
+            return None;
+        }
 
-        new_object_node_id
+        debug_log!("set payload of object #{}: {:?}", id, object);
+
+        Some(std::mem::replace(
+            obj_space.get_data_payload_mut(id),
+            object,
+        ))
     }
 
-    pub fn mark_as_removed<I: Iterator<Item = NodeId>>(&self, data_ids: I) {
-        self.obj_space.write().unwrap().mark_as_removed(data_ids);
+    /// Calls `f` for every object of the tree, objects marked as removed are skipped.
+    pub fn for_each_object<F>(&self, mut f: F)
+    where
+        F: FnMut(NodeId, &ObjectT, &MBR<CoordT>),
+    {
+        let obj_space = self.obj_space.read();
+
+        obj_space
+            .iter()
+            .filter(|(id, ..)| !obj_space.is_removed(id))
+            .for_each(|(id, object, mbr)| f(id, object, mbr));
     }
 
-    pub fn restore_removed(&self) {
-        self.obj_space.write().unwrap().restore_removed();
+    pub fn visit<V: Visitor<CoordT, ObjectT>>(&self, visitor: &mut V) {
+        self.visit_with_depth(visitor);
     }
 
-    fn insert_helper<P>(obj_space: &mut obj_space![], insert_node_id: RecordId, predicate: P)
-    where
-        P: FnMut(RecordId, usize) -> bool,
-    {
-        let mbr = obj_space.get_mbr(insert_node_id).clone();
-        debug_log!("insert {:?} with {}", insert_node_id, mbr);
+    /// Like `visit`, but starts from the `start` record instead of the root.
+    ///
+    /// `RecordId::Root` means the tree root. If `start` is a data record,
+    /// only `visit_data` is called for it.
+    pub fn visit_from<V: Visitor<CoordT, ObjectT>>(&self, visitor: &mut V, start: RecordId) {
+        let obj_space = self.obj_space.read();
+
+        let start = match start {
+            RecordId::Root => {
+                if obj_space.is_empty() {
+                    return;
+                }
 
-        let max_records = obj_space.max_records;
+                obj_space.root_id
+            }
+            _ => start,
+        };
 
-        let node_id = Self::select_node(obj_space, &mbr, predicate);
+        Self::visit_helper(&obj_space, visitor, start, 0);
+    }
 
+    /// Visits the tree level by level using a queue instead of the recursion.
+    ///
+    /// `enter_node` is called when the node is taken from the queue,
+    /// so the nodes are grouped by the levels and the data are visited last.
+    /// `leave_node` is never called in this mode.
+    pub fn visit_bfs<V: Visitor<CoordT, ObjectT>>(&self, visitor: &mut V) {
+        let obj_space = self.obj_space.read();
+
+        if obj_space.is_empty() {
+            return;
+        }
+
+        let mut queue = VecDeque::new();
+        queue.push_back(obj_space.root_id);
+
+        while let Some(id) = queue.pop_front() {
+            match id {
+                RecordId::Data(data_id) => visitor.visit_data(id, obj_space.get_data(data_id)),
+                _ => {
+                    let node = obj_space.get_node(id);
+                    visitor.enter_node(id, node);
+                    queue.extend(node.payload.iter().cloned());
+                }
+            }
+        }
+    }
+
+    /// Like `visit`, but only the nodes and the data intersecting the `area` are visited.
+    ///
+    /// The visited data are the same as found by `search`.
+    pub fn visit_in_area<V: Visitor<CoordT, ObjectT>>(&self, visitor: &mut V, area: &MBR<CoordT>) {
+        let obj_space = self.obj_space.read();
+
+        if obj_space.is_empty() || !mbr::intersects(obj_space.get_root_mbr(), area) {
+            return;
+        }
+
+        Self::visit_in_area_helper(&obj_space, visitor, obj_space.root_id, area);
+    }
+
+    fn visit_in_area_helper<V: Visitor<CoordT, ObjectT>>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        visitor: &mut V,
+        id: RecordId,
+        area: &MBR<CoordT>,
+    ) {
+        match id {
+            RecordId::Data(data_id) => visitor.visit_data(id, obj_space.get_data(data_id)),
+            _ => {
+                let node = obj_space.get_node(id);
+                visitor.enter_node(id, node);
+                node.payload
+                    .iter()
+                    .filter(filter_intersections!(area in obj_space))
+                    .for_each(|&child_id| {
+                        Self::visit_in_area_helper(obj_space, visitor, child_id, area);
+                    });
+                visitor.leave_node(id, node);
+            }
+        }
+    }
+
+    /// Like `visit`, but the visitor also gets the depth of every record.
+    pub fn visit_with_depth<V: DepthVisitor<CoordT, ObjectT>>(&self, visitor: &mut V) {
+        let obj_space = self.obj_space.read();
+
+        if obj_space.is_empty() {
+            return;
+        }
+
+        Self::visit_helper(&obj_space, visitor, obj_space.root_id, 0);
+    }
+
+    /// Calls `f` for every leaf, i.e. for every node which children are the data.
+    pub fn for_each_leaf<F: FnMut(RecordId, &InternalNode<CoordT>)>(&self, mut f: F) {
+        let obj_space = self.obj_space.read();
+
+        if obj_space.is_empty() {
+            return;
+        }
+
+        Self::for_each_leaf_helper(&obj_space, obj_space.root_id, &mut f);
+    }
+
+    fn for_each_leaf_helper<F: FnMut(RecordId, &InternalNode<CoordT>)>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        id: RecordId,
+        f: &mut F,
+    ) {
+        let node = obj_space.get_node(id);
+
+        match id {
+            RecordId::Leaf(_) => f(id, node),
+            _ => node.payload.iter().for_each(|&child_id| {
+                Self::for_each_leaf_helper(obj_space, child_id, f);
+            }),
+        }
+    }
+
+    pub fn search(&self, area: &MBR<CoordT>) -> Vec<NodeId> {
+        let obj_space = self.obj_space.read();
+
+        Self::search_obj_space(&obj_space, area)
+    }
+
+    /// Like `search`, but returns `None` instead of waiting
+    /// if the tree is locked for writing at the moment.
+    pub fn try_search(&self, area: &MBR<CoordT>) -> Option<Vec<NodeId>> {
+        let obj_space = self.obj_space.try_read()?;
+
+        Some(Self::search_obj_space(&obj_space, area))
+    }
+
+    fn search_obj_space(obj_space: &ObjSpace<CoordT, ObjectT>, area: &MBR<CoordT>) -> Vec<NodeId> {
+        let mut result = vec![];
+
+        debug_log!("search in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(obj_space, root_id, area, &mut |_, &rec_id| {
+            result.push(rec_id.as_node_id())
+        });
+
+        debug_log!("search result in area {} -- {:?}", area, result);
+
+        result
+    }
+
+    /// Like `search`, but the subtrees are searched in parallel with rayon.
+    ///
+    /// The read lock on the tree is held until the search is completed.
+    #[cfg(feature = "rayon")]
+    pub fn par_search(&self, area: &MBR<CoordT>) -> Vec<NodeId>
+    where
+        CoordT: Send + Sync,
+        ObjectT: Send + Sync,
+    {
+        let obj_space = self.obj_space.read();
+
+        debug_log!("parallel search in area {}", area);
+
+        let result = if obj_space.is_empty() {
+            vec![]
+        } else {
+            Self::par_search_helper(&obj_space, obj_space.root_id, area)
+        };
+
+        debug_log!("parallel search result in area {} -- {:?}", area, result);
+
+        result
+    }
+
+    /// Returns IDs of the objects fully inside the `area` (bounds are inclusive).
+    ///
+    /// Nodes are still pruned by intersection, since a node partially outside the `area`
+    /// can contain objects which are fully inside.
+    pub fn search_contained(&self, area: &MBR<CoordT>) -> Vec<NodeId> {
+        let obj_space = self.obj_space.read();
+        let mut result = vec![];
+
+        debug_log!("search contained in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            if area.contains(obj_space.get_mbr(rec_id)) {
+                result.push(rec_id.as_node_id())
+            }
+        });
+
+        debug_log!("search contained result in area {} -- {:?}", area, result);
+
+        result
+    }
+
+    /// Returns IDs of the objects whose MBR contains the `point` (bounds are inclusive).
+    pub fn search_containing_point(&self, point: &[CoordT]) -> Vec<NodeId> {
+        let obj_space = self.obj_space.read();
+        assert_eq!(point.len(), obj_space.dimension, "unexpected dimension");
+
+        let mut result = vec![];
+
+        debug_log!("search containing point {:?}", point);
+
+        let root_id = obj_space.root_id;
+        Self::search_by_helper(
+            &obj_space,
+            root_id,
+            &|mbr| mbr.contains_point(point),
+            &mut |_, &rec_id| result.push(rec_id.as_node_id()),
+        );
+
+        debug_log!("search containing point {:?} -- {:?}", point, result);
+
+        result
+    }
+
+    /// Returns IDs of the objects whose MBR is not farther than `radius` from the `point`.
+    ///
+    /// The distance is measured like in `mbr::min_distance`,
+    /// so a `radius` of 0 selects only the objects containing the `point`.
+    pub fn search_within(&self, point: &[CoordT], radius: f64) -> Vec<NodeId> {
+        let obj_space = self.obj_space.read();
+        assert_eq!(point.len(), obj_space.dimension, "unexpected dimension");
+
+        let mut result = vec![];
+
+        debug_log!("search within {} from {:?}", radius, point);
+
+        let point_mbr = Self::point_mbr(point);
+
+        let root_id = obj_space.root_id;
+        Self::search_by_helper(
+            &obj_space,
+            root_id,
+            &|mbr| mbr::min_distance(&point_mbr, mbr) <= radius,
+            &mut |_, &rec_id| result.push(rec_id.as_node_id()),
+        );
+
+        debug_log!("search within {} from {:?} -- {:?}", radius, point, result);
+
+        result
+    }
+
+    /// Returns IDs of the objects crossed by the ray `origin + t * dir` (`t >= 0`).
+    ///
+    /// See `MBR::intersects_ray`.
+    pub fn search_ray(&self, origin: &[CoordT], dir: &[CoordT]) -> Vec<NodeId> {
+        let obj_space = self.obj_space.read();
+        let mut result = vec![];
+
+        debug_log!("search ray from {:?} in direction {:?}", origin, dir);
+
+        let root_id = obj_space.root_id;
+        Self::search_by_helper(
+            &obj_space,
+            root_id,
+            &|mbr| mbr.intersects_ray(origin, dir),
+            &mut |_, &rec_id| result.push(rec_id.as_node_id()),
+        );
+
+        debug_log!(
+            "search ray from {:?} in direction {:?} -- {:?}",
+            origin,
+            dir,
+            result
+        );
+
+        result
+    }
+
+    /// Returns IDs of the objects intersecting the `area` with their distances to the `from` point,
+    /// sorted ascending by distance. Equal distances are ordered by ID.
+    ///
+    /// The distance is measured like in `mbr::min_distance`.
+    pub fn search_sorted(&self, area: &MBR<CoordT>, from: &[CoordT]) -> Vec<(NodeId, f64)> {
+        let obj_space = self.obj_space.read();
+        assert_eq!(from.len(), obj_space.dimension, "unexpected dimension");
+
+        let mut result = vec![];
+
+        debug_log!("search sorted in area {} from {:?}", area, from);
+
+        let from_mbr = Self::point_mbr(from);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            let distance = mbr::min_distance(&from_mbr, obj_space.get_mbr(rec_id));
+            result.push((rec_id.as_node_id(), distance))
+        });
+
+        result.sort_unstable_by(|(lhs_id, lhs_distance), (rhs_id, rhs_distance)| {
+            lhs_distance
+                .partial_cmp(rhs_distance)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| lhs_id.cmp(rhs_id))
+        });
+
+        debug_log!(
+            "search sorted in area {} from {:?} -- {:?}",
+            area,
+            from,
+            result
+        );
+
+        result
+    }
+
+    /// Checks if at least one object intersects the `area`, the search stops at the first hit.
+    pub fn any_in_area(&self, area: &MBR<CoordT>) -> bool {
+        let obj_space = self.obj_space.read();
+
+        let root_id = obj_space.root_id;
+        let flow =
+            Self::search_helper_while(
+                &obj_space,
+                root_id,
+                area,
+                &mut |_, _| ControlFlow::Break(()),
+            );
+
+        let found = flow.is_break();
+
+        debug_log!("any in area {} -- {}", area, found);
+
+        found
+    }
+
+    /// Returns all pairs of the objects whose MBRs intersect each other.
+    ///
+    /// Each unordered pair is returned once, the lesser ID goes first.
+    pub fn intersecting_pairs(&self) -> Vec<(NodeId, NodeId)> {
+        let obj_space = self.obj_space.read();
+        let mut result = vec![];
+
+        debug_log!("search intersecting pairs");
+
+        if !obj_space.is_empty() {
+            Self::self_join_helper(&obj_space, obj_space.root_id, &mut result);
+        }
+
+        debug_log!("intersecting pairs -- {:?}", result);
+
+        result
+    }
+
+    /// Counts objects intersecting the `area` without collecting their IDs.
+    pub fn search_count(&self, area: &MBR<CoordT>) -> usize {
+        let obj_space = self.obj_space.read();
+        let mut count = 0;
+
+        debug_log!("search count in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |_, _| count += 1);
+
+        debug_log!("search count in area {} -- {}", area, count);
+
+        count
+    }
+
+    /// Applies `f` to every object intersecting the `area` and collects the results.
+    ///
+    /// Unlike `search` followed by `access_object`, the read lock is taken only once.
+    pub fn search_objects<R>(
+        &self,
+        area: &MBR<CoordT>,
+        f: impl Fn(&ObjectT, &MBR<CoordT>) -> R,
+    ) -> Vec<R> {
+        let obj_space = self.obj_space.read();
+        let mut result = vec![];
+
+        debug_log!("search objects in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            let node = obj_space.get_data(rec_id.as_node_id());
+            result.push(f(&node.payload, &node.mbr))
+        });
+
+        debug_log!("search objects in area {} -- {} found", area, result.len());
+
+        result
+    }
+
+    /// Returns IDs of the `k` objects whose MBRs are the nearest to the `point`,
+    /// ordered ascending by distance.
+    ///
+    /// If the tree contains less than `k` objects, all of them are returned.
+    pub fn nearest(&self, point: &[CoordT], k: usize) -> Vec<NodeId> {
+        self.nearest_by(point, k, Euclidean)
+    }
+
+    /// Like `nearest`, but the distance is measured with the given `metric`.
+    pub fn nearest_by<M: Metric<CoordT>>(
+        &self,
+        point: &[CoordT],
+        k: usize,
+        metric: M,
+    ) -> Vec<NodeId> {
+        debug_log!("search {} nearest to {:?}", k, point);
+
+        let result = self
+            .nearest_iter_by(point, metric)
+            .take(k)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+
+        debug_log!("{} nearest to {:?} -- {:?}", k, point, result);
+
+        result
+    }
+
+    /// Lazy version of `nearest`: yields objects with their distances to the `point`
+    /// in ascending order of distance, the tree is traversed only as far as the iterator is consumed.
+    pub fn nearest_iter(&self, point: &[CoordT]) -> NearestIter<'_, CoordT, ObjectT> {
+        self.nearest_iter_by(point, Euclidean)
+    }
+
+    /// Like `nearest_iter`, but the distance is measured with the given `metric`.
+    pub fn nearest_iter_by<M: Metric<CoordT>>(
+        &self,
+        point: &[CoordT],
+        metric: M,
+    ) -> NearestIter<'_, CoordT, ObjectT, M> {
+        let obj_space = self.obj_space.read();
+        assert_eq!(point.len(), obj_space.dimension, "unexpected dimension");
+
+        let mut queue = BinaryHeap::new();
+        if !obj_space.is_empty() {
+            queue.push(NearestCandidate {
+                distance: 0.0,
+                record_id: obj_space.root_id,
+            });
+        }
+
+        NearestIter {
+            obj_space,
+            point: point.to_vec(),
+            metric,
+            queue,
+        }
+    }
+
+    /// Lazy version of `search`: the tree is traversed only as far as the iterator is consumed.
+    pub fn search_iter(&self, area: &MBR<CoordT>) -> SearchIter<'_, CoordT, ObjectT> {
+        let obj_space = self.obj_space.read();
+
+        debug_log!("search iter in area {}", area);
+
+        let stack = if obj_space.is_empty() {
+            vec![]
+        } else {
+            vec![obj_space.root_id]
+        };
+
+        SearchIter {
+            obj_space,
+            area: area.clone(),
+            stack,
+        }
+    }
+
+    /// Like `search_access`, but the traversal stops as soon as the `handler` returns
+    /// `ControlFlow::Break`.
+    pub fn search_access_while<H>(&self, area: &MBR<CoordT>, mut handler: H)
+    where
+        H: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId) -> ControlFlow<()>,
+    {
+        let obj_space = self.obj_space.read();
+
+        debug_log!("search access while in area {}", area);
+
+        let root_id = obj_space.root_id;
+        let flow =
+            Self::search_helper_while(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+                handler(obj_space, rec_id.as_node_id())
+            });
+
+        debug_log!("search access while in area {} -- {:?}", area, flow);
+    }
+
+    pub fn search_access_obj_space<Handler>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        area: &MBR<CoordT>,
+        mut handler: Handler,
+    ) where
+        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId),
+    {
+        debug_log!("search access in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            handler(&*obj_space, rec_id.as_node_id());
+        });
+
+        debug_log!("search access in area {} -- COMPLETED", area);
+    }
+
+    pub fn search_access<H>(&self, area: &MBR<CoordT>, handler: H)
+    where
+        H: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId),
+    {
+        let obj_space = self.obj_space.read();
+
+        Self::search_access_obj_space(&*obj_space, area, handler);
+
+        // debug_log!("search access in area {}", area);
+
+        // let root_id = obj_space.root_id;
+        // Self::search_helper(
+        //     &obj_space,
+        //     root_id,
+        //     area,
+        //     &mut |obj_space, &rec_id| {
+        //         handler(&*obj_space, rec_id.as_node_id());
+        //     }
+        // );
+
+        // debug_log!("search access in area {} -- COMPLETED", area);
+    }
+
+    /// Counts leaves whose MBR intersects with `mbr`.
+    ///
+    /// Nothing is inserted, the count is an estimate of how many
+    /// leaf regions an insertion of an object with `mbr` would touch.
+    pub fn insert_impact(&self, mbr: &MBR<CoordT>) -> usize {
+        let obj_space = self.obj_space.read();
+        let mut impact = 0;
+
+        debug_log!("insert impact of {}", mbr);
+
+        let root_id = obj_space.root_id;
+        if mbr::intersects(obj_space.get_mbr(root_id), mbr) {
+            Self::search_leaves_helper(&obj_space, root_id, mbr, &mut |_, _| impact += 1);
+        }
+
+        debug_log!("insert impact of {} -- {}", mbr, impact);
+
+        impact
+    }
+
+    /// Estimates the number of node accesses of a random query with the `query_volume`.
+    ///
+    /// The query is assumed to be a hypercube uniformly distributed over the root MBR.
+    /// The estimate is the sum over all tree nodes of the probability that the query
+    /// intersects the node MBR, i.e. the product over axes of
+    /// `(node_length + query_length) / space_length` clamped to `1.0`.
+    pub fn expected_node_accesses(&self, query_volume: CoordT) -> f64 {
+        let obj_space = self.obj_space.read();
+
+        if obj_space.is_empty() {
+            return 0.0;
+        }
+
+        let to_f64 = |value: &CoordT| {
+            value
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64")
+        };
+
+        let dimension = obj_space.dimension;
+        let query_length = to_f64(&query_volume).powf(1.0 / dimension as f64);
+        let root_mbr = obj_space.get_root_mbr();
+
+        let mut accesses = 0.0;
+
+        let root_id = obj_space.root_id;
+        Self::visit_nodes_helper(&obj_space, root_id, &mut |obj_space, node_id| {
+            let mbr = obj_space.get_mbr(node_id);
+
+            accesses += (0..dimension)
+                .map(|axis| {
+                    let space_length = to_f64(&root_mbr.bounds(axis).length());
+                    if space_length == 0.0 {
+                        return 1.0;
+                    }
+
+                    let node_length = to_f64(&mbr.bounds(axis).length());
+
+                    ((node_length + query_length) / space_length).min(1.0)
+                })
+                .product::<f64>();
+        });
+
+        debug_log!(
+            "expected node accesses for query volume {:?} -- {}",
+            query_volume,
+            accesses
+        );
+
+        accesses
+    }
+
+    /// Counts objects per cell of a regular grid with the given `cell_size` per axis.
+    ///
+    /// Each object is assigned to exactly one cell: the cell containing its MBR center,
+    /// so an object spanning multiple cells is counted only once.
+    /// A cell with index `i` on axis `a` covers `[i * cell_size[a]; (i + 1) * cell_size[a])`.
+    pub fn grid_histogram(&self, cell_size: &[CoordT]) -> HashMap<Vec<i64>, usize> {
+        let obj_space = self.obj_space.read();
+        assert_eq!(cell_size.len(), obj_space.dimension, "unexpected dimension");
+
+        let to_f64 = |value: &CoordT| {
+            value
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64")
+        };
+
+        let cell_size = cell_size.iter().map(to_f64).collect::<Vec<_>>();
+        let mut histogram = HashMap::new();
+
+        obj_space.iter().for_each(|(_, _, mbr)| {
+            let cell = cell_size
+                .iter()
+                .enumerate()
+                .map(|(axis, cell_size)| {
+                    let bounds = mbr.bounds(axis);
+                    let center = (to_f64(&bounds.min) + to_f64(&bounds.max)) / 2.0;
+
+                    (center / cell_size).floor() as i64
+                })
+                .collect::<Vec<_>>();
+
+            *histogram.entry(cell).or_insert(0) += 1;
+        });
+
+        histogram
+    }
+
+    /// Returns IDs of the objects whose MBR aspect ratio exceeds `max_aspect_ratio`.
+    ///
+    /// See `MBR::aspect_ratio` for the degenerate cases.
+    pub fn find_slivers(&self, max_aspect_ratio: f64) -> Vec<NodeId> {
+        let obj_space = self.obj_space.read();
+
+        debug_log!("find slivers with max aspect ratio {}", max_aspect_ratio);
+
+        let result = obj_space
+            .iter()
+            .filter(|(_, _, mbr)| mbr.aspect_ratio() > max_aspect_ratio)
+            .map(|(id, ..)| id)
+            .collect::<Vec<_>>();
+
+        debug_log!(
+            "slivers with max aspect ratio {} -- {:?}",
+            max_aspect_ratio,
+            result
+        );
+
+        result
+    }
+
+    /// Counts objects intersecting the `area` which satisfy the `predicate`.
+    ///
+    /// Read-only counterpart of `retain`.
+    pub fn count_matching<P>(&self, area: &MBR<CoordT>, mut predicate: P) -> usize
+    where
+        P: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId) -> bool,
+    {
+        let obj_space = self.obj_space.read();
+        let mut count = 0;
+
+        debug_log!("count matching in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            if predicate(obj_space, rec_id.as_node_id()) {
+                count += 1;
+            }
+        });
+
+        debug_log!("count matching in area {} -- {}", area, count);
+
+        count
+    }
+
+    /// Marks the objects intersecting the `area` which don't satisfy the `predicate` as removed.
+    ///
+    /// Returns IDs of the removed objects.
+    pub fn retain<P>(&self, area: &MBR<CoordT>, mut predicate: P) -> Vec<NodeId>
+    where
+        P: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId) -> bool,
+    {
+        let mut obj_space = self.obj_space.write();
+        let mut remove_list = vec![];
+
+        debug_log!("retain in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            let data_id = rec_id.as_node_id();
+
+            if !predicate(&*obj_space, data_id) {
+                remove_list.push(data_id);
+            }
+        });
+
+        let removed_ids = obj_space.mark_as_removed(remove_list.into_iter());
+
+        debug_log!("retain in area {} -- COMPLETED", area);
+
+        removed_ids
+    }
+
+    /// Marks all the objects which satisfy the `predicate` as removed regardless of their location.
+    ///
+    /// Returns IDs of the removed objects.
+    pub fn remove_matching<P>(&self, mut predicate: P) -> Vec<NodeId>
+    where
+        P: FnMut(NodeId, &ObjectT, &MBR<CoordT>) -> bool,
+    {
+        let mut obj_space = self.obj_space.write();
+
+        debug_log!("remove matching");
+
+        let remove_list = obj_space
+            .iter()
+            .filter(|(id, ..)| !obj_space.is_removed(id))
+            .filter(|&(id, object, mbr)| predicate(id, object, mbr))
+            .map(|(id, ..)| id)
+            .collect::<Vec<_>>();
+
+        let removed_ids = obj_space.mark_as_removed(remove_list.into_iter());
+
+        debug_log!("remove matching -- {:?}", removed_ids);
+
+        removed_ids
+    }
+
+    /// Like `retain`, but the removed objects are also detached from the tree structure
+    /// and the underfull nodes are dissolved, see `remove`.
+    pub fn retain_condensed<P>(&self, area: &MBR<CoordT>, mut predicate: P) -> Vec<NodeId>
+    where
+        P: FnMut(&ObjSpace<CoordT, ObjectT>, NodeId) -> bool,
+    {
+        let mut obj_space = self.obj_space.write();
+        let mut remove_list = vec![];
+
+        debug_log!("retain condensed in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper(&obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            let data_id = rec_id.as_node_id();
+
+            if !predicate(obj_space, data_id) {
+                remove_list.push(data_id);
+            }
+        });
+
+        let removed_ids = obj_space.mark_as_removed(remove_list.into_iter());
+
+        for &data_id in removed_ids.iter() {
+            if obj_space.is_data_bound(data_id) {
+                Self::detach_data(&mut obj_space, data_id);
+            }
+        }
+
+        debug_log!("retain condensed in area {} -- COMPLETED", area);
+
+        removed_ids
+    }
+
+    /// Like `retain`, but the `predicate` can modify the objects.
+    pub fn retain_mut<P>(&self, area: &MBR<CoordT>, mut predicate: P) -> Vec<NodeId>
+    where
+        P: FnMut(&mut ObjSpace<CoordT, ObjectT>, NodeId) -> bool,
+    {
+        let mut obj_space = self.obj_space.write();
+        let mut remove_list = vec![];
+
+        debug_log!("retain mut in area {}", area);
+
+        let root_id = obj_space.root_id;
+        Self::search_helper_mut(&mut obj_space, root_id, area, &mut |obj_space, &rec_id| {
+            let data_id = rec_id.as_node_id();
+
+            if !predicate(&mut *obj_space, data_id) {
+                remove_list.push(data_id);
+            }
+        });
+
+        let removed_ids = obj_space.mark_as_removed(remove_list.into_iter());
+
+        debug_log!("retain mut in area {} -- COMPLETED", area);
+
+        removed_ids
+    }
+
+    /// The write lock is held until the tree is fixed up,
+    /// so the concurrent queries are blocked for the whole insertion.
+    ///
+    /// # Panics
+    /// Panics if the MBR dimension differs from the tree one, see `try_insert`.
+    pub fn insert(&self, object: ObjectT, mbr: MBR<CoordT>) -> NodeId {
+        match self.try_insert(object, mbr) {
+            Ok(id) => id,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Like `insert`, but returns an error instead of panicking
+    /// if the MBR dimension differs from the tree one. The tree stays unchanged in this case.
+    pub fn try_insert(&self, object: ObjectT, mbr: MBR<CoordT>) -> Result<NodeId, InsertError> {
+        let mut obj_space = self.obj_space.write();
+
+        let expected = obj_space.dimension;
+        let got = mbr.dimension();
+        if expected != got {
+            debug_log!("insert with {} refused: dimension mismatch", mbr);
+            return Err(InsertError::DimensionMismatch { expected, got });
+        }
+
+        Ok(Self::insert_obj_space(
+            &mut obj_space,
+            object,
+            mbr,
+            &mut DefaultInsertHandler,
+        ))
+    }
+
+    /// Inserts all the objects under the same write lock.
+    ///
+    /// If the tree is empty, it is built statically like by `rebuild`.
+    /// Returns IDs of the objects in the input order.
+    pub fn insert_many(
+        &self,
+        items: impl IntoIterator<Item = (ObjectT, MBR<CoordT>)>,
+    ) -> Vec<NodeId> {
+        let mut obj_space = self.obj_space.write();
+        let dimension = obj_space.dimension;
+        let was_empty = obj_space.is_empty();
+
+        let ids = items
+            .into_iter()
+            .map(|(object, mbr)| {
+                assert_eq!(mbr.dimension(), dimension, "unexpected dimension");
+                obj_space.make_data_node(object, mbr)
+            })
+            .collect::<Vec<_>>();
+
+        debug_log!("insert many: {} objects", ids.len());
+
+        if was_empty {
+            Self::rebuild_obj_space(&mut obj_space, INSERT_MANY_ALPHA, Self::split_groups);
+        } else {
+            for &id in ids.iter() {
+                Self::insert_helper(&mut obj_space, RecordId::Data(id), |node_id, _| {
+                    matches![node_id, RecordId::Leaf(_)]
+                });
+            }
+        }
+
+        debug_log!("[COMPLETED] insert many: {} objects", ids.len());
+
+        ids
+    }
+
+    /// Inserts the object only if the tree contains less than `cap` objects.
+    ///
+    /// The check and the insertion are performed under the same write lock.
+    pub fn insert_capped(
+        &self,
+        object: ObjectT,
+        mbr: MBR<CoordT>,
+        cap: usize,
+    ) -> Result<NodeId, CapExceeded> {
+        let mut obj_space = self.obj_space.write();
+
+        let len = obj_space.data_num();
+        if len >= cap {
+            debug_log!("insert with {} refused: cap = {}, len = {}", mbr, cap, len);
+            return Err(CapExceeded { cap, len });
+        }
+
+        Ok(Self::insert_obj_space(
+            &mut obj_space,
+            object,
+            mbr,
+            &mut DefaultInsertHandler,
+        ))
+    }
+
+    pub fn insert_transaction(
+        &self,
+        object: ObjectT,
+        mbr: MBR<CoordT>,
+        helper: &mut impl InsertHandler<CoordT, ObjectT>,
+    ) -> NodeId {
+        let mut obj_space = self.obj_space.write();
+
+        Self::insert_obj_space(&mut obj_space, object, mbr, helper)
+    }
+
+    /// Marks the objects as removed, see `ObjSpace::mark_as_removed`.
+    ///
+    /// Returns the IDs which were actually removed by this call.
+    pub fn mark_as_removed<I: Iterator<Item = NodeId>>(&self, data_ids: I) -> Vec<NodeId> {
+        self.obj_space.write().mark_as_removed(data_ids)
+    }
+
+    /// Restores all the objects marked as removed.
+    ///
+    /// The objects removed by `remove` stay removed.
+    /// Returns the number of the restored objects.
+    pub fn restore_removed(&self) -> usize {
+        self.restore_removed_ids().len()
+    }
+
+    /// Like `restore_removed`, but returns IDs of the restored objects.
+    ///
+    /// Restored objects which are no longer bound to the tree structure are inserted back
+    /// like by `restore_many`.
+    pub fn restore_removed_ids(&self) -> Vec<NodeId> {
+        let mut obj_space = self.obj_space.write();
+        let restored_ids = obj_space.restore_removed();
+
+        for &data_id in restored_ids.iter() {
+            Self::restore_helper(&mut obj_space, data_id);
+        }
+
+        debug_log!("restore removed -- {:?}", restored_ids);
+
+        restored_ids
+    }
+
+    /// Restores only the given removed objects.
+    ///
+    /// Restored objects which are no longer bound to the tree structure
+    /// (e.g. after `rebuild`) are inserted back.
+    pub fn restore_many<I: Iterator<Item = NodeId>>(&self, data_ids: I) {
+        let mut obj_space = self.obj_space.write();
+
+        for data_id in obj_space.restore_removed_ids(data_ids) {
+            Self::restore_helper(&mut obj_space, data_id);
+        }
+    }
+
+    /// Puts the restored object back into the tree structure.
+    fn restore_helper(obj_space: &mut obj_space![], data_id: NodeId) {
+        debug_log!("restore object #{}", data_id);
+
+        if obj_space.is_data_bound(data_id) {
+            // Ancestors MBRs could be tightened without the removed object.
+            Self::expand_ancestors_mbr(obj_space, RecordId::Data(data_id));
+        } else {
+            Self::insert_helper(obj_space, RecordId::Data(data_id), |node_id, _| {
+                matches![node_id, RecordId::Leaf(_)]
+            });
+        }
+    }
+
+    /// Recomputes MBRs of all the nodes without the removed objects.
+    ///
+    /// Leaves containing only the removed objects are dropped from the tree structure
+    /// like by `remove`, `restore_removed` and `restore_many` insert the objects back.
+    pub fn tighten(&self) {
+        let mut obj_space = self.obj_space.write();
+
+        debug_log!("tighten lr-tree");
+
+        // Such a leaf would get an undefined MBR.
+        while let Some(leaf_id) = Self::find_removed_leaf(&obj_space) {
+            debug_log!("drop {:?} with removed objects only", leaf_id);
+
+            let removed_ids = std::mem::take(&mut obj_space.get_node_mut(leaf_id).payload);
+            for removed_id in removed_ids {
+                obj_space.set_parent_info(removed_id, RecordId::Root);
+            }
+
+            Self::condense_tree(&mut obj_space, leaf_id);
+        }
+
+        let root_id = obj_space.root_id;
+        Self::tighten_helper(&mut obj_space, root_id);
+
+        debug_log!("tighten lr-tree -- {}", obj_space.get_root_mbr());
+    }
+
+    /// Removes the object from the tree and returns its payload.
+    ///
+    /// Unlike `mark_as_removed`, the object is detached from the tree structure:
+    /// nodes which have less than `min_records` children left are dissolved
+    /// and their objects are inserted back. The removed object can't be restored.
+    ///
+    /// Returns `None` if the object is already removed.
+    pub fn remove(&self, id: NodeId) -> Option<ObjectT> {
+        let mut obj_space = self.obj_space.write();
+
+        Self::remove_obj_space(&mut obj_space, id)
+    }
+
+    /// Sets the new MBR of the object keeping its ID.
+    ///
+    /// If the new MBR is inside the object's leaf MBR, only the MBRs are updated,
+    /// otherwise the object is detached from the tree structure and inserted back.
+    pub fn update(&self, id: NodeId, new_mbr: MBR<CoordT>) {
+        let mut obj_space = self.obj_space.write();
+        assert_eq!(
+            new_mbr.dimension(),
+            obj_space.dimension,
+            "unexpected dimension"
+        );
+
+        debug_log!("update object #{} with {}", id, new_mbr);
+
+        if !obj_space.is_data_bound(id) {
+            obj_space.set_data_mbr(id, new_mbr);
+            return;
+        }
+
+        let leaf_id = obj_space.get_data(id).parent_id;
+
+        if obj_space.get_mbr(leaf_id).contains(&new_mbr) {
+            obj_space.set_data_mbr(id, new_mbr);
+
+            let mut node_id = leaf_id;
+            while !matches![node_id, RecordId::Root] {
+                Self::shrink_node_mbr(&mut obj_space, node_id);
+                node_id = obj_space.get_node(node_id).parent_id;
+            }
+        } else {
+            Self::detach_data(&mut obj_space, id);
+
+            obj_space.set_data_mbr(id, new_mbr);
+            Self::insert_helper(&mut obj_space, RecordId::Data(id), |node_id, _| {
+                matches![node_id, RecordId::Leaf(_)]
+            });
+        }
+
+        debug_log!("[COMPLETED] update object #{}", id);
+    }
+
+    /// Drops the objects marked as removed from the storage and renumbers the others.
+    ///
+    /// Returns the map from the old IDs of the remaining objects to the new ones.
+    pub fn compact(&self) -> HashMap<NodeId, NodeId> {
+        let mut obj_space = self.obj_space.write();
+
+        debug_log!("compact lr-tree");
+
+        let mut removed_ids = vec![];
+        let root_id = obj_space.root_id;
+        Self::visit_nodes_helper(&obj_space, root_id, &mut |obj_space, node_id| {
+            if let RecordId::Leaf(_) = node_id {
+                removed_ids.extend(
+                    obj_space
+                        .get_node(node_id)
+                        .payload
+                        .iter()
+                        .map(|child_id| child_id.as_node_id())
+                        .filter(|id| obj_space.is_removed(id)),
+                );
+            }
+        });
+
+        for removed_id in removed_ids {
+            if obj_space.is_data_bound(removed_id) {
+                Self::detach_data(&mut obj_space, removed_id);
+            }
+        }
+
+        let id_map = obj_space.compact_data();
+
+        debug_log!("compact lr-tree -- {:?}", id_map);
+
+        id_map
+    }
+
+    /// Releases the memory left by the removed tree nodes without changing the objects IDs.
+    ///
+    /// The IDs of the tree nodes are kept too, so the `RecordId`s got before
+    /// (e.g. by `for_each_leaf`) stay valid.
+    /// The objects marked as removed stay in the storage, use `compact` to drop them.
+    pub fn shrink_to_fit(&self) {
+        let mut obj_space = self.obj_space.write();
+
+        debug_log!("shrink lr-tree to fit");
+
+        obj_space.shrink_to_fit();
+
+        debug_log!("[COMPLETED] shrink lr-tree to fit");
+    }
+
+    fn insert_obj_space(
+        obj_space: &mut obj_space![],
+        object: ObjectT,
+        mbr: MBR<CoordT>,
+        helper: &mut impl InsertHandler<CoordT, ObjectT>,
+    ) -> NodeId {
+        assert_eq!(mbr.dimension(), obj_space.dimension, "unexpected dimension");
+
+        let new_object_id = RecordId::Data(obj_space.make_data_node(object, mbr));
+        let new_object_node_id = new_object_id.as_node_id();
+
+        helper.before_insert(&*obj_space, new_object_node_id);
+
+        Self::insert_helper(obj_space, new_object_id, |node_id, _| {
+            matches![node_id, RecordId::Leaf(_)]
+        });
+
+        helper.after_insert(&*obj_space, new_object_node_id);
+
+        new_object_node_id
+    }
+
+    fn remove_obj_space(obj_space: &mut obj_space![], id: NodeId) -> Option<ObjectT> {
+        if obj_space.is_removed(&id) {
+            return None;
+        }
+
+        debug_log!("remove object #{}", id);
+
+        let object = obj_space.get_data_payload(id).clone();
+        let is_bound = obj_space.is_data_bound(id);
+
+        obj_space.delete_data(id);
+
+        if is_bound {
+            Self::detach_data(obj_space, id);
+        }
+
+        debug_log!("[COMPLETED] remove object #{}", id);
+
+        Some(object)
+    }
+
+    fn insert_helper<P>(obj_space: &mut obj_space![], insert_node_id: RecordId, mut predicate: P)
+    where
+        P: FnMut(RecordId, usize) -> bool,
+    {
+        let mut reinserted_levels = HashSet::new();
+
+        Self::insert_record(
+            obj_space,
+            insert_node_id,
+            &mut predicate,
+            &mut reinserted_levels,
+        );
+    }
+
+    fn insert_record(
+        obj_space: &mut obj_space![],
+        insert_node_id: RecordId,
+        predicate: &mut dyn FnMut(RecordId, usize) -> bool,
+        reinserted_levels: &mut HashSet<usize>,
+    ) {
+        let mbr = obj_space.get_mbr(insert_node_id).clone();
+        debug_log!("insert {:?} with {}", insert_node_id, mbr);
+
+        let max_records = obj_space.max_records;
+
+        let node_id = Self::select_node(obj_space, &mbr, predicate);
+
+        let mut reinsert_ids = vec![];
         let leaf = obj_space.get_node_mut(node_id);
         let extra_leaf_id = if leaf.payload.len() < max_records {
             bind!([obj_space] node_id => insert_node_id);
             None
         } else {
-            let extra_leaf_id = Self::split_node(obj_space, node_id, insert_node_id);
-            Some(extra_leaf_id)
+            Self::overflow_treatment(
+                obj_space,
+                node_id,
+                insert_node_id,
+                reinserted_levels,
+                &mut reinsert_ids,
+            )
         };
 
-        Self::fix_tree(obj_space, node_id, extra_leaf_id);
+        Self::fix_tree(
+            obj_space,
+            node_id,
+            extra_leaf_id,
+            reinserted_levels,
+            &mut reinsert_ids,
+        );
+
+        for (reinsert_id, level) in reinsert_ids {
+            let root_level = Self::node_level(obj_space, obj_space.root_id);
+
+            debug_log!("reinsert {:?} into level {}", reinsert_id, level);
+
+            Self::insert_record(
+                obj_space,
+                reinsert_id,
+                &mut |_, height| height == root_level - level,
+                reinserted_levels,
+            );
+        }
 
         let obj_node_id = insert_node_id.as_node_id();
         debug_log!(
@@ -380,36 +1955,9 @@ where
                 debug_log!("node for {} -- {:?}", mbr, node_id);
                 return node_id;
             } else {
-                node_id = *obj_space
-                    .get_node(node_id)
-                    .payload
-                    .iter()
-                    .map(|child_id| {
-                        let delta = mbr::mbr_delta(obj_space.get_mbr(*child_id), mbr);
+                let children = &obj_space.get_node(node_id).payload;
 
-                        debug_log!("{}, delta for {:?} = {:?}", mbr, child_id, delta);
-
-                        (child_id, delta)
-                    })
-                    .min_by(|lhs, rhs| {
-                        let (&lhs_id, lhs_delta) = lhs;
-                        let (&rhs_id, rhs_delta) = rhs;
-
-                        let ord = lhs_delta
-                            .partial_cmp(rhs_delta)
-                            .expect("cmp result is expected");
-
-                        match ord {
-                            Ordering::Equal => obj_space
-                                .get_mbr(lhs_id)
-                                .volume()
-                                .partial_cmp(&obj_space.get_mbr(rhs_id).volume())
-                                .expect("cmp result is expected"),
-                            _ => ord,
-                        }
-                    })
-                    .map(|(id, _)| id)
-                    .unwrap()
+                node_id = obj_space.choose_subtree.choose(obj_space, children, mbr);
             }
 
             height += 1;
@@ -420,6 +1968,8 @@ where
         obj_space: &mut obj_space![],
         mut node_id: RecordId,
         mut extra_node_id: Option<RecordId>,
+        reinserted_levels: &mut HashSet<usize>,
+        reinsert_ids: &mut Vec<(RecordId, usize)>,
     ) {
         debug_log!("fix tree");
 
@@ -428,68 +1978,501 @@ where
         while !matches![parent_node_id, RecordId::Root] {
             debug_log!("fix {:?}", node_id);
 
-            let parent_mbr = obj_space.get_mbr(parent_node_id);
-            let node_mbr = obj_space.get_mbr(node_id);
-            let fixed_parent_mbr = mbr::common_mbr(parent_mbr, node_mbr);
-            obj_space.set_mbr(parent_node_id, fixed_parent_mbr);
+            let parent_mbr = obj_space.get_mbr(parent_node_id);
+            let node_mbr = obj_space.get_mbr(node_id);
+            let fixed_parent_mbr = mbr::common_mbr(parent_mbr, node_mbr);
+            obj_space.set_mbr(parent_node_id, fixed_parent_mbr);
+
+            if let Some(new_node_id) = extra_node_id {
+                let parent = obj_space.get_node_mut(parent_node_id);
+
+                if parent.payload.len() < max_records {
+                    bind!([obj_space] parent_node_id => new_node_id);
+                    extra_node_id = None;
+                } else {
+                    extra_node_id = Self::overflow_treatment(
+                        obj_space,
+                        parent_node_id,
+                        new_node_id,
+                        reinserted_levels,
+                        reinsert_ids,
+                    );
+                }
+            }
+
+            node_id = parent_node_id;
+            parent_node_id = obj_space.get_node(node_id).parent_id;
+        }
+
+        if let Some(extra_node_id) = extra_node_id {
+            debug_log!("fix root {:?}", node_id);
+
+            let new_root_id = obj_space.make_node(RecordIdKind::Internal);
+            bind!([obj_space] new_root_id => node_id);
+            bind!([obj_space] new_root_id => extra_node_id);
+
+            obj_space.root_id = new_root_id;
+        }
+
+        debug_log!("[COMPLETED] fix tree");
+    }
+
+    /// Splits the overflowed node and returns the new one,
+    /// or takes the farthest children out to `reinsert_ids` according to `InsertPolicy`.
+    fn overflow_treatment(
+        obj_space: &mut obj_space![],
+        node_id: RecordId,
+        extra_child_id: RecordId,
+        reinserted_levels: &mut HashSet<usize>,
+        reinsert_ids: &mut Vec<(RecordId, usize)>,
+    ) -> Option<RecordId> {
+        let is_root = matches![obj_space.get_node(node_id).parent_id, RecordId::Root];
+
+        if let InsertPolicy::ForcedReinsert { count } = obj_space.insert_policy {
+            let level = Self::node_level(obj_space, node_id);
+
+            if !is_root && reinserted_levels.insert(level) {
+                debug_log!("forced reinsert from {:?}", node_id);
+
+                bind!([obj_space] node_id => extra_child_id);
+
+                let center = obj_space.get_mbr(node_id).center();
+                let center_distance = |mbr: &MBR<CoordT>| -> f64 {
+                    mbr.center()
+                        .into_iter()
+                        .zip(center.iter())
+                        .map(|(lhs, rhs)| {
+                            let delta = (lhs - rhs.clone())
+                                .to_f64()
+                                .expect("CoordT is expected to be convertible to f64");
+
+                            delta * delta
+                        })
+                        .sum()
+                };
+
+                let mut children = obj_space.get_node_mut(node_id).abort_children();
+                children.sort_by(|lhs, rhs| {
+                    center_distance(obj_space.get_mbr(*lhs))
+                        .partial_cmp(&center_distance(obj_space.get_mbr(*rhs)))
+                        .expect("cmp result is expected")
+                });
+
+                // The closest of the farthest children are reinserted first.
+                let farthest_ids = children.split_off(children.len() - count);
+                bind!([obj_space] node_id => set(children));
+
+                let mut ancestor_id = node_id;
+                while !matches![ancestor_id, RecordId::Root] {
+                    Self::shrink_node_mbr(obj_space, ancestor_id);
+                    ancestor_id = obj_space.get_node(ancestor_id).parent_id;
+                }
+
+                for &child_id in farthest_ids.iter() {
+                    obj_space.set_parent_info(child_id, RecordId::Root);
+                }
+
+                reinsert_ids.extend(farthest_ids.into_iter().map(|child_id| (child_id, level)));
+
+                return None;
+            }
+        }
+
+        Some(Self::split_node(obj_space, node_id, extra_child_id))
+    }
+
+    /// Number of the levels below the node: 0 for a leaf.
+    fn node_level(obj_space: &ObjSpace<CoordT, ObjectT>, mut node_id: RecordId) -> usize {
+        let mut level = 0;
+
+        while let RecordId::Internal(_) = node_id {
+            node_id = obj_space.get_node(node_id).payload[0];
+            level += 1;
+        }
+
+        level
+    }
+
+    /// Removes the data node from its leaf and condenses the tree.
+    fn detach_data(obj_space: &mut obj_space![], id: NodeId) {
+        let leaf_id = obj_space.get_data(id).parent_id;
+
+        obj_space
+            .get_node_mut(leaf_id)
+            .payload
+            .retain(|&child_id| child_id != RecordId::Data(id));
+        obj_space.set_parent_info(RecordId::Data(id), RecordId::Root);
+
+        Self::condense_tree(obj_space, leaf_id);
+    }
+
+    /// Dissolves underfull nodes on the path from the `node_id` to the root,
+    /// shrinks MBRs of the others and inserts the orphaned objects back.
+    fn condense_tree(obj_space: &mut obj_space![], mut node_id: RecordId) {
+        debug_log!("condense tree from {:?}", node_id);
+
+        let min_records = obj_space.min_records;
+        let mut orphaned_ids = vec![];
+
+        loop {
+            let parent_id = obj_space.get_node(node_id).parent_id;
+            if matches![parent_id, RecordId::Root] {
+                break;
+            }
+
+            if obj_space.get_node(node_id).payload.len() < min_records {
+                debug_log!("dissolve {:?}", node_id);
+
+                obj_space
+                    .get_node_mut(parent_id)
+                    .payload
+                    .retain(|&child_id| child_id != node_id);
+
+                Self::dissolve_subtree(obj_space, node_id, &mut orphaned_ids);
+            } else {
+                Self::shrink_node_mbr(obj_space, node_id);
+            }
+
+            node_id = parent_id;
+        }
+
+        Self::shrink_node_mbr(obj_space, node_id);
+
+        // The root is an internal node with a single child -- the child is the new root.
+        // The root without children at all is replaced with an empty leaf.
+        while let root_id @ RecordId::Internal(_) = obj_space.root_id {
+            let root = obj_space.get_node(root_id);
+            let new_root_id = match root.payload.len() {
+                0 => obj_space.make_node(RecordIdKind::Leaf),
+                1 => root.payload[0],
+                _ => break,
+            };
+
+            debug_log!("shorten tree: {:?} is the new root", new_root_id);
+
+            obj_space.free_node(root_id);
+            obj_space.set_parent_info(new_root_id, RecordId::Root);
+            obj_space.root_id = new_root_id;
+        }
+
+        for orphaned_id in orphaned_ids {
+            // Objects marked as removed are just dropped from the tree structure,
+            // `restore_removed` and `restore_many` insert them back if needed.
+            if obj_space.is_removed(&orphaned_id.as_node_id()) {
+                obj_space.set_parent_info(orphaned_id, RecordId::Root);
+                continue;
+            }
+
+            Self::insert_helper(obj_space, orphaned_id, |node_id, _| {
+                matches![node_id, RecordId::Leaf(_)]
+            });
+        }
+
+        debug_log!("[COMPLETED] condense tree");
+    }
+
+    /// Finds a non-empty leaf which children are all removed.
+    fn find_removed_leaf(obj_space: &ObjSpace<CoordT, ObjectT>) -> Option<RecordId> {
+        let mut removed_leaf_id = None;
+
+        let root_id = obj_space.root_id;
+        Self::visit_nodes_helper(obj_space, root_id, &mut |obj_space, id| {
+            let children = &obj_space.get_node(id).payload;
+
+            if matches!(id, RecordId::Leaf(_))
+                && !children.is_empty()
+                && children
+                    .iter()
+                    .all(|child_id| obj_space.is_removed(&child_id.as_node_id()))
+            {
+                removed_leaf_id.get_or_insert(id);
+            }
+        });
+
+        removed_leaf_id
+    }
+
+    /// Frees the node with all its descendants and collects the data of the subtree.
+    ///
+    /// Every descendant is freed, so no freed node keeps stale references to the data
+    /// which are inserted back.
+    fn dissolve_subtree(
+        obj_space: &mut obj_space![],
+        node_id: RecordId,
+        orphaned_ids: &mut Vec<RecordId>,
+    ) {
+        let mut dissolved_ids = vec![];
+        Self::visit_nodes_helper(obj_space, node_id, &mut |obj_space, id| {
+            if let RecordId::Leaf(_) = id {
+                orphaned_ids.extend(obj_space.get_node(id).payload.iter().cloned());
+            }
+
+            dissolved_ids.push(id);
+        });
+
+        for dissolved_id in dissolved_ids {
+            obj_space.free_node(dissolved_id);
+        }
+    }
+
+    fn tighten_helper(obj_space: &mut obj_space![], node_id: RecordId) {
+        let children = obj_space.get_node(node_id).payload.clone();
+
+        if let RecordId::Internal(_) = node_id {
+            for &child_id in children.iter() {
+                Self::tighten_helper(obj_space, child_id);
+            }
+        }
+
+        let mbr = mbr::common_mbr_from_iter(
+            children
+                .iter()
+                .filter(|child_id| match child_id {
+                    RecordId::Data(id) => !obj_space.is_removed(id),
+                    _ => true,
+                })
+                .map(|&child_id| obj_space.get_mbr(child_id)),
+        );
+
+        obj_space.set_mbr(node_id, mbr);
+    }
+
+    /// Expands MBRs of the record ancestors to cover the record.
+    fn expand_ancestors_mbr(obj_space: &mut obj_space![], mut record_id: RecordId) {
+        let mut parent_id = obj_space.get_parent_id(record_id);
+
+        while !matches![parent_id, RecordId::Root] {
+            let mbr = mbr::common_mbr(obj_space.get_mbr(parent_id), obj_space.get_mbr(record_id));
+            obj_space.set_mbr(parent_id, mbr);
+
+            record_id = parent_id;
+            parent_id = obj_space.get_parent_id(record_id);
+        }
+    }
+
+    fn shrink_node_mbr(obj_space: &mut obj_space![], node_id: RecordId) {
+        let mbr = mbr::common_mbr_from_iter(
+            obj_space
+                .get_node(node_id)
+                .payload
+                .iter()
+                .map(|&id| obj_space.get_mbr(id)),
+        );
+
+        obj_space.set_mbr(node_id, mbr);
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_search_helper(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        node_id: RecordId,
+        area: &MBR<CoordT>,
+    ) -> Vec<NodeId>
+    where
+        CoordT: Send + Sync,
+        ObjectT: Send + Sync,
+    {
+        let node = obj_space.get_node(node_id);
+        match node_id {
+            RecordId::Leaf(_) => node
+                .payload
+                .iter()
+                .filter(filter_intersections!(area in obj_space))
+                .map(|child_id| child_id.as_node_id())
+                .collect(),
+            _ => node
+                .payload
+                .par_iter()
+                .filter(filter_intersections!(area in obj_space))
+                .flat_map_iter(|&child_id| Self::par_search_helper(obj_space, child_id, area))
+                .collect(),
+        }
+    }
+
+    fn search_helper<Handler>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        node_id: RecordId,
+        area: &MBR<CoordT>,
+        handler: &mut Handler,
+    ) where
+        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, &RecordId),
+    {
+        if obj_space.is_empty() {
+            return;
+        }
+
+        let node = obj_space.get_node(node_id);
+        match node_id {
+            RecordId::Leaf(_) => node
+                .payload
+                .iter()
+                .filter(filter_intersections!(area in obj_space))
+                .for_each(|child_id| handler(obj_space, child_id)),
+            _ => node
+                .payload
+                .iter()
+                .filter(filter_intersections!(area in obj_space))
+                .for_each(|&child_id| {
+                    Self::search_helper(obj_space, child_id, area, handler);
+                }),
+        }
+    }
+
+    fn search_helper_while<Handler>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        node_id: RecordId,
+        area: &MBR<CoordT>,
+        handler: &mut Handler,
+    ) -> ControlFlow<()>
+    where
+        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, &RecordId) -> ControlFlow<()>,
+    {
+        if obj_space.is_empty() {
+            return ControlFlow::Continue(());
+        }
+
+        let node = obj_space.get_node(node_id);
+        let mut children = node
+            .payload
+            .iter()
+            .filter(filter_intersections!(area in obj_space));
+
+        match node_id {
+            RecordId::Leaf(_) => children.try_for_each(|child_id| handler(obj_space, child_id)),
+            _ => children.try_for_each(|&child_id| {
+                Self::search_helper_while(obj_space, child_id, area, handler)
+            }),
+        }
+    }
 
-            if let Some(new_node_id) = extra_node_id {
-                let parent = obj_space.get_node_mut(parent_node_id);
+    /// Collects the intersecting pairs of the objects inside the node subtree.
+    fn self_join_helper(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        node_id: RecordId,
+        result: &mut Vec<(NodeId, NodeId)>,
+    ) {
+        let children = &obj_space.get_node(node_id).payload;
 
-                if parent.payload.len() < max_records {
-                    bind!([obj_space] parent_node_id => new_node_id);
-                    extra_node_id = None;
-                } else {
-                    extra_node_id = Some(Self::split_node(obj_space, parent_node_id, new_node_id));
-                }
+        for (i, &lhs_id) in children.iter().enumerate() {
+            if !matches!(lhs_id, RecordId::Data(_)) {
+                Self::self_join_helper(obj_space, lhs_id, result);
             }
 
-            node_id = parent_node_id;
-            parent_node_id = obj_space.get_node(node_id).parent_id;
+            let lhs_mbr = obj_space.get_mbr(lhs_id);
+            children[i + 1..]
+                .iter()
+                .filter(|&&rhs_id| mbr::intersects(lhs_mbr, obj_space.get_mbr(rhs_id)))
+                .for_each(|&rhs_id| Self::join_helper(obj_space, lhs_id, rhs_id, result));
         }
+    }
 
-        if let Some(extra_node_id) = extra_node_id {
-            debug_log!("fix root {:?}", node_id);
-
-            let new_root_id = obj_space.make_node(RecordIdKind::Internal);
-            bind!([obj_space] new_root_id => node_id);
-            bind!([obj_space] new_root_id => extra_node_id);
+    /// Collects the intersecting pairs of the objects from two different subtrees.
+    /// The caller has to check that the subtrees MBRs intersect.
+    fn join_helper(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        lhs_id: RecordId,
+        rhs_id: RecordId,
+        result: &mut Vec<(NodeId, NodeId)>,
+    ) {
+        match (lhs_id, rhs_id) {
+            (RecordId::Data(lhs), RecordId::Data(rhs)) => {
+                result.push((std::cmp::min(lhs, rhs), std::cmp::max(lhs, rhs)))
+            }
+            (RecordId::Data(_), _) => Self::join_helper(obj_space, rhs_id, lhs_id, result),
+            _ => {
+                let rhs_mbr = obj_space.get_mbr(rhs_id);
 
-            obj_space.root_id = new_root_id;
+                obj_space
+                    .get_node(lhs_id)
+                    .payload
+                    .iter()
+                    .filter(|&&child_id| mbr::intersects(obj_space.get_mbr(child_id), rhs_mbr))
+                    .for_each(|&child_id| Self::join_helper(obj_space, rhs_id, child_id, result));
+            }
         }
+    }
 
-        debug_log!("[COMPLETED] fix tree");
+    fn visit_nodes_helper<Handler>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        node_id: RecordId,
+        handler: &mut Handler,
+    ) where
+        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, RecordId),
+    {
+        handler(obj_space, node_id);
+
+        if let RecordId::Internal(_) = node_id {
+            obj_space
+                .get_node(node_id)
+                .payload
+                .iter()
+                .for_each(|&child_id| Self::visit_nodes_helper(obj_space, child_id, handler));
+        }
     }
 
-    fn search_helper<Handler>(
+    fn search_leaves_helper<Handler>(
         obj_space: &ObjSpace<CoordT, ObjectT>,
         node_id: RecordId,
         area: &MBR<CoordT>,
         handler: &mut Handler,
     ) where
-        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, &RecordId),
+        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, RecordId),
     {
         if obj_space.is_empty() {
             return;
         }
 
-        let node = obj_space.get_node(node_id);
         match node_id {
-            RecordId::Leaf(_) => node
-                .payload
-                .iter()
-                .filter(filter_intersections!(area in obj_space))
-                .for_each(|child_id| handler(obj_space, child_id)),
-            _ => node
+            RecordId::Leaf(_) => handler(obj_space, node_id),
+            _ => obj_space
+                .get_node(node_id)
                 .payload
                 .iter()
                 .filter(filter_intersections!(area in obj_space))
                 .for_each(|&child_id| {
-                    Self::search_helper(obj_space, child_id, area, handler);
+                    Self::search_leaves_helper(obj_space, child_id, area, handler);
                 }),
         }
     }
 
+    fn point_mbr(point: &[CoordT]) -> MBR<CoordT> {
+        MBR::new(
+            point
+                .iter()
+                .map(|coord| Bounds::new(coord.clone(), coord.clone()))
+                .collect(),
+        )
+    }
+
+    /// Like `search_helper`, but prunes the records which don't satisfy the `predicate`
+    /// instead of the ones which don't intersect an area.
+    fn search_by_helper<Predicate, Handler>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        node_id: RecordId,
+        predicate: &Predicate,
+        handler: &mut Handler,
+    ) where
+        Predicate: Fn(&MBR<CoordT>) -> bool,
+        Handler: FnMut(&ObjSpace<CoordT, ObjectT>, &RecordId),
+    {
+        if obj_space.is_empty() {
+            return;
+        }
+
+        let node = obj_space.get_node(node_id);
+        let children = node
+            .payload
+            .iter()
+            .filter(|&&child_id| predicate(obj_space.get_mbr(child_id)));
+
+        match node_id {
+            RecordId::Leaf(_) => children.for_each(|child_id| handler(obj_space, child_id)),
+            _ => children.for_each(|&child_id| {
+                Self::search_by_helper(obj_space, child_id, predicate, handler);
+            }),
+        }
+    }
+
     fn search_helper_mut<Handler>(
         obj_space: &mut ObjSpace<CoordT, ObjectT>,
         node_id: RecordId,
@@ -528,168 +2511,132 @@ where
     ) -> RecordId {
         debug_log!("split {:?}", node_id);
 
-        let dimension = obj_space.dimension;
-
         let mut children = obj_space.get_node_mut(node_id).abort_children();
         children.push(extra_child_id);
 
         let children_len = children.len();
 
-        let (lhs, rhs) = Self::select_first_pair(obj_space, &mut children, dimension);
-        debug_log!("select first pair = ({:?}, {:?})", lhs, rhs);
-
-        bind!([obj_space] node_id => lhs);
-
-        let new_node_id = obj_space.make_node(node_id.kind());
-
-        bind!([obj_space] new_node_id => rhs);
-
-        let mut node_num = 1;
-        let mut new_node_num = 1;
-        while !children.is_empty() {
-            let num = children.len();
-            if obj_space.min_records.saturating_sub(node_num) >= num {
-                bind!([obj_space] node_id => set(children));
-                break;
-            }
-
-            if obj_space.min_records.saturating_sub(new_node_num) >= num {
-                bind!([obj_space] new_node_id => set(children));
-                break;
-            }
+        let (lhs, rhs) = obj_space.split_strategy.split(obj_space, &mut children);
 
-            let rec_id = children.pop().unwrap();
-            let rec_mbr = obj_space.get_mbr(rec_id);
-            let mbr = obj_space.get_mbr(node_id);
-            let new_mbr = obj_space.get_mbr(new_node_id);
+        assert_eq!(
+            lhs.len() + rhs.len(),
+            children_len,
+            "Two nodes after split must contain all old nodes + the new one"
+        );
+        assert!(
+            !lhs.is_empty() && !rhs.is_empty(),
+            "Two nodes after split must not be empty"
+        );
 
-            let mbr_volume = mbr.volume();
-            let new_mbr_volume = new_mbr.volume();
+        for child_id in lhs {
+            bind!([obj_space] node_id => child_id);
+        }
 
-            let delta = mbr::common_mbr(mbr, rec_mbr).volume() - mbr_volume;
-            let new_delta = mbr::common_mbr(new_mbr, rec_mbr).volume() - new_mbr_volume;
+        let new_node_id = obj_space.make_node(node_id.kind());
 
-            if delta < new_delta || delta == new_delta && node_num < new_node_num {
-                bind!([obj_space] node_id => rec_id);
-                node_num += 1;
-            } else {
-                bind!([obj_space] new_node_id => rec_id);
-                new_node_num += 1;
-            }
+        for child_id in rhs {
+            bind!([obj_space] new_node_id => child_id);
         }
 
-        debug_assert_eq!(
-            obj_space.get_node(node_id).payload.len()
-                + obj_space.get_node(new_node_id).payload.len(),
-            children_len,
-            "Two nodes after split must contain all old nodes + the new one"
-        );
-
         debug_log!("[COMPLETED] split {:?}", node_id);
         new_node_id
     }
 
-    fn select_first_pair(
-        obj_space: &mut obj_space![],
-        records: &mut Vec<RecordId>,
-        dimension: usize,
-    ) -> (RecordId, RecordId) {
-        let params = (0..dimension)
-            .map(|dim| (dim, records.iter()))
-            .map(|(dim, mut records)| {
-                let first_id = records.next().unwrap();
-                let bounds = obj_space.get_mbr(*first_id).bounds(dim);
-
-                let mut min = bounds.min.clone();
-                let mut max = bounds.min.clone();
-
-                let mut max_low_idx = 0;
-                let mut max_low_id = first_id;
-                let mut max_low = min.clone();
-
-                let mut min_high_idx = 0;
-                let mut min_high_id = first_id;
-                let mut min_high = max.clone();
-
-                records
-                    .enumerate()
-                    .map(|(index, id)| {
-                        // We skipped one element, but we need an index for a whole vector
-                        (index + 1, id)
-                    })
-                    .for_each(|(index, id)| {
-                        let bounds = obj_space.get_mbr(*id).bounds(dim);
-
-                        if bounds.min > max_low {
-                            max_low_idx = index;
-                            max_low_id = id;
-                            max_low = bounds.min.clone();
-                        } else if bounds.max < min_high {
-                            min_high_idx = index;
-                            min_high_id = id;
-                            min_high = bounds.max.clone();
-                        }
-
-                        if bounds.max > max {
-                            max = bounds.max.clone();
-                        }
-
-                        if bounds.min < min {
-                            min = bounds.min.clone();
-                        }
-                    });
-
-                let length = max - min;
-                let d = (min_high - max_low) / length;
+    fn visit_helper<V: DepthVisitor<CoordT, ObjectT>>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        visitor: &mut V,
+        id: RecordId,
+        depth: usize,
+    ) {
+        match id {
+            RecordId::Data(data_id) => visitor.visit_data(id, obj_space.get_data(data_id), depth),
+            _ => {
+                let node = obj_space.get_node(id);
+                visitor.enter_node(id, node, depth);
+                node.payload.iter().for_each(|&child_id| {
+                    Self::visit_helper(obj_space, visitor, child_id, depth + 1);
+                });
+                visitor.leave_node(id, node, depth);
+            }
+        }
+    }
 
-                (d, *max_low_id, *min_high_id, max_low_idx, min_high_idx)
-            })
-            .min_by(|(d_lhs, ..), (d_rhs, ..)| {
-                d_lhs.partial_cmp(d_rhs).expect("cmp result expected")
+    /// Packs the records into new nodes of the `kind` and returns the nodes IDs.
+    fn str_pack(
+        obj_space: &mut ObjSpace<CoordT, ObjectT>,
+        ids: Vec<RecordId>,
+        kind: RecordIdKind,
+    ) -> Vec<RecordId> {
+        Self::str_tile(obj_space, ids, 0)
+            .into_iter()
+            .map(|mut run| {
+                let node_id = obj_space.make_node(kind);
+                bind!([obj_space] node_id => set(run));
+
+                node_id
             })
-            .unwrap();
+            .collect()
+    }
+
+    /// Splits the records into runs of at most `max_records`:
+    /// the records are sorted along the `axis` and cut into slices,
+    /// then every slice is tiled along the next axes.
+    fn str_tile(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        mut ids: Vec<RecordId>,
+        axis: usize,
+    ) -> Vec<Vec<RecordId>> {
+        let max_records = obj_space.max_records;
+        let min_records = obj_space.min_records;
 
-        let (_, mut lhs, mut rhs, mut lhs_idx, mut rhs_idx) = params;
+        ids.sort_by(|lhs, rhs| {
+            let lhs = obj_space.get_mbr(*lhs).bounds(axis).midpoint();
+            let rhs = obj_space.get_mbr(*rhs).bounds(axis).midpoint();
 
-        match rhs_idx.cmp(&lhs_idx) {
-            Ordering::Greater => std::mem::swap(&mut lhs_idx, &mut rhs_idx),
-            Ordering::Equal => {
-                // they are not separated - arbitrarily choose the first and the last
-                lhs_idx = records.len() - 1;
-                rhs_idx = 0;
+            lhs.partial_cmp(&rhs).expect("cmp result is expected")
+        });
 
-                lhs = records[lhs_idx];
-                rhs = records[rhs_idx];
+        let rest_axes_num = obj_space.dimension - axis;
+        if rest_axes_num == 1 {
+            let mut runs = ids
+                .chunks(max_records)
+                .map(<[_]>::to_vec)
+                .collect::<Vec<_>>();
+
+            // The last run borrows records from the previous one if it is underfull.
+            if let [.., previous, last] = runs.as_mut_slice() {
+                if last.len() < min_records {
+                    let borrowed_num = min_records - last.len();
+                    let borrowed = previous.split_off(previous.len() - borrowed_num);
+                    last.splice(0..0, borrowed);
+                }
             }
-            _ => {}
-        }
 
-        records.swap_remove(lhs_idx);
-        records.swap_remove(rhs_idx);
+            return runs;
+        }
 
-        (lhs, rhs)
-    }
+        let node_num = ids.len().div_ceil(max_records);
+        let slice_num = (node_num as f64).powf(1.0 / rest_axes_num as f64).ceil() as usize;
+        let slice_len = node_num.div_ceil(slice_num) * max_records;
 
-    fn visit_helper<V: Visitor<CoordT, ObjectT>>(&self, visitor: &mut V, id: RecordId) {
-        match id {
-            RecordId::Data(data_id) => {
-                visitor.visit_data(id, self.obj_space.read().unwrap().get_data(data_id))
-            }
-            _ => {
-                let obj_space = self.obj_space.read().unwrap();
-                let node = obj_space.get_node(id);
-                visitor.enter_node(id, node);
-                node.payload.iter().for_each(|&child_id| {
-                    self.visit_helper(visitor, child_id);
-                });
-                visitor.leave_node(id, node);
+        let mut slices = ids.chunks(slice_len).map(<[_]>::to_vec).collect::<Vec<_>>();
+        if let [.., previous, last] = slices.as_mut_slice() {
+            if last.len() < min_records {
+                previous.append(last);
+                slices.pop();
             }
         }
+
+        slices
+            .into_iter()
+            .flat_map(|slice| Self::str_tile(obj_space, slice, axis + 1))
+            .collect()
     }
 
     fn build_node(
         obj_space: &mut ObjSpace<CoordT, ObjectT>,
         alpha: f32,
+        split_groups: SplitGroups<CoordT, ObjectT>,
         node_id: RecordId,
         level: usize,
         unbinded_ids: &mut [RecordId],
@@ -714,7 +2661,7 @@ where
 
         let ids_num = unbinded_ids.len();
         let node_child_num = (ids_num as f64).powf(1.0 / (level + 1) as f64).ceil() as usize;
-        let groups = Self::split_groups(obj_space, alpha, node_child_num, level, unbinded_ids);
+        let groups = split_groups(obj_space, alpha, node_child_num, level, unbinded_ids);
 
         for (group, mbr) in groups {
             let new_node_id = obj_space.make_node_with_mbr(new_node_id_kind, mbr);
@@ -724,21 +2671,69 @@ where
             }
 
             obj_space.set_parent_info(new_node_id, node_id);
-            Self::build_node(obj_space, alpha, new_node_id, level - 1, group);
+            Self::build_node(
+                obj_space,
+                alpha,
+                split_groups,
+                new_node_id,
+                level - 1,
+                group,
+            );
         }
     }
 
     fn split_groups<'ids>(
-        obj_space: &mut ObjSpace<CoordT, ObjectT>,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
         alpha: f32,
         node_child_num: usize,
         level: usize,
         unbinded_ids: &'ids mut [RecordId],
     ) -> Vec<NodeGroup<'ids, CoordT>> {
-        let mut groups = vec![];
-        let mut sub_group_1;
-        let mut sub_group_2;
+        let first_group_coeff = node_child_num / 2;
+        let second_group_coeff = node_child_num - first_group_coeff;
+
+        let (group_1, group_2) = Self::split_into_2_groups(
+            obj_space,
+            alpha,
+            first_group_coeff,
+            second_group_coeff,
+            level,
+            unbinded_ids,
+        );
+
+        let mut groups = Self::split_sub_groups(
+            obj_space,
+            alpha,
+            first_group_coeff,
+            level,
+            group_1,
+            Self::split_groups,
+        );
+        groups.append(&mut Self::split_sub_groups(
+            obj_space,
+            alpha,
+            second_group_coeff,
+            level,
+            group_2,
+            Self::split_groups,
+        ));
 
+        groups
+    }
+
+    /// Like `split_groups`, but the two halves are split further on separate rayon tasks.
+    #[cfg(feature = "rayon")]
+    fn par_split_groups<'ids>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        alpha: f32,
+        node_child_num: usize,
+        level: usize,
+        unbinded_ids: &'ids mut [RecordId],
+    ) -> Vec<NodeGroup<'ids, CoordT>>
+    where
+        CoordT: Send + Sync,
+        ObjectT: Send + Sync,
+    {
         let first_group_coeff = node_child_num / 2;
         let second_group_coeff = node_child_num - first_group_coeff;
 
@@ -751,37 +2746,54 @@ where
             unbinded_ids,
         );
 
-        let (_, ref mbr_1) = group_1;
-        if !mbr_1.is_undefined() {
-            if first_group_coeff > 1 {
-                sub_group_1 =
-                    Self::split_groups(obj_space, alpha, first_group_coeff, level, group_1.0);
-            } else {
-                sub_group_1 = vec![group_1]
-            }
-        } else {
-            sub_group_1 = vec![];
-        }
+        let (mut groups, mut sub_groups_2) = rayon::join(
+            || {
+                Self::split_sub_groups(
+                    obj_space,
+                    alpha,
+                    first_group_coeff,
+                    level,
+                    group_1,
+                    Self::par_split_groups,
+                )
+            },
+            || {
+                Self::split_sub_groups(
+                    obj_space,
+                    alpha,
+                    second_group_coeff,
+                    level,
+                    group_2,
+                    Self::par_split_groups,
+                )
+            },
+        );
 
-        let (_, ref mbr_2) = group_2;
-        if !mbr_2.is_undefined() {
-            if second_group_coeff > 1 {
-                sub_group_2 =
-                    Self::split_groups(obj_space, alpha, second_group_coeff, level, group_2.0);
-            } else {
-                sub_group_2 = vec![group_2];
-            }
+        groups.append(&mut sub_groups_2);
+        groups
+    }
+
+    /// Splits the `group` further into `group_coeff` groups with the `split_groups`.
+    fn split_sub_groups<'ids>(
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        alpha: f32,
+        group_coeff: usize,
+        level: usize,
+        group: NodeGroup<'ids, CoordT>,
+        split_groups: SplitGroups<CoordT, ObjectT>,
+    ) -> Vec<NodeGroup<'ids, CoordT>> {
+        let (_, ref mbr) = group;
+        if mbr.is_undefined() {
+            vec![]
+        } else if group_coeff > 1 {
+            split_groups(obj_space, alpha, group_coeff, level, group.0)
         } else {
-            sub_group_2 = vec![];
+            vec![group]
         }
-
-        groups.append(&mut sub_group_1);
-        groups.append(&mut sub_group_2);
-        groups
     }
 
     fn split_into_2_groups<'ids>(
-        obj_space: &mut ObjSpace<CoordT, ObjectT>,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
         alpha: f32,
         first_group_coeff: usize,
         second_group_coeff: usize,
@@ -800,19 +2812,8 @@ where
         let sort_axis_idx = Self::find_sort_axis_index(obj_space, unbinded_ids);
 
         unbinded_ids.sort_unstable_by(|&lhs_id, &rhs_id| {
-            let sort_value = |bounds: &Bounds<CoordT>| {
-                let sum = (bounds.min.clone() + bounds.max.clone())
-                    .to_f32()
-                    .expect("CoordT is expected to be convertible to f32");
-
-                sum / 2.0
-            };
-
-            let lhs = obj_space.get_mbr(lhs_id).bounds(sort_axis_idx);
-            let rhs = obj_space.get_mbr(rhs_id).bounds(sort_axis_idx);
-
-            let lhs = sort_value(lhs);
-            let rhs = sort_value(rhs);
+            let lhs = obj_space.get_mbr(lhs_id).bounds(sort_axis_idx).midpoint();
+            let rhs = obj_space.get_mbr(rhs_id).bounds(sort_axis_idx).midpoint();
 
             lhs.partial_cmp(&rhs).expect("cmp result is expected")
         });
@@ -904,7 +2905,7 @@ where
     }
 
     fn find_sort_axis_index<'ids>(
-        obj_space: &mut ObjSpace<CoordT, ObjectT>,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
         unbinded_ids: &'ids [RecordId],
     ) -> usize {
         (0..obj_space.dimension)
@@ -943,6 +2944,142 @@ where
     }
 }
 
+/// Builds the tree statically like `insert_many` on an empty tree.
+///
+/// The dimension is taken from the first item,
+/// `min_records` is 2 and `max_records` is 8.
+/// No items make an empty tree of the dimension 2 like `TreeConfig::default`.
+///
+/// # Panics
+/// Panics if the items have different dimensions.
+impl<CoordT, ObjectT> FromIterator<(ObjectT, MBR<CoordT>)> for LRTree<CoordT, ObjectT>
+where
+    CoordT: CoordTrait,
+    ObjectT: Debug + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = (ObjectT, MBR<CoordT>)>>(items: I) -> Self {
+        let mut items = items.into_iter().peekable();
+        let dimension = match items.peek() {
+            Some((_, mbr)) => mbr.dimension(),
+            None => {
+                return Self::with_obj_space(ObjSpace::new(
+                    DEFAULT_DIMENSION,
+                    DEFAULT_MIN_RECORDS,
+                    DEFAULT_MAX_RECORDS,
+                ))
+            }
+        };
+
+        let data = items.enumerate().map(|(i, (object, mbr))| {
+            assert_eq!(
+                mbr.dimension(),
+                dimension,
+                "item #{} dimension differs from the first item one",
+                i
+            );
+
+            (object, mbr)
+        });
+
+        let tree = Self::with_obj_space(ObjSpace::with_data(
+            dimension,
+            DEFAULT_MIN_RECORDS,
+            DEFAULT_MAX_RECORDS,
+            data,
+        ));
+        tree.rebuild(INSERT_MANY_ALPHA);
+
+        tree
+    }
+}
+
+/// One-line summary: `LRTree { dim: D, objects: N, height: H, root_mbr: MBR { ... } }`.
+impl<CoordT, ObjectT> Display for LRTree<CoordT, ObjectT>
+where
+    CoordT: CoordTrait,
+    ObjectT: Debug + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let obj_space = self.obj_space.read();
+
+        write!(
+            f,
+            "LRTree {{ dim: {}, objects: {}, height: {}, root_mbr: {} }}",
+            obj_space.dimension,
+            obj_space.data_num(),
+            Self::height_obj_space(&obj_space),
+            obj_space.get_root_mbr()
+        )
+    }
+}
+
+/// Serialized as its `ObjSpace`.
+#[cfg(feature = "serde")]
+impl<CoordT, ObjectT> Serialize for LRTree<CoordT, ObjectT>
+where
+    CoordT: CoordTrait + Serialize,
+    ObjectT: Debug + Clone + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.lock_obj_space().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, CoordT, ObjectT> Deserialize<'de> for LRTree<CoordT, ObjectT>
+where
+    CoordT: CoordTrait + Deserialize<'de>,
+    ObjectT: Debug + Clone + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ObjSpace::deserialize(deserializer).map(Self::with_obj_space)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<CoordT, ObjectT> LRTree<CoordT, ObjectT>
+where
+    CoordT: CoordTrait + Serialize + DeserializeOwned,
+    ObjectT: Debug + Clone + Serialize + DeserializeOwned,
+{
+    /// Writes the tree to the file in the bincode format.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        debug_log!("save lr-tree to {}", path.as_ref().display());
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, self)
+            .map_err(|error| Self::bincode_to_io_error(*error))?;
+        writer.flush()?;
+
+        debug_log!("[COMPLETED] save lr-tree");
+
+        Ok(())
+    }
+
+    /// Reads the tree written by `save`.
+    ///
+    /// Malformed data is reported as `io::ErrorKind::InvalidData`,
+    /// a truncated file as `io::ErrorKind::UnexpectedEof`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        debug_log!("load lr-tree from {}", path.as_ref().display());
+
+        let reader = BufReader::new(File::open(path)?);
+        let tree =
+            bincode::deserialize_from(reader).map_err(|error| Self::bincode_to_io_error(*error))?;
+
+        debug_log!("[COMPLETED] load lr-tree");
+
+        Ok(tree)
+    }
+
+    fn bincode_to_io_error(error: bincode::ErrorKind) -> io::Error {
+        match error {
+            bincode::ErrorKind::Io(error) => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
 #[cfg(feature = "with-dbg-vis")]
 use dbg_vis::{DebugVis, DebugVisJSON};
 
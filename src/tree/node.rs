@@ -4,9 +4,13 @@ use {
     std::string::ToString,
 };
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 pub type NodeId = Id;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RecordId {
     Root,
     Internal(NodeId),
@@ -65,8 +69,17 @@ impl ToString for RecordId {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "CoordT: Serialize, PayloadT: Serialize",
+        deserialize = "CoordT: Deserialize<'de>, PayloadT: Deserialize<'de>"
+    ))
+)]
 pub struct Node<CoordT: CoordTrait, PayloadT: Clone> {
     pub(crate) parent_id: RecordId,
+    #[cfg_attr(feature = "serde", serde(with = "crate::mbr::serde_maybe_undefined"))]
     pub(crate) mbr: MBR<CoordT>,
     pub(crate) payload: PayloadT,
 }
@@ -1,20 +1,43 @@
 use {
     super::{
-        mbr, CoordTrait, DataNode, InternalNode, InternalNodeTrait, Node, NodeId, RecordId,
-        RecordIdKind, MBR,
+        mbr, ChooseSubtree, CoordTrait, DataNode, DefaultInsertHandler, InsertPolicy, InternalNode,
+        InternalNodeTrait, LRTree, LeastEnlargement, LinearSplit, Node, NodeId, RecordId,
+        RecordIdKind, SplitStrategy, MBR,
     },
     id_storage::ShrinkableStorage,
-    std::{fmt::Debug, iter::Extend},
+    std::{
+        collections::{HashMap, HashSet},
+        fmt::Debug,
+        iter::Extend,
+        sync::Arc,
+    },
 };
 
+#[cfg(feature = "serde")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
 #[derive(Debug)]
 pub struct ObjSpace<CoordT: CoordTrait, ObjectT: Clone> {
     nodes: Vec<InternalNode<CoordT>>,
+    free_node_ids: Vec<usize>,
     data_nodes: ShrinkableStorage<DataNode<CoordT, ObjectT>>,
+
+    /// Number of the data IDs allocated in the `data_nodes`, both live and removed.
+    data_len: usize,
+
+    /// Data IDs freed in the `data_nodes`.
+    removed_data_ids: HashSet<NodeId>,
+
+    /// Removed data IDs which can't be restored, see `delete_data`.
+    deleted_data_ids: HashSet<NodeId>,
     pub(crate) dimension: usize,
     pub(crate) min_records: usize,
     pub(crate) max_records: usize,
     pub(crate) root_id: RecordId,
+    pub(crate) choose_subtree: Arc<dyn ChooseSubtree<CoordT, ObjectT>>,
+    pub(crate) insert_policy: InsertPolicy,
+    pub(crate) split_strategy: Arc<dyn SplitStrategy<CoordT, ObjectT>>,
+    pub(crate) margin_tie_break: bool,
 }
 
 impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
@@ -43,12 +66,18 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
     }
 
     pub fn clone_shrinked(&self) -> Self {
-        Self::with_data_nodes(
+        let mut obj_space = Self::with_data_nodes(
             self.dimension,
             self.min_records,
             self.max_records,
             self.data_nodes.shrink(),
-        )
+        );
+        obj_space.choose_subtree = self.choose_subtree.clone();
+        obj_space.insert_policy = self.insert_policy;
+        obj_space.split_strategy = self.split_strategy.clone();
+        obj_space.margin_tie_break = self.margin_tie_break;
+
+        obj_space
     }
 
     /// # Safety
@@ -58,9 +87,13 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
         P: FnMut(&ObjectT, &MBR<CoordT>) -> bool,
     {
         self.data_nodes
-            .retain(|node| predicate(&node.payload, &node.mbr))
+            .retain(|node| predicate(&node.payload, &node.mbr));
+        self.data_len = self.data_nodes.volume();
+        self.removed_data_ids.clear();
+        self.deleted_data_ids.clear();
     }
 
+    /// The `data_nodes` must have no freed IDs.
     pub(crate) fn with_data_nodes(
         dimension: usize,
         min_records: usize,
@@ -73,11 +106,19 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
 
         let mut storage = Self {
             nodes: vec![],
+            free_node_ids: vec![],
+            data_len: data_nodes.volume(),
             data_nodes,
+            removed_data_ids: HashSet::new(),
+            deleted_data_ids: HashSet::new(),
             dimension,
             min_records,
             max_records,
             root_id: RecordId::Root,
+            choose_subtree: Arc::new(LeastEnlargement),
+            insert_policy: InsertPolicy::default(),
+            split_strategy: Arc::new(LinearSplit),
+            margin_tie_break: true,
         };
 
         storage.root_id = storage.make_node(RecordIdKind::Leaf);
@@ -85,8 +126,20 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
         storage
     }
 
+    /// Removes all the data and the tree structure,
+    /// so the IDs start from scratch like in a new `ObjSpace`.
+    pub(crate) fn clear(&mut self) {
+        self.data_nodes = ShrinkableStorage::new();
+        self.data_len = 0;
+        self.removed_data_ids.clear();
+        self.deleted_data_ids.clear();
+
+        self.clear_tree_structure();
+    }
+
     pub(crate) fn clear_tree_structure(&mut self) {
         self.nodes.clear();
+        self.free_node_ids.clear();
 
         self.root_id = self.make_node(RecordIdKind::Leaf);
     }
@@ -102,16 +155,43 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
     ) -> RecordId {
         let node = InternalNode::with_mbr(self.max_records as usize, mbr);
 
-        let id = self.nodes.len();
-        self.nodes.push(node);
+        let id = match self.free_node_ids.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
 
         RecordId::from_node_id(id, node_id_kind)
     }
 
+    /// Detaches the node from the tree structure and
+    /// makes its slot available for reuse by `make_node`.
+    ///
+    /// The node's children are not freed.
+    pub(crate) fn free_node(&mut self, id: RecordId) {
+        debug_assert! {
+            !matches!(id, RecordId::Data(_)),
+            "data id is not allowed here"
+        };
+
+        self.get_node_mut(id).abort_children();
+        self.set_parent_info(id, RecordId::Root);
+
+        self.free_node_ids.push(id.as_node_id());
+    }
+
     pub fn make_data_node(&mut self, object: ObjectT, mbr: MBR<CoordT>) -> NodeId {
         let node = Self::make_data_node_raw(object, mbr);
 
-        self.data_nodes.insert(node)
+        let id = self.data_nodes.insert(node);
+        self.data_len = self.data_len.max(id + 1);
+
+        id
     }
 
     fn make_data_node_raw(object: ObjectT, mbr: MBR<CoordT>) -> DataNode<CoordT, ObjectT> {
@@ -165,16 +245,172 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
         self.data_nodes.iter_ids().map(RecordId::Data)
     }
 
-    pub fn mark_as_removed<I: Iterator<Item = NodeId>>(&mut self, data_ids: I) {
-        self.data_nodes.free_ids(data_ids);
+    /// Marks the data nodes as removed.
+    ///
+    /// Returns the IDs which were actually removed by this call in the input order:
+    /// already removed, unknown and duplicate IDs are skipped.
+    pub fn mark_as_removed<I: Iterator<Item = NodeId>>(&mut self, data_ids: I) -> Vec<NodeId> {
+        let mut removed_ids = vec![];
+        for id in data_ids {
+            if id < self.data_len && self.removed_data_ids.insert(id) {
+                removed_ids.push(id);
+            }
+        }
+
+        self.data_nodes.free_ids(removed_ids.iter().cloned());
+
+        removed_ids
+    }
+
+    /// Inserts the object into the tree structure like `LRTree::insert`.
+    ///
+    /// Unlike `make_data_node`, the object is bound to a leaf
+    /// and is visible to the queries right away.
+    pub fn insert(&mut self, object: ObjectT, mbr: MBR<CoordT>) -> NodeId {
+        LRTree::insert_obj_space(self, object, mbr, &mut DefaultInsertHandler)
+    }
+
+    /// Removes the object from the tree structure like `LRTree::remove`.
+    ///
+    /// Returns `None` if the object is already removed.
+    pub fn remove(&mut self, id: NodeId) -> Option<ObjectT> {
+        LRTree::remove_obj_space(self, id)
+    }
+
+    /// Marks the data node as removed for good: unlike `mark_as_removed`,
+    /// it is never brought back by `restore_removed` and `restore_removed_ids`.
+    pub(crate) fn delete_data(&mut self, data_id: NodeId) {
+        self.mark_as_removed(std::iter::once(data_id));
+        self.deleted_data_ids.insert(data_id);
+    }
+
+    /// Whether the costs of the MBRs are tie-broken by the margin, see `LRTree::set_margin_tie_break`.
+    pub fn margin_tie_break(&self) -> bool {
+        self.margin_tie_break
     }
 
     pub fn is_removed(&self, data_id: &NodeId) -> bool {
         self.data_nodes.is_id_free(data_id)
     }
 
-    pub(crate) fn restore_removed(&mut self) {
+    /// Restores all the removed data nodes except the deleted ones.
+    ///
+    /// Returns the IDs that were actually restored.
+    pub(crate) fn restore_removed(&mut self) -> Vec<NodeId> {
+        let restored_ids = self
+            .removed_data_ids
+            .difference(&self.deleted_data_ids)
+            .cloned()
+            .collect::<Vec<_>>();
+
         self.data_nodes.restore_freed();
+        self.data_nodes
+            .free_ids(self.deleted_data_ids.iter().cloned());
+        self.removed_data_ids = self.deleted_data_ids.clone();
+
+        restored_ids
+    }
+
+    /// Restores only the given removed IDs, other removed IDs stay removed.
+    ///
+    /// Returns the IDs that were actually restored.
+    pub(crate) fn restore_removed_ids<I: Iterator<Item = NodeId>>(
+        &mut self,
+        data_ids: I,
+    ) -> Vec<NodeId> {
+        let restore_ids = data_ids
+            .filter(|id| self.is_removed(id) && !self.deleted_data_ids.contains(id))
+            .collect::<HashSet<_>>();
+
+        if restore_ids.is_empty() {
+            return vec![];
+        }
+
+        self.removed_data_ids.retain(|id| !restore_ids.contains(id));
+
+        // The storage can restore only all the freed IDs at once.
+        self.data_nodes.restore_freed();
+        self.data_nodes
+            .free_ids(self.removed_data_ids.iter().cloned());
+
+        restore_ids.into_iter().collect()
+    }
+
+    /// Drops the removed data nodes from the storage and renumbers the others.
+    ///
+    /// Data references in the tree nodes reachable from the root are rewritten to the new IDs,
+    /// so removed data nodes must not be referenced by the tree structure.
+    /// Returns the map from the old IDs to the new ones.
+    pub(crate) fn compact_data(&mut self) -> HashMap<NodeId, NodeId> {
+        let mut data_nodes = ShrinkableStorage::new();
+
+        let id_map = self
+            .data_nodes
+            .iter()
+            .map(|(old_id, node)| (old_id, data_nodes.insert(node.clone())))
+            .collect::<HashMap<_, _>>();
+
+        self.data_nodes = data_nodes;
+        self.data_len = id_map.len();
+        self.removed_data_ids.clear();
+        self.deleted_data_ids.clear();
+
+        let mut node_ids = vec![self.root_id];
+        while let Some(node_id) = node_ids.pop() {
+            for child_id in self.nodes[node_id.as_node_id()].payload.iter_mut() {
+                match child_id {
+                    RecordId::Data(id) => *id = id_map[id],
+                    _ => node_ids.push(*child_id),
+                }
+            }
+        }
+
+        id_map
+    }
+
+    /// Drops the free tree nodes at the end of the storage and releases the excess capacity.
+    ///
+    /// Neither the tree nodes nor the data are renumbered.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        let mut free_node_ids = self.free_node_ids.iter().copied().collect::<HashSet<_>>();
+        while !self.nodes.is_empty() && free_node_ids.remove(&(self.nodes.len() - 1)) {
+            self.nodes.pop();
+        }
+
+        let nodes_len = self.nodes.len();
+        self.free_node_ids.retain(|&id| id < nodes_len);
+
+        self.nodes.shrink_to_fit();
+        self.free_node_ids.shrink_to_fit();
+        self.removed_data_ids.shrink_to_fit();
+        self.deleted_data_ids.shrink_to_fit();
+    }
+
+    #[cfg(test)]
+    pub(crate) fn nodes_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn free_nodes_len(&self) -> usize {
+        self.free_node_ids.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn nodes_capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Checks if the data node is referenced by its parent node.
+    pub(crate) fn is_data_bound(&self, id: NodeId) -> bool {
+        match self.get_data(id).parent_id {
+            RecordId::Root => false,
+            parent_id => self
+                .nodes
+                .get(parent_id.as_node_id())
+                .map(|parent| parent.payload.contains(&RecordId::Data(id)))
+                .unwrap_or(false),
+        }
     }
 
     pub(crate) fn set_parent_info(&mut self, id: RecordId, parent_id: RecordId) {
@@ -190,6 +426,13 @@ impl<CoordT: CoordTrait, ObjectT: Debug + Clone> ObjSpace<CoordT, ObjectT> {
         }
     }
 
+    pub(crate) fn get_parent_id(&self, id: RecordId) -> RecordId {
+        match id {
+            RecordId::Data(id) => self.get_data(id).parent_id,
+            _ => self.nodes[id.as_node_id()].parent_id,
+        }
+    }
+
     pub(crate) fn add_child(&mut self, id: RecordId, child_id: RecordId) {
         let child_mbr = self.get_mbr(child_id).clone();
         let node = self.get_node_mut(id);
@@ -257,9 +500,133 @@ impl<CoordT: CoordTrait, ObjectT: Clone + Debug> Extend<(ObjectT, MBR<CoordT>)>
     for ObjSpace<CoordT, ObjectT>
 {
     fn extend<T: IntoIterator<Item = (ObjectT, MBR<CoordT>)>>(&mut self, iter: T) {
-        self.data_nodes.extend(
-            iter.into_iter()
-                .map(|(object, mbr)| Self::make_data_node_raw(object, mbr)),
-        );
+        let mut added_num = 0;
+        self.data_nodes
+            .extend(iter.into_iter().map(|(object, mbr)| {
+                added_num += 1;
+                Self::make_data_node_raw(object, mbr)
+            }));
+
+        self.data_len += added_num;
+    }
+}
+
+/// Serialized form of the `ObjSpace`.
+///
+/// `data_nodes` are indexed by the data IDs, so the IDs are kept after the deserialization.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct ObjSpaceSnapshot<NodesT, DataNodesT> {
+    dimension: usize,
+    min_records: usize,
+    max_records: usize,
+    root_id: RecordId,
+    nodes: NodesT,
+    free_node_ids: Vec<usize>,
+    data_nodes: DataNodesT,
+    removed_data_ids: Vec<NodeId>,
+    deleted_data_ids: Vec<NodeId>,
+}
+
+/// The tree options (`choose_subtree`, `insert_policy`, `split_strategy` and `margin_tie_break`)
+/// are not serialized, the deserialized `ObjSpace` uses the default ones.
+///
+/// Objects marked as removed are serialized too, so they still can be restored.
+#[cfg(feature = "serde")]
+impl<CoordT, ObjectT> Serialize for ObjSpace<CoordT, ObjectT>
+where
+    CoordT: CoordTrait + Serialize,
+    ObjectT: Debug + Clone + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data_nodes = (0..self.data_len)
+            .map(|id| self.get_data(id))
+            .collect::<Vec<_>>();
+
+        let mut removed_data_ids = self.removed_data_ids.iter().cloned().collect::<Vec<_>>();
+        removed_data_ids.sort_unstable();
+
+        ObjSpaceSnapshot {
+            dimension: self.dimension,
+            min_records: self.min_records,
+            max_records: self.max_records,
+            root_id: self.root_id,
+            nodes: &self.nodes,
+            free_node_ids: self.free_node_ids.clone(),
+            data_nodes,
+            removed_data_ids,
+            deleted_data_ids: self.deleted_data_ids.iter().cloned().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, CoordT, ObjectT> Deserialize<'de> for ObjSpace<CoordT, ObjectT>
+where
+    CoordT: CoordTrait + Deserialize<'de>,
+    ObjectT: Debug + Clone + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = ObjSpaceSnapshot::<
+            Vec<InternalNode<CoordT>>,
+            Vec<DataNode<CoordT, ObjectT>>,
+        >::deserialize(deserializer)?;
+
+        if snapshot.dimension == 0
+            || snapshot.min_records < 2
+            || snapshot.min_records > (snapshot.max_records as f64 / 2.0).ceil() as usize
+        {
+            return Err(D::Error::custom("invalid tree parameters"));
+        }
+
+        let nodes_num = snapshot.nodes.len();
+        let data_num = snapshot.data_nodes.len();
+        let is_valid_id = |id: RecordId| match id {
+            RecordId::Root => true,
+            RecordId::Data(id) => id < data_num,
+            _ => id.as_node_id() < nodes_num,
+        };
+
+        let is_valid = matches!(snapshot.root_id, RecordId::Internal(_) | RecordId::Leaf(_))
+            && is_valid_id(snapshot.root_id)
+            && snapshot.nodes.iter().all(|node| {
+                is_valid_id(node.parent_id) && node.payload.iter().all(|&id| is_valid_id(id))
+            })
+            && snapshot
+                .data_nodes
+                .iter()
+                .all(|node| is_valid_id(node.parent_id))
+            && snapshot.free_node_ids.iter().all(|&id| id < nodes_num)
+            && snapshot.removed_data_ids.iter().all(|&id| id < data_num)
+            && snapshot
+                .deleted_data_ids
+                .iter()
+                .all(|id| snapshot.removed_data_ids.contains(id));
+
+        if !is_valid {
+            return Err(D::Error::custom("record ID is out of range"));
+        }
+
+        let mut data_nodes = ShrinkableStorage::new();
+        data_nodes.extend(snapshot.data_nodes);
+        data_nodes.free_ids(snapshot.removed_data_ids.iter().cloned());
+
+        Ok(Self {
+            nodes: snapshot.nodes,
+            free_node_ids: snapshot.free_node_ids,
+            data_nodes,
+            data_len: data_num,
+            removed_data_ids: snapshot.removed_data_ids.into_iter().collect(),
+            deleted_data_ids: snapshot.deleted_data_ids.into_iter().collect(),
+            dimension: snapshot.dimension,
+            min_records: snapshot.min_records,
+            max_records: snapshot.max_records,
+            root_id: snapshot.root_id,
+            choose_subtree: Arc::new(LeastEnlargement),
+            insert_policy: InsertPolicy::default(),
+            split_strategy: Arc::new(LinearSplit),
+            margin_tie_break: true,
+        })
     }
 }
@@ -7,8 +7,10 @@ use {
     proptest::{
         collection::{self, size_range},
         prelude::*,
+        test_runner::TestRunner,
     },
     std::{
+        cell::Cell,
         collections::HashSet,
         sync::atomic::{AtomicUsize, Ordering},
     },
@@ -34,6 +36,10 @@ const MAX_RECORDS: usize = 20;
 const MIN_MBR_NUM: usize = 0;
 const MAX_MBR_NUM: usize = 200;
 
+const MIN_CLUSTER_NUM: usize = 2;
+const MAX_CLUSTER_NUM: usize = 5;
+const CLUSTER_RADIUS: Coord = 40;
+
 static TEST_2D_NUM: AtomicUsize = AtomicUsize::new(0);
 
 // include!("../../tests/res/test_dumper.rs");
@@ -191,6 +197,20 @@ fn static_build_tree(test_params: &TestParams) -> Tree {
     tree
 }
 
+fn str_build_tree(test_params: &TestParams) -> Tree {
+    let obj_space = tree::ObjSpace::with_data(
+        test_params.dim,
+        test_params.min_records,
+        test_params.max_records,
+        as_objects(test_params.mbrs.iter()),
+    );
+
+    let tree = Tree::with_obj_space(obj_space);
+    tree.rebuild_str();
+
+    tree
+}
+
 fn hybrid_build_tree(test_params: &TestParams) -> Tree {
     let tree = static_build_tree(test_params);
 
@@ -215,6 +235,140 @@ fn remake_static_tree(old_tree: &Tree, test_params: &TestParams) -> Tree {
     tree
 }
 
+fn clustered_mbrs() -> impl Strategy<Value = Vec<MBR>> {
+    let center = MIN_COORD + CLUSTER_RADIUS..MAX_COORD - CLUSTER_RADIUS;
+    let offset = -CLUSTER_RADIUS..CLUSTER_RADIUS;
+    let size = 1..CLUSTER_RADIUS / 4;
+
+    collection::vec((center.clone(), center), MIN_CLUSTER_NUM..=MAX_CLUSTER_NUM)
+        .prop_flat_map(move |centers| {
+            let objects = collection::vec(
+                (
+                    0..centers.len(),
+                    offset.clone(),
+                    offset.clone(),
+                    size.clone(),
+                    size.clone(),
+                ),
+                MAX_MBR_NUM / 2..MAX_MBR_NUM,
+            );
+
+            (Just(centers), objects)
+        })
+        .prop_map(|(centers, objects)| {
+            objects
+                .into_iter()
+                .map(|(cluster, x_offset, y_offset, width, height)| {
+                    let (x, y) = centers[cluster];
+                    let (x, y) = (x + x_offset, y + y_offset);
+
+                    mbr! {
+                        X = [x; x + width],
+                        Y = [y; y + height]
+                    }
+                })
+                .collect()
+        })
+}
+
+fn choose_subtree_build_tree(
+    mbrs: &[MBR],
+    choose_subtree: impl tree::ChooseSubtree<Coord, Object> + 'static,
+) -> Tree {
+    let tree = Tree::with_obj_space(tree::ObjSpace::new(2, MIN_RECORDS, MAX_RECORDS / 2));
+    tree.set_choose_subtree(choose_subtree);
+
+    for (i, mbr) in as_objects(mbrs.iter()) {
+        tree.insert(i, mbr);
+    }
+
+    tree
+}
+
+fn split_strategy_build_tree(
+    mbrs: &[MBR],
+    split_strategy: impl tree::SplitStrategy<Coord, Object> + 'static,
+) -> Tree {
+    let tree = Tree::with_obj_space(tree::ObjSpace::new(2, MIN_RECORDS, MAX_RECORDS / 2));
+    tree.set_split_strategy(split_strategy);
+
+    for (i, mbr) in as_objects(mbrs.iter()) {
+        tree.insert(i, mbr);
+    }
+
+    tree
+}
+
+/// Checks that every node except the root has from `min_records` to `max_records` children.
+fn check_node_fill(tree: &Tree) -> Result<(), TestCaseError> {
+    let obj_space = tree.lock_obj_space();
+
+    let mut stack = vec![obj_space.root_id];
+    while let Some(node_id) = stack.pop() {
+        let children = &obj_space.get_node(node_id).payload;
+
+        prop_assert!(
+            children.len() <= obj_space.max_records,
+            "{:?} is overflowed",
+            node_id
+        );
+        prop_assert!(
+            node_id == obj_space.root_id || children.len() >= obj_space.min_records,
+            "{:?} is underfull",
+            node_id
+        );
+
+        if let tree::RecordId::Internal(_) = node_id {
+            stack.extend(children.iter().cloned());
+        }
+    }
+
+    Ok(())
+}
+
+/// Sums the overlap volumes of the sibling leaves.
+///
+/// It is the overlap `LeastOverlap` minimizes on the level above the leaves.
+fn sibling_leaf_overlap(tree: &Tree) -> Coord {
+    let obj_space = tree.lock_obj_space();
+
+    let mut overlap = 0;
+    let mut stack = vec![obj_space.root_id];
+    while let Some(node_id) = stack.pop() {
+        let children = &obj_space.get_node(node_id).payload;
+
+        match children.first() {
+            Some(tree::RecordId::Leaf(_)) => {
+                for (i, lhs) in children.iter().enumerate() {
+                    for rhs in &children[i + 1..] {
+                        overlap +=
+                            mbr::overlap_volume(obj_space.get_mbr(*lhs), obj_space.get_mbr(*rhs));
+                    }
+                }
+            }
+            Some(tree::RecordId::Internal(_)) => stack.extend(children.iter().cloned()),
+            _ => {}
+        }
+    }
+
+    overlap
+}
+
+/// Sums the overlap volumes of all the leaf pairs.
+fn leaf_overlap(tree: &Tree) -> Coord {
+    let mut leaf_mbrs = vec![];
+    tree.for_each_leaf(|_, leaf| leaf_mbrs.push(leaf.mbr.clone()));
+
+    let mut overlap = 0;
+    for (i, lhs) in leaf_mbrs.iter().enumerate() {
+        for rhs in &leaf_mbrs[i + 1..] {
+            overlap += mbr::overlap_volume(lhs, rhs);
+        }
+    }
+
+    overlap
+}
+
 fn search_intersections<'a>(
     search_mbr: &MBR,
     data_mbrs: impl Iterator<Item = (Object, MBR)>,
@@ -324,3 +478,193 @@ proptest! {
         }
     }
 }
+
+proptest! {
+    #[test]
+    fn common_mbr_mixed_dimensions_property_test(
+        (lhs, rhs) in (dim(), dim()).prop_flat_map(|(lhs_dim, rhs_dim)| {
+            (any_with::<MBR>(lhs_dim), any_with::<MBR>(rhs_dim))
+        })
+    ) {
+        let common = mbr::common_mbr(&lhs, &rhs);
+
+        prop_assert_eq!(
+            common.dimension(),
+            std::cmp::max(lhs.dimension(), rhs.dimension())
+        );
+        prop_assert!(common.contains(&lhs), "{} doesn't contain {}", common, lhs);
+        prop_assert!(common.contains(&rhs), "{} doesn't contain {}", common, rhs);
+    }
+}
+
+/// `LeastOverlap` doesn't win every case, so the overlaps are summed over all the cases.
+/// The runner is deterministic, so the sums are reproducible.
+#[test]
+fn choose_subtree_property_test() {
+    init_logger();
+
+    let enlargement_overlap = Cell::new(0);
+    let overlap_overlap = Cell::new(0);
+
+    TestRunner::deterministic()
+        .run(
+            &(clustered_mbrs(), any_with::<MBR>(2)),
+            |(mbrs, search_mbr)| {
+                let expected_found = search_intersections(&search_mbr, as_objects(mbrs.iter()));
+
+                let enlargement_tree = choose_subtree_build_tree(&mbrs, tree::LeastEnlargement);
+                check_tree(
+                    &enlargement_tree,
+                    &search_mbr,
+                    &expected_found,
+                    "least-enlargement",
+                )?;
+                enlargement_overlap
+                    .set(enlargement_overlap.get() + sibling_leaf_overlap(&enlargement_tree));
+
+                let overlap_tree = choose_subtree_build_tree(&mbrs, tree::LeastOverlap);
+                check_tree(&overlap_tree, &search_mbr, &expected_found, "least-overlap")?;
+                overlap_overlap.set(overlap_overlap.get() + sibling_leaf_overlap(&overlap_tree));
+
+                Ok(())
+            },
+        )
+        .unwrap();
+
+    assert!(
+        overlap_overlap.get() < enlargement_overlap.get(),
+        "least-overlap leaves overlap {}, least-enlargement leaves overlap {}",
+        overlap_overlap.get(),
+        enlargement_overlap.get()
+    );
+}
+
+proptest! {
+    #[test]
+    fn str_build_property_test(test_params in any::<TestParams>()) {
+        init_logger();
+
+        let dyn_tree = dynamic_build_tree(&test_params);
+        let str_tree = str_build_tree(&test_params);
+
+        let dyn_found: HashSet<_> = dyn_tree.search(&test_params.search_mbr).into_iter().collect();
+        let str_found: HashSet<_> = str_tree.search(&test_params.search_mbr).into_iter().collect();
+
+        prop_assert_eq!(str_found, dyn_found);
+    }
+}
+
+/// Like `choose_subtree_property_test`, the leaf overlaps are summed over all the cases.
+#[test]
+fn split_strategy_property_test() {
+    init_logger();
+
+    let linear_overlap = Cell::new(0);
+    let quadratic_overlap = Cell::new(0);
+
+    TestRunner::deterministic()
+        .run(
+            &(clustered_mbrs(), any_with::<MBR>(2)),
+            |(mbrs, search_mbr)| {
+                let expected_found = search_intersections(&search_mbr, as_objects(mbrs.iter()));
+
+                let linear_tree = split_strategy_build_tree(&mbrs, tree::LinearSplit);
+                check_node_fill(&linear_tree)?;
+                check_tree(&linear_tree, &search_mbr, &expected_found, "linear")?;
+                linear_overlap.set(linear_overlap.get() + leaf_overlap(&linear_tree));
+
+                let quadratic_tree = split_strategy_build_tree(&mbrs, tree::QuadraticSplit);
+                check_node_fill(&quadratic_tree)?;
+                check_tree(&quadratic_tree, &search_mbr, &expected_found, "quadratic")?;
+                quadratic_overlap.set(quadratic_overlap.get() + leaf_overlap(&quadratic_tree));
+
+                Ok(())
+            },
+        )
+        .unwrap();
+
+    assert!(
+        quadratic_overlap.get() < linear_overlap.get(),
+        "quadratic leaves overlap {}, linear leaves overlap {}",
+        quadratic_overlap.get(),
+        linear_overlap.get()
+    );
+}
+
+#[cfg(feature = "rayon")]
+proptest! {
+    #[test]
+    fn par_search_property_test(test_params in any::<TestParams>()) {
+        init_logger();
+
+        let tree = hybrid_build_tree(&test_params);
+
+        let found: HashSet<_> = tree.par_search(&test_params.search_mbr).into_iter().collect();
+        let expected: HashSet<_> = tree.search(&test_params.search_mbr).into_iter().collect();
+
+        prop_assert_eq!(found, expected);
+    }
+}
+
+/// Converts the MBR to floating point coordinates.
+///
+/// The coordinates are coarsened, so the MBRs often touch each other.
+#[cfg(feature = "simd")]
+fn coarse_float_mbr<T: tree::CoordTrait>(mbr: &MBR) -> mbr::MBR<T> {
+    let coarse = |coord: &Coord| T::from(coord / 25).expect("coord is expected to fit");
+
+    mbr::MBR::new(
+        mbr.iter_bounds()
+            .map(|bounds| mbr::Bounds::new(coarse(&bounds.min), coarse(&bounds.max)))
+            .collect(),
+    )
+}
+
+#[cfg(feature = "simd")]
+proptest! {
+    #[test]
+    fn simd_intersects_property_test(
+        (lhs, rhs) in (dim(), dim()).prop_flat_map(|(lhs_dim, rhs_dim)| {
+            (any_with::<MBR>(lhs_dim), any_with::<MBR>(rhs_dim))
+        })
+    ) {
+        let (lhs_f32, rhs_f32) = (coarse_float_mbr::<f32>(&lhs), coarse_float_mbr::<f32>(&rhs));
+        prop_assert_eq!(
+            tree::simd::intersects_f32(&lhs_f32, &rhs_f32),
+            mbr::intersects_scalar(&lhs_f32, &rhs_f32)
+        );
+
+        let (lhs_f64, rhs_f64) = (coarse_float_mbr::<f64>(&lhs), coarse_float_mbr::<f64>(&rhs));
+        prop_assert_eq!(
+            tree::simd::intersects_f64(&lhs_f64, &rhs_f64),
+            mbr::intersects_scalar(&lhs_f64, &rhs_f64)
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+proptest! {
+    #[test]
+    fn serde_property_test(test_params in any::<TestParams>()) {
+        init_logger();
+
+        let tree = hybrid_build_tree(&test_params);
+        for &i in test_params.mark_removed.iter().filter(|&&i| i % 2 == 0) {
+            tree.remove(i);
+        }
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let restored_tree: Tree = serde_json::from_str(&json).unwrap();
+
+        let found: HashSet<_> = restored_tree.search(&test_params.search_mbr).into_iter().collect();
+        let expected: HashSet<_> = tree.search(&test_params.search_mbr).into_iter().collect();
+        prop_assert_eq!(found, expected);
+
+        tree.restore_removed();
+        restored_tree.restore_removed();
+
+        let found: HashSet<_> = restored_tree.search(&test_params.search_mbr).into_iter().collect();
+        let expected: HashSet<_> = tree.search(&test_params.search_mbr).into_iter().collect();
+        prop_assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,186 @@
+//! Vectorized versions of the hot MBR checks for floating point coordinates.
+//!
+//! `mbr::intersects` dispatches the `f32` and `f64` MBRs here.
+//! The results are exactly the same as of the scalar `mbr` functions.
+//! On targets without SSE2 the scalar code is used.
+
+use {
+    super::{CoordTrait, MBR},
+    std::ops::Range,
+};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// The same as `mbr::intersects` for `MBR<f32>`, compares 2 axes at once.
+pub fn intersects_f32(lhs: &MBR<f32>, rhs: &MBR<f32>) -> bool {
+    if std::ptr::eq(lhs, rhs) {
+        return true;
+    }
+
+    let dimension = lhs.dimension().min(rhs.dimension());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let packed_dimension = dimension - dimension % 2;
+        let (lhs_coords, rhs_coords) = (flat_coords(lhs), flat_coords(rhs));
+
+        for axis in (0..packed_dimension).step_by(2) {
+            let coords = axis * 2..axis * 2 + 4;
+            let (lhs, rhs) = (&lhs_coords[coords.clone()], &rhs_coords[coords]);
+
+            // SSE2 is always available on x86_64.
+            let overlaps = unsafe {
+                // [min0, max0, min1, max1]
+                let lhs = _mm_loadu_ps(lhs.as_ptr());
+                let rhs = _mm_loadu_ps(rhs.as_ptr());
+
+                // [lhs.min0, lhs.min1, rhs.min0, rhs.min1] <= [rhs.max0, rhs.max1, lhs.max0, lhs.max1]
+                let mins = _mm_shuffle_ps(lhs, rhs, 0b10_00_10_00);
+                let maxs = _mm_shuffle_ps(rhs, lhs, 0b11_01_11_01);
+
+                _mm_movemask_ps(_mm_cmple_ps(mins, maxs)) == 0b1111
+            };
+
+            if !overlaps {
+                return false;
+            }
+        }
+
+        intersects_axes(lhs, rhs, packed_dimension..dimension)
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    intersects_axes(lhs, rhs, 0..dimension)
+}
+
+/// The same as `mbr::intersects` for `MBR<f64>`, compares 2 axes at once.
+pub fn intersects_f64(lhs: &MBR<f64>, rhs: &MBR<f64>) -> bool {
+    if std::ptr::eq(lhs, rhs) {
+        return true;
+    }
+
+    let dimension = lhs.dimension().min(rhs.dimension());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let packed_dimension = dimension - dimension % 2;
+        let (lhs_coords, rhs_coords) = (flat_coords(lhs), flat_coords(rhs));
+
+        for axis in (0..packed_dimension).step_by(2) {
+            let coords = axis * 2..axis * 2 + 4;
+            let (lhs, rhs) = (&lhs_coords[coords.clone()], &rhs_coords[coords]);
+
+            // SSE2 is always available on x86_64.
+            let overlaps = unsafe {
+                // [min, max] of the both axes
+                let (lhs0, lhs1) = (_mm_loadu_pd(lhs.as_ptr()), _mm_loadu_pd(lhs[2..].as_ptr()));
+                let (rhs0, rhs1) = (_mm_loadu_pd(rhs.as_ptr()), _mm_loadu_pd(rhs[2..].as_ptr()));
+
+                let mask = _mm_and_pd(
+                    _mm_cmple_pd(_mm_unpacklo_pd(lhs0, lhs1), _mm_unpackhi_pd(rhs0, rhs1)),
+                    _mm_cmple_pd(_mm_unpacklo_pd(rhs0, rhs1), _mm_unpackhi_pd(lhs0, lhs1)),
+                );
+
+                _mm_movemask_pd(mask) == 0b11
+            };
+
+            if !overlaps {
+                return false;
+            }
+        }
+
+        intersects_axes(lhs, rhs, packed_dimension..dimension)
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    intersects_axes(lhs, rhs, 0..dimension)
+}
+
+/// The bounds of the MBR as the `[min, max, min, max, ...]` coordinates.
+#[cfg(target_arch = "x86_64")]
+fn flat_coords<CoordT: CoordTrait>(mbr: &MBR<CoordT>) -> &[CoordT] {
+    let bounds = mbr.bounds_slice();
+
+    // `Bounds` is `repr(C)` of two `CoordT`, so it has no padding.
+    unsafe { std::slice::from_raw_parts(bounds.as_ptr() as *const CoordT, bounds.len() * 2) }
+}
+
+/// Handles the axes left after the vectorized part.
+fn intersects_axes<CoordT: CoordTrait>(
+    lhs: &MBR<CoordT>,
+    rhs: &MBR<CoordT>,
+    axes: Range<usize>,
+) -> bool {
+    axes.map(|axis| (lhs.bounds(axis), rhs.bounds(axis)))
+        .all(|(lhs, rhs)| lhs.min <= rhs.max && rhs.min <= lhs.max)
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{mbr, mbr::Bounds},
+    };
+
+    #[test]
+    fn test_simd_intersects() {
+        let lhs = mbr! {
+            X = [0.0; 10.0],
+            Y = [0.0; 10.0],
+            Z = [0.0; 10.0],
+            W = [0.0; 10.0],
+            V = [0.0; 10.0]
+        };
+
+        // Touching on the vectorized and on the leftover axes
+        let touching = mbr! {
+            X = [10.0; 20.0],
+            Y = [-5.0;  0.0],
+            Z = [ 5.0;  6.0],
+            W = [ 5.0;  6.0],
+            V = [10.0; 11.0]
+        };
+
+        let apart = mbr! {
+            X = [ 5.0;  6.0],
+            Y = [ 5.0;  6.0],
+            Z = [ 5.0;  6.0],
+            W = [ 5.0;  6.0],
+            V = [10.5; 11.0]
+        };
+
+        let lower_dimension = mbr! { X = [10.0; 20.0], Y = [5.0; 6.0] };
+        let undefined = unsafe { MBR::undefined() };
+        let nan = unsafe { MBR::new_unchecked(vec![Bounds::new_unchecked(f64::NAN, f64::NAN)]) };
+
+        let cases = [
+            (&lhs, &lhs),
+            (&lhs, &touching),
+            (&lhs, &apart),
+            (&lhs, &lower_dimension),
+            (&lhs, &undefined),
+            (&lhs, &nan),
+            (&nan, &nan),
+        ];
+
+        for &(lhs, rhs) in cases.iter() {
+            assert_eq!(intersects_f64(lhs, rhs), mbr::intersects_scalar(lhs, rhs));
+        }
+
+        assert!(intersects_f64(&lhs, &touching));
+        assert!(!intersects_f64(&lhs, &apart));
+
+        let to_f32 = |mbr: &MBR<f64>| {
+            MBR::new(
+                mbr.iter_bounds()
+                    .map(|bounds| Bounds::new(bounds.min as f32, bounds.max as f32))
+                    .collect(),
+            )
+        };
+
+        assert!(intersects_f32(&to_f32(&lhs), &to_f32(&touching)));
+        assert!(!intersects_f32(&to_f32(&lhs), &to_f32(&apart)));
+        assert!(intersects_f32(&to_f32(&lhs), &unsafe { MBR::undefined() }));
+    }
+}
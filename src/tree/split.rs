@@ -0,0 +1,280 @@
+use {
+    super::{mbr, CoordTrait, ObjSpace, RecordId, MBR},
+    std::{cmp::Ordering, fmt::Debug},
+};
+
+/// Algorithm of splitting an overflowed node, see `LRTree::set_split_strategy`.
+pub trait SplitStrategy<CoordT: CoordTrait, ObjectT: Clone>: Debug + Send + Sync {
+    /// Distributes the `children` of the overflowed node into two groups:
+    /// the first one stays in the node, the second one goes to a new node.
+    ///
+    /// Both groups must be non-empty.
+    fn split(
+        &self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        children: &mut Vec<RecordId>,
+    ) -> (Vec<RecordId>, Vec<RecordId>);
+}
+
+/// The seeds are the records with the greatest normalized separation along some axis,
+/// the rest are distributed by the least enlargement.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinearSplit;
+
+/// Guttman's quadratic split: the seeds are the pair wasting the most volume,
+/// the rest are distributed by the greatest preference for one of the groups first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuadraticSplit;
+
+impl<CoordT: CoordTrait, ObjectT: Debug + Clone> SplitStrategy<CoordT, ObjectT> for LinearSplit {
+    fn split(
+        &self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        children: &mut Vec<RecordId>,
+    ) -> (Vec<RecordId>, Vec<RecordId>) {
+        let (lhs, rhs) = select_first_pair(obj_space, children, obj_space.dimension);
+        crate::debug_log!("select first pair = ({:?}, {:?})", lhs, rhs);
+
+        let mut lhs = Group::new(obj_space, lhs);
+        let mut rhs = Group::new(obj_space, rhs);
+
+        while !children.is_empty() {
+            if fill_up(obj_space, &mut lhs, &mut rhs, children) {
+                break;
+            }
+
+            let rec_id = children.pop().unwrap();
+            let rec_mbr = obj_space.get_mbr(rec_id);
+
+            let delta = mbr::cost_delta(&lhs.mbr, rec_mbr, obj_space.margin_tie_break);
+            let new_delta = mbr::cost_delta(&rhs.mbr, rec_mbr, obj_space.margin_tie_break);
+
+            if delta < new_delta || delta == new_delta && lhs.ids.len() < rhs.ids.len() {
+                lhs.push(obj_space, rec_id);
+            } else {
+                rhs.push(obj_space, rec_id);
+            }
+        }
+
+        (lhs.ids, rhs.ids)
+    }
+}
+
+impl<CoordT: CoordTrait, ObjectT: Debug + Clone> SplitStrategy<CoordT, ObjectT> for QuadraticSplit {
+    fn split(
+        &self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        children: &mut Vec<RecordId>,
+    ) -> (Vec<RecordId>, Vec<RecordId>) {
+        let cost = |mbr: &MBR<CoordT>| mbr.cost(obj_space.margin_tie_break);
+        let waste = |lhs: &MBR<CoordT>, rhs: &MBR<CoordT>| {
+            cost(&mbr::common_mbr(lhs, rhs)) - cost(lhs) - cost(rhs)
+        };
+
+        let (lhs_idx, rhs_idx) = (0..children.len())
+            .flat_map(|lhs_idx| {
+                (lhs_idx + 1..children.len()).map(move |rhs_idx| (lhs_idx, rhs_idx))
+            })
+            .map(|(lhs_idx, rhs_idx)| {
+                let lhs_mbr = obj_space.get_mbr(children[lhs_idx]);
+                let rhs_mbr = obj_space.get_mbr(children[rhs_idx]);
+
+                ((lhs_idx, rhs_idx), waste(lhs_mbr, rhs_mbr))
+            })
+            .max_by(|(_, lhs), (_, rhs)| lhs.partial_cmp(rhs).expect("cmp result is expected"))
+            .map(|(pair, _)| pair)
+            .unwrap();
+
+        // `rhs_idx` is greater, so removing it first doesn't move `lhs_idx`.
+        let rhs = children.swap_remove(rhs_idx);
+        let lhs = children.swap_remove(lhs_idx);
+        crate::debug_log!("select first pair = ({:?}, {:?})", lhs, rhs);
+
+        let mut lhs = Group::new(obj_space, lhs);
+        let mut rhs = Group::new(obj_space, rhs);
+
+        while !children.is_empty() {
+            if fill_up(obj_space, &mut lhs, &mut rhs, children) {
+                break;
+            }
+
+            let (rec_idx, delta, new_delta) = children
+                .iter()
+                .enumerate()
+                .map(|(idx, &rec_id)| {
+                    let rec_mbr = obj_space.get_mbr(rec_id);
+
+                    (
+                        idx,
+                        mbr::cost_delta(&lhs.mbr, rec_mbr, obj_space.margin_tie_break),
+                        mbr::cost_delta(&rhs.mbr, rec_mbr, obj_space.margin_tie_break),
+                    )
+                })
+                .max_by(
+                    |(_, lhs_delta, lhs_new_delta), (_, rhs_delta, rhs_new_delta)| {
+                        (*lhs_delta - *lhs_new_delta)
+                            .abs()
+                            .partial_cmp(&(*rhs_delta - *rhs_new_delta).abs())
+                            .expect("cmp result is expected")
+                    },
+                )
+                .unwrap();
+
+            let rec_id = children.swap_remove(rec_idx);
+
+            let ord = delta
+                .partial_cmp(&new_delta)
+                .expect("cmp result is expected")
+                .then_with(|| {
+                    cost(&lhs.mbr)
+                        .partial_cmp(&cost(&rhs.mbr))
+                        .expect("cmp result is expected")
+                })
+                .then_with(|| lhs.ids.len().cmp(&rhs.ids.len()));
+
+            if ord == Ordering::Greater {
+                rhs.push(obj_space, rec_id);
+            } else {
+                lhs.push(obj_space, rec_id);
+            }
+        }
+
+        (lhs.ids, rhs.ids)
+    }
+}
+
+/// Records of one node being split and their common MBR.
+struct Group<CoordT> {
+    ids: Vec<RecordId>,
+    mbr: MBR<CoordT>,
+}
+
+impl<CoordT: CoordTrait> Group<CoordT> {
+    fn new<ObjectT: Debug + Clone>(obj_space: &ObjSpace<CoordT, ObjectT>, id: RecordId) -> Self {
+        Self {
+            ids: vec![id],
+            mbr: obj_space.get_mbr(id).clone(),
+        }
+    }
+
+    fn push<ObjectT: Debug + Clone>(
+        &mut self,
+        obj_space: &ObjSpace<CoordT, ObjectT>,
+        id: RecordId,
+    ) {
+        self.mbr = mbr::common_mbr(&self.mbr, obj_space.get_mbr(id));
+        self.ids.push(id);
+    }
+}
+
+/// Moves all the rest `children` into a group if it needs them to get `min_records`.
+///
+/// Returns `true` if the `children` are moved.
+fn fill_up<CoordT: CoordTrait, ObjectT: Debug + Clone>(
+    obj_space: &ObjSpace<CoordT, ObjectT>,
+    lhs: &mut Group<CoordT>,
+    rhs: &mut Group<CoordT>,
+    children: &mut Vec<RecordId>,
+) -> bool {
+    let num = children.len();
+
+    for group in [lhs, rhs] {
+        if obj_space.min_records.saturating_sub(group.ids.len()) >= num {
+            group.ids.extend(children.drain(..).rev());
+            return true;
+        }
+    }
+
+    false
+}
+
+fn select_first_pair<CoordT: CoordTrait, ObjectT: Debug + Clone>(
+    obj_space: &ObjSpace<CoordT, ObjectT>,
+    records: &mut Vec<RecordId>,
+    dimension: usize,
+) -> (RecordId, RecordId) {
+    let params = (0..dimension)
+        .map(|dim| (dim, records.iter()))
+        .map(|(dim, mut records)| {
+            let first_id = records.next().unwrap();
+            let bounds = obj_space.get_mbr(*first_id).bounds(dim);
+
+            let mut min = bounds.min.clone();
+            let mut max = bounds.min.clone();
+
+            let mut max_low_idx = 0;
+            let mut max_low_id = first_id;
+            let mut max_low = min.clone();
+
+            let mut min_high_idx = 0;
+            let mut min_high_id = first_id;
+            let mut min_high = max.clone();
+
+            records
+                .enumerate()
+                .map(|(index, id)| {
+                    // We skipped one element, but we need an index for a whole vector
+                    (index + 1, id)
+                })
+                .for_each(|(index, id)| {
+                    let bounds = obj_space.get_mbr(*id).bounds(dim);
+
+                    if bounds.min > max_low {
+                        max_low_idx = index;
+                        max_low_id = id;
+                        max_low = bounds.min.clone();
+                    } else if bounds.max < min_high {
+                        min_high_idx = index;
+                        min_high_id = id;
+                        min_high = bounds.max.clone();
+                    }
+
+                    if bounds.max > max {
+                        max = bounds.max.clone();
+                    }
+
+                    if bounds.min < min {
+                        min = bounds.min.clone();
+                    }
+                });
+
+            let length = max - min;
+
+            // A degenerate axis can't separate records, so it is the worst choice
+            let d = if length.is_zero() {
+                f64::INFINITY
+            } else {
+                let to_f64 = |value: CoordT| {
+                    value
+                        .to_f64()
+                        .expect("CoordT is expected to be convertible to f64")
+                };
+
+                to_f64(min_high - max_low) / to_f64(length)
+            };
+
+            (d, *max_low_id, *min_high_id, max_low_idx, min_high_idx)
+        })
+        .min_by(|(d_lhs, ..), (d_rhs, ..)| d_lhs.partial_cmp(d_rhs).expect("cmp result expected"))
+        .unwrap();
+
+    let (_, mut lhs, mut rhs, mut lhs_idx, mut rhs_idx) = params;
+
+    match rhs_idx.cmp(&lhs_idx) {
+        Ordering::Greater => std::mem::swap(&mut lhs_idx, &mut rhs_idx),
+        Ordering::Equal => {
+            // they are not separated - arbitrarily choose the first and the last
+            lhs_idx = records.len() - 1;
+            rhs_idx = 0;
+
+            lhs = records[lhs_idx];
+            rhs = records[rhs_idx];
+        }
+        _ => {}
+    }
+
+    records.swap_remove(lhs_idx);
+    records.swap_remove(rhs_idx);
+
+    (lhs, rhs)
+}
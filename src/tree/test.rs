@@ -5,9 +5,14 @@ use {
             node::{NodeId, RecordId, RecordIdKind},
             DataNode,
         },
-        InsertHandler, InternalNode, LRTree, ObjSpace, Visitor,
+        CapExceeded, Chebyshev, ChooseSubtree, ConfigError, DepthVisitor, DotVisitor, Euclidean,
+        InsertError, InsertHandler, InsertPolicy, InternalNode, LRTree, LeastEnlargement,
+        LeastOverlap, Manhattan, ObjSpace, QuadraticSplit, SplitStrategy, TreeConfig, Visitor, MBR,
+    },
+    std::{
+        collections::{hash_set::HashSet, HashMap},
+        ops::ControlFlow,
     },
-    std::collections::hash_set::HashSet,
 };
 
 use {
@@ -15,6 +20,15 @@ use {
     std::sync::Once,
 };
 
+#[cfg(feature = "csv")]
+use crate::CsvImportError;
+
+#[cfg(feature = "geojson")]
+use crate::GeoJsonImportError;
+
+#[cfg(feature = "svg")]
+use crate::SvgVisitor;
+
 struct Logger;
 
 impl log::Log for Logger {
@@ -50,7 +64,7 @@ fn test_tree_leaf() {
     init_logger();
 
     let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
-    let root_id = tree.obj_space.read().unwrap().root_id;
+    let root_id = tree.obj_space.read().root_id;
 
     let first_id = tree.insert(
         "First",
@@ -61,7 +75,7 @@ fn test_tree_leaf() {
     );
 
     {
-        let obj_space = tree.obj_space.read().unwrap();
+        let obj_space = tree.obj_space.read();
         let tree_mbr = obj_space.get_mbr(root_id);
 
         tree.access_object(first_id, |object, mbr| {
@@ -83,7 +97,7 @@ fn test_tree_leaf() {
     });
 
     {
-        let obj_space = tree.obj_space.read().unwrap();
+        let obj_space = tree.obj_space.read();
         let tree_mbr = obj_space.get_mbr(root_id);
 
         assert_eq!(
@@ -136,7 +150,7 @@ fn test_tree_leaf() {
     });
 
     {
-        let obj_space = tree.obj_space.read().unwrap();
+        let obj_space = tree.obj_space.read();
         let tree_mbr = obj_space.get_mbr(root_id);
 
         assert_eq!(
@@ -149,12 +163,160 @@ fn test_tree_leaf() {
     }
 }
 
+#[test]
+fn test_tree_search_objects() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    tree.insert(
+        "First",
+        mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        },
+    );
+
+    tree.insert(
+        "Second",
+        mbr! {
+            X = [-5; -3],
+            Y = [-5;  5]
+        },
+    );
+
+    tree.insert(
+        "Third",
+        mbr! {
+            X = [-4; 4],
+            Y = [ 2; 3]
+        },
+    );
+
+    let areas = [
+        mbr! { X = [-2; -1], Y = [-3; 3] },
+        mbr! { X = [ 7; 15], Y = [ 2; 3] },
+        mbr! { X = [-7; -4], Y = [ 2; 3] },
+        mbr! { X = [-4;  4], Y = [ 2; 3] },
+    ];
+
+    for area in areas.iter() {
+        let expected = tree
+            .search(area)
+            .into_iter()
+            .map(|id| tree.access_object(id, |&object, mbr| (object, mbr.clone())))
+            .collect::<Vec<_>>();
+
+        let found = tree.search_objects(area, |&object, mbr| (object, mbr.clone()));
+
+        assert_eq!(found, expected);
+    }
+
+    let found = tree.search_objects(&mbr! { X = [-4; 4], Y = [2; 3] }, |&object, _| object);
+    assert_eq!(found, vec!["First", "Second", "Third"]);
+}
+
+#[test]
+fn test_tree_set_payload() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    let first_id = tree.insert("First", mbr! { X = [0; 10], Y = [0; 10] });
+    let second_id = tree.insert("Second", mbr! { X = [-5; -3], Y = [-5; 5] });
+
+    assert_eq!(tree.set_payload(first_id, "Updated"), Some("First"));
+
+    let area = mbr! {
+        X = [7; 15],
+        Y = [2;  3]
+    };
+
+    assert_eq!(tree.search(&area), vec![first_id]);
+
+    tree.access_object(first_id, |&object, mbr| {
+        assert_eq!(object, "Updated");
+        assert_eq!(*mbr, mbr! { X = [0; 10], Y = [0; 10] });
+    });
+
+    tree.mark_as_removed(vec![second_id].into_iter());
+    assert_eq!(tree.set_payload(second_id, "Removed"), None);
+
+    tree.restore_removed();
+    tree.access_object(second_id, |&object, _| assert_eq!(object, "Second"));
+}
+
+#[test]
+fn test_tree_for_each_object() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    let first_id = tree.insert("First", mbr! { X = [0; 10], Y = [0; 10] });
+    let second_id = tree.insert("Second", mbr! { X = [-5; -3], Y = [-5; 5] });
+    let third_id = tree.insert("Third", mbr! { X = [-4; 4], Y = [2; 3] });
+
+    tree.mark_as_removed(vec![second_id].into_iter());
+
+    let mut visited = vec![];
+    tree.for_each_object(|id, &object, mbr| visited.push((id, object, mbr.clone())));
+
+    visited.sort_unstable_by_key(|&(id, ..)| id);
+
+    assert_eq!(
+        visited,
+        vec![
+            (first_id, "First", mbr! { X = [0; 10], Y = [0; 10] }),
+            (third_id, "Third", mbr! { X = [-4; 4], Y = [2; 3] }),
+        ]
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_tree_par_search() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    for x in 0..40 {
+        for y in 0..40 {
+            tree.insert(
+                (x * 40 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let areas = [
+        mbr! { X = [  0; 400], Y = [  0; 400] },
+        mbr! { X = [ 33;  67], Y = [ 12; 388] },
+        mbr! { X = [  6;   9], Y = [  0; 400] },
+        mbr! { X = [500; 600], Y = [500; 600] },
+    ];
+
+    for area in areas.iter() {
+        let expected = tree.search(area).into_iter().collect::<HashSet<_>>();
+        let found = tree.par_search(area);
+
+        assert_eq!(found.len(), expected.len());
+        assert_eq!(found.into_iter().collect::<HashSet<_>>(), expected);
+    }
+
+    assert!(LRTree::<i32, usize>::with_obj_space(ObjSpace::new(2, 2, 5))
+        .par_search(&areas[0])
+        .is_empty());
+}
+
 #[test]
 fn test_tree_search_access() {
     init_logger();
 
     let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
-    let root_id = tree.obj_space.read().unwrap().root_id;
+    let root_id = tree.obj_space.read().root_id;
 
     let first_id = tree.insert(
         "First",
@@ -165,7 +327,7 @@ fn test_tree_search_access() {
     );
 
     {
-        let obj_space = tree.obj_space.read().unwrap();
+        let obj_space = tree.obj_space.read();
         let tree_mbr = obj_space.get_mbr(root_id);
 
         tree.access_object(first_id, |object, mbr| {
@@ -187,7 +349,7 @@ fn test_tree_search_access() {
     });
 
     {
-        let obj_space = tree.obj_space.read().unwrap();
+        let obj_space = tree.obj_space.read();
         let tree_mbr = obj_space.get_mbr(root_id);
 
         assert_eq!(
@@ -333,384 +495,4136 @@ fn test_tree_split() {
 }
 
 #[test]
-fn test_tree_insert_transaction() {
+fn test_tree_nearest() {
     init_logger();
 
     let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
-    tree.insert(
-        1,
-        mbr! {
-            X = [0; 10],
-            Y = [0; 10]
-        },
-    );
+    assert!(tree.nearest(&[0, 0], 3).is_empty());
 
-    tree.insert(
-        2,
-        mbr! {
-            X = [11; 21],
-            Y = [ 0; 10]
-        },
-    );
+    let mbrs = vec![
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
 
-    tree.insert(
-        3,
-        mbr! {
-            X = [22; 32],
-            Y = [ 0; 10]
-        },
-    );
+    for (object, mbr) in mbrs.into_iter().enumerate() {
+        tree.insert(object + 1, mbr);
+    }
 
-    tree.insert(
-        4,
-        mbr! {
-            X = [ 0; 10],
-            Y = [11; 21]
-        },
-    );
+    let nearest_objects = |point: &[i32], k| {
+        tree.nearest(point, k)
+            .into_iter()
+            .map(|id| tree.access_object(id, |&object, _| object))
+            .collect::<Vec<_>>()
+    };
 
-    tree.insert(
-        5,
-        mbr! {
-            X = [11; 21],
-            Y = [11; 21]
-        },
-    );
+    // Distances: #3 -- 0, #2 -- 3, #6 -- 6, #5 -- sqrt(45).
+    assert_eq!(nearest_objects(&[24, 5], 3), vec![3, 2, 6]);
 
-    tree.insert(
-        6,
-        mbr! {
-            X = [22; 32],
-            Y = [11; 21]
-        },
-    );
+    // Distances: #5 -- 0, #2 and #4 -- 1, #1 -- sqrt(2).
+    assert_eq!(nearest_objects(&[11, 11], 1), vec![5]);
+    assert_eq!(nearest_objects(&[-5, -5], 0), Vec::<usize>::new());
 
-    macro_rules! new_value {
-        () => {
-            7
-        };
-    }
+    let mut all = nearest_objects(&[16, 10], 10);
+    assert_eq!(all.len(), 6);
+    assert_eq!(all[0], 2);
 
-    struct Handler;
+    all.sort_unstable();
+    assert_eq!(all, vec![1, 2, 3, 4, 5, 6]);
+}
 
-    impl InsertHandler<i32, i32> for Handler {
-        fn before_insert(&mut self, obj_space: &ObjSpace<i32, i32>, new_id: usize) {
-            assert_eq!(*obj_space.get_data_payload(new_id), new_value![]);
+#[test]
+fn test_tree_search_count() {
+    init_logger();
 
-            let expected: HashSet<NodeId> = [0, 1, 2, 3, 4, 5].iter().cloned().collect();
-            let mut intersections = HashSet::new();
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    assert_eq!(tree.search_count(&mbr! { X = [0; 32], Y = [0; 21] }), 0);
 
-            LRTree::search_access_obj_space(obj_space, obj_space.get_data_mbr(new_id), |_, id| {
-                intersections.insert(id);
-            });
+    let mbrs = [
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
 
-            assert_eq!(intersections, expected);
-        }
+    for (object, mbr) in mbrs.iter().cloned().enumerate() {
+        tree.insert(object + 1, mbr);
     }
 
-    tree.insert_transaction(
-        new_value![],
-        mbr! {
-            X = [3; 25],
-            Y = [3; 15]
-        },
-        &mut Handler,
-    );
+    let areas = [
+        mbr! { X = [ 3; 25], Y = [ 3; 15] },
+        mbr! { X = [ 3;  5], Y = [ 3;  5] },
+        mbr! { X = [10; 11], Y = [ 0;  0] },
+        mbr! { X = [40; 50], Y = [40; 50] },
+        mbr! { X = [21; 22], Y = [10; 11] },
+    ];
+
+    for area in areas.iter() {
+        assert_eq!(tree.search_count(area), tree.search(area).len());
+    }
+
+    assert_eq!(tree.search_count(&areas[0]), 6);
+    assert_eq!(tree.search_count(&areas[3]), 0);
+    assert_eq!(tree.search_count(&areas[4]), 4);
 }
 
 #[test]
-fn test_tree_retain() {
+fn test_tree_search_contained() {
     init_logger();
 
-    let mut remove_values = vec![];
     let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
-    tree.insert(
-        1,
+
+    let interior_id = tree.insert(
+        "Interior",
         mbr! {
-            X = [0; 10],
-            Y = [0; 10]
+            X = [2; 4],
+            Y = [2; 4]
         },
     );
 
-    let value = tree.insert(
-        2,
+    let straddling_id = tree.insert(
+        "Straddling",
         mbr! {
-            X = [11; 21],
-            Y = [ 0; 10]
+            X = [8; 12],
+            Y = [2;  4]
         },
     );
-    remove_values.push(value);
 
-    tree.insert(
-        3,
+    let touching_id = tree.insert(
+        "Touching",
         mbr! {
-            X = [22; 32],
-            Y = [ 0; 10]
+            X = [0; 10],
+            Y = [9; 10]
         },
     );
 
     tree.insert(
-        4,
+        "Outside",
         mbr! {
-            X = [ 0; 10],
-            Y = [11; 21]
+            X = [20; 30],
+            Y = [20; 30]
         },
     );
 
-    let value = tree.insert(
+    let area = mbr! {
+        X = [0; 10],
+        Y = [0; 10]
+    };
+
+    let found: HashSet<NodeId> = tree.search_contained(&area).into_iter().collect();
+    let expected: HashSet<NodeId> = [interior_id, touching_id].iter().cloned().collect();
+
+    assert_eq!(found, expected);
+    assert!(!found.contains(&straddling_id));
+    assert!(tree.search(&area).contains(&straddling_id));
+}
+
+#[test]
+fn test_tree_search_containing_point() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    assert!(tree.search_containing_point(&[5, 5]).is_empty());
+
+    let mut covering_ids = HashSet::new();
+    for i in 0..5 {
+        let id = tree.insert(
+            i,
+            mbr! {
+                X = [i; 10 + i],
+                Y = [0; 10 - i]
+            },
+        );
+
+        covering_ids.insert(id);
+    }
+
+    let far_id = tree.insert(
         5,
         mbr! {
-            X = [11; 21],
-            Y = [11; 21]
+            X = [20; 30],
+            Y = [20; 30]
         },
     );
-    remove_values.push(value);
 
-    tree.insert(
+    let edge_id = tree.insert(
         6,
         mbr! {
-            X = [22; 32],
-            Y = [11; 21]
+            X = [0; 5],
+            Y = [6; 8]
         },
     );
 
-    tree.retain(
-        &mbr! {
-            X = [3; 25],
-            Y = [3; 15]
-        },
-        |obj_space, id| !remove_values.contains(&obj_space.get_data_payload(id)),
-    );
+    let found: HashSet<NodeId> = tree.search_containing_point(&[5, 5]).into_iter().collect();
+    assert_eq!(found, covering_ids);
 
-    let root_mbr = tree.lock_obj_space().get_root_mbr().clone();
-    let new_tree = LRTree::with_obj_space(tree.lock_obj_space().clone_shrinked());
+    let found: HashSet<NodeId> = tree.search_containing_point(&[5, 6]).into_iter().collect();
+    assert!(found.contains(&edge_id));
 
-    new_tree.search_access(&root_mbr, |obj_space, id| {
-        assert!(!remove_values.contains(&obj_space.get_data_payload(id)))
-    });
+    assert_eq!(tree.search_containing_point(&[25, 20]), vec![far_id]);
+    assert!(tree.search_containing_point(&[15, 15]).is_empty());
 }
 
 #[test]
-fn test_tree_same_delta() {
+fn test_tree_search_within() {
     init_logger();
 
     let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
-    let mut obj_space = tree.obj_space.write().unwrap();
+    assert!(tree.search_within(&[24, 5], 100.0).is_empty());
 
-    let first_node_id = obj_space.make_node(RecordIdKind::Leaf);
-    let node = obj_space.get_node_mut(first_node_id);
-    node.mbr = mbr! {
-        X = [0; 5],
-        Y = [0; 5]
-    };
+    let mbrs = [
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
 
-    let second_node_id = obj_space.make_node(RecordIdKind::Leaf);
-    let node = obj_space.get_node_mut(second_node_id);
-    node.mbr = mbr! {
-        X = [12; 13],
-        Y = [-1; 4]
+    for (object, mbr) in mbrs.iter().cloned().enumerate() {
+        tree.insert(object + 1, mbr);
+    }
+
+    let objects_within = |point: &[i32], radius| {
+        let mut objects = tree
+            .search_within(point, radius)
+            .into_iter()
+            .map(|id| tree.access_object(id, |&object, _| object))
+            .collect::<Vec<_>>();
+
+        objects.sort_unstable();
+        objects
     };
 
-    let root_id = RecordId::from_node_id(obj_space.root_id.as_node_id(), RecordIdKind::Internal);
-    obj_space.root_id = root_id;
-    bind!([obj_space] root_id => first_node_id);
-    bind!([obj_space] root_id => second_node_id);
+    // Distances: #3 -- 0, #2 -- 3, #6 -- 6, #5 -- sqrt(45).
+    assert_eq!(objects_within(&[24, 5], 4.0), vec![2, 3]);
+    assert_eq!(objects_within(&[24, 5], 6.0), vec![2, 3, 6]);
 
-    let node_0_data_0 = obj_space.make_data_node(
-        0,
-        mbr! {
-            X = [0; 3],
-            Y = [0; 3]
-        },
-    );
-    obj_space.get_data_mut(node_0_data_0).parent_id = first_node_id;
+    assert_eq!(objects_within(&[10, 5], 0.0), vec![1]);
+    assert_eq!(objects_within(&[15, 15], 0.0), vec![5]);
+    assert!(objects_within(&[40, 40], 0.0).is_empty());
+    assert_eq!(objects_within(&[40, 40], 1000.0), vec![1, 2, 3, 4, 5, 6]);
+}
 
-    let node_0_data_1 = obj_space.make_data_node(
-        1,
-        mbr! {
-            X = [4; 5],
-            Y = [4; 5]
-        },
+#[test]
+fn test_tree_search_iter() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    assert_eq!(
+        tree.search_iter(&mbr! { X = [0; 100], Y = [0; 100] })
+            .count(),
+        0
     );
-    obj_space.get_data_mut(node_0_data_1).parent_id = first_node_id;
+
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x, y),
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let areas = [
+        mbr! { X = [ 0; 100], Y = [ 0; 100] },
+        mbr! { X = [ 3;  25], Y = [ 3;  15] },
+        mbr! { X = [ 6;   9], Y = [ 6;   9] },
+        mbr! { X = [45;  45], Y = [ 0; 100] },
+    ];
+
+    for area in areas.iter() {
+        assert_eq!(
+            tree.search_iter(area).collect::<Vec<_>>(),
+            tree.search(area)
+        );
+    }
+
+    let first = tree.search_iter(&areas[0]).take(10).collect::<Vec<_>>();
+    assert_eq!(first, tree.search(&areas[0])[..10]);
+}
+
+#[test]
+fn test_tree_search_access_while() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x, y),
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let area = mbr! {
+        X = [0; 100],
+        Y = [0; 100]
+    };
+
+    let mut all_visited = 0;
+    tree.search_access_while(&area, |_, _| {
+        all_visited += 1;
+        ControlFlow::Continue(())
+    });
+
+    assert_eq!(all_visited, 100);
+
+    let mut visited = 0;
+    let mut found = None;
+    tree.search_access_while(&area, |obj_space, id| {
+        visited += 1;
+
+        if obj_space.get_data_payload(id).0 == 5 {
+            found = Some(id);
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert!(visited < all_visited);
+    assert_eq!(tree.access_object(found.unwrap(), |&(x, _), _| x), 5);
+
+    let mut visited = 0;
+    tree.search_access_while(&area, |_, _| {
+        visited += 1;
+        ControlFlow::Break(())
+    });
+
+    assert_eq!(visited, 1);
+}
+
+#[test]
+fn test_tree_nearest_iter() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    assert_eq!(tree.nearest_iter(&[0, 0]).count(), 0);
+
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x, y),
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let nearest = tree.nearest_iter(&[37, 52]).collect::<Vec<_>>();
+    assert_eq!(nearest.len(), 100);
+
+    assert!(nearest.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    let (first_id, first_distance) = nearest[0];
+    assert_eq!(first_distance, 2.0);
+    assert_eq!(tree.access_object(first_id, |&object, _| object), (3, 5));
+
+    let (_, last_distance) = nearest[99];
+    assert_eq!(last_distance, (53.0f64 * 53.0 + 47.0 * 47.0).sqrt());
+}
+
+#[test]
+fn test_tree_search_ray() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    assert!(tree.search_ray(&[-5, 5], &[1, 0]).is_empty());
+
+    let mbrs = [
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
+
+    for (object, mbr) in mbrs.iter().cloned().enumerate() {
+        tree.insert(object + 1, mbr);
+    }
+
+    let objects_on_ray = |origin: &[i32], dir: &[i32]| {
+        let mut objects = tree
+            .search_ray(origin, dir)
+            .into_iter()
+            .map(|id| tree.access_object(id, |&object, _| object))
+            .collect::<Vec<_>>();
+
+        objects.sort_unstable();
+        objects
+    };
+
+    assert_eq!(objects_on_ray(&[-5, 5], &[1, 0]), vec![1, 2, 3]);
+    assert_eq!(objects_on_ray(&[40, 15], &[-1, 0]), vec![4, 5, 6]);
+    assert_eq!(objects_on_ray(&[15, 5], &[1, 0]), vec![2, 3]);
+    assert!(objects_on_ray(&[40, 5], &[1, 0]).is_empty());
+    assert!(objects_on_ray(&[-5, 30], &[1, 0]).is_empty());
+}
+
+#[test]
+fn test_tree_search_sorted() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    let far_id = tree.insert("Far", mbr! { X = [40; 50], Y = [0; 10] });
+    let near_id = tree.insert("Near", mbr! { X = [0; 10], Y = [0; 10] });
+    let middle_id = tree.insert("Middle", mbr! { X = [13; 20], Y = [14; 20] });
+    let middle_twin_id = tree.insert("Middle twin", mbr! { X = [14; 20], Y = [13; 20] });
+    tree.insert("Outside", mbr! { X = [100; 110], Y = [100; 110] });
+
+    let area = mbr! {
+        X = [0; 60],
+        Y = [0; 60]
+    };
+
+    let found = tree.search_sorted(&area, &[5, 10]);
+
+    assert_eq!(
+        found,
+        vec![
+            (near_id, 0.0),
+            (middle_id, (8.0f64 * 8.0 + 4.0 * 4.0).sqrt()),
+            (middle_twin_id, (9.0f64 * 9.0 + 3.0 * 3.0).sqrt()),
+            (far_id, 35.0),
+        ]
+    );
+
+    let found = tree.search_sorted(&mbr! { X = [14; 20], Y = [14; 20] }, &[10, 10]);
+
+    assert_eq!(
+        found,
+        vec![
+            (middle_id, (3.0f64 * 3.0 + 4.0 * 4.0).sqrt()),
+            (middle_twin_id, (4.0f64 * 4.0 + 3.0 * 3.0).sqrt()),
+        ]
+    );
+    assert!(middle_id < middle_twin_id);
+}
+
+#[test]
+fn test_tree_any_in_area() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    assert!(!tree.any_in_area(&mbr! { X = [0; 32], Y = [0; 21] }));
+
+    let mbrs = [
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
+
+    for (object, mbr) in mbrs.iter().cloned().enumerate() {
+        tree.insert(object + 1, mbr);
+    }
+
+    let areas = [
+        mbr! { X = [ 3; 25], Y = [ 3; 15] },
+        mbr! { X = [ 3;  5], Y = [ 3;  5] },
+        mbr! { X = [10; 11], Y = [ 0;  0] },
+        mbr! { X = [40; 50], Y = [40; 50] },
+        mbr! { X = [-5; -1], Y = [ 0; 10] },
+        mbr! { X = [21; 22], Y = [10; 11] },
+    ];
+
+    for area in areas.iter() {
+        assert_eq!(tree.any_in_area(area), !tree.search(area).is_empty());
+    }
+
+    assert!(tree.any_in_area(&areas[0]));
+    assert!(!tree.any_in_area(&areas[3]));
+}
+
+#[test]
+fn test_tree_nearest_by() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    // L-shaped layout around the query point at the origin:
+    // the arms are on the axes and the corner is on the diagonal.
+    let corner_id = tree.insert("Corner", mbr! { X = [3; 4], Y = [3; 4] });
+    let horizontal_id = tree.insert("Horizontal", mbr! { X = [5; 9], Y = [0; 1] });
+    let vertical_id = tree.insert("Vertical", mbr! { X = [-1; 0], Y = [6; 9] });
+
+    // Corner: L2 -- sqrt(18), L1 -- 6, L∞ -- 3.
+    // Horizontal: L2 -- 5, L1 -- 5, L∞ -- 5.
+    // Vertical: L2 -- 6, L1 -- 6, L∞ -- 6.
+    assert_eq!(tree.nearest(&[0, 0], 1), vec![corner_id]);
+    assert_eq!(tree.nearest_by(&[0, 0], 1, Euclidean), vec![corner_id]);
+    assert_eq!(tree.nearest_by(&[0, 0], 1, Manhattan), vec![horizontal_id]);
+    assert_eq!(tree.nearest_by(&[0, 0], 1, Chebyshev), vec![corner_id]);
+
+    assert_eq!(
+        tree.nearest_by(&[0, 0], 3, Euclidean),
+        vec![corner_id, horizontal_id, vertical_id]
+    );
+
+    let distances = tree
+        .nearest_iter_by(&[0, 0], Manhattan)
+        .map(|(_, distance)| distance)
+        .collect::<Vec<_>>();
+
+    assert_eq!(distances, vec![5.0, 6.0, 6.0]);
+}
+
+#[test]
+fn test_tree_intersecting_pairs() {
+    init_logger();
+
+    let brute_force_pairs = |tree: &LRTree<i32, usize>| {
+        let obj_space = tree.lock_obj_space();
+        let objects = obj_space.iter().collect::<Vec<_>>();
+
+        let mut pairs = HashSet::new();
+        for (i, (lhs_id, _, lhs_mbr)) in objects.iter().enumerate() {
+            for (rhs_id, _, rhs_mbr) in objects[i + 1..].iter() {
+                if mbr::intersects(lhs_mbr, rhs_mbr) {
+                    pairs.insert((*lhs_id.min(rhs_id), *lhs_id.max(rhs_id)));
+                }
+            }
+        }
+
+        pairs
+    };
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    assert!(tree.intersecting_pairs().is_empty());
+
+    let mbrs = [
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
+
+    for (object, mbr) in mbrs.iter().cloned().enumerate() {
+        tree.insert(object + 1, mbr);
+    }
+
+    assert!(tree.intersecting_pairs().is_empty());
+
+    let cross_id = tree.insert(7, mbr! { X = [5; 25], Y = [10; 11] });
+    let pairs = tree.intersecting_pairs();
+
+    assert_eq!(pairs.len(), 6);
+    assert!(pairs.iter().all(|&(lhs, rhs)| lhs < rhs));
+    assert!(pairs
+        .iter()
+        .all(|&(lhs, rhs)| lhs == cross_id || rhs == cross_id));
+    assert_eq!(
+        pairs.iter().cloned().collect::<HashSet<_>>(),
+        brute_force_pairs(&tree)
+    );
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 10 + y % 3],
+                    Y = [y * 10; y * 10 + 5 + x % 7]
+                },
+            );
+        }
+    }
+
+    let pairs = tree.intersecting_pairs();
+    let expected = brute_force_pairs(&tree);
+
+    assert_eq!(pairs.len(), expected.len());
+    assert_eq!(pairs.into_iter().collect::<HashSet<_>>(), expected);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_tree_par_rebuild() {
+    init_logger();
+
+    // Deterministic pseudo-random MBRs
+    let mut seed = 7u64;
+    let mut next = move |max: u64| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 33) % max) as i32
+    };
+
+    let items = (0..2000)
+        .map(|object| {
+            let x = next(1000);
+            let y = next(1000);
+
+            (
+                object,
+                mbr! { X = [x; x + 1 + next(30)], Y = [y; y + 1 + next(30)] },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for &alpha in [0.0, 0.25, 0.5].iter() {
+        let tree = LRTree::with_obj_space(ObjSpace::with_data(2, 2, 6, items.iter().cloned()));
+        tree.rebuild(alpha);
+
+        let par_tree = LRTree::with_obj_space(ObjSpace::with_data(2, 2, 6, items.iter().cloned()));
+        par_tree.par_rebuild(alpha);
+
+        assert_eq!(check_tree_structure(&par_tree), check_tree_structure(&tree));
+        assert_eq!(par_tree.height(), tree.height());
+
+        for _ in 0..20 {
+            let x = next(1000);
+            let y = next(1000);
+            let area = mbr! { X = [x; x + next(200)], Y = [y; y + next(200)] };
+
+            let found = par_tree.search(&area).into_iter().collect::<HashSet<_>>();
+            let expected = tree.search(&area).into_iter().collect::<HashSet<_>>();
+
+            assert_eq!(found, expected);
+        }
+    }
+}
+
+#[test]
+fn test_tree_insert_many() {
+    init_logger();
+
+    // Deterministic pseudo-random MBRs
+    let mut seed = 42u64;
+    let mut next = move |max: u64| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 33) % max) as i32
+    };
+
+    let items = (0..1000)
+        .map(|object| {
+            let x = next(1000);
+            let y = next(1000);
+
+            (
+                object,
+                mbr! { X = [x; x + next(30)], Y = [y; y + next(30)] },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let one_by_one_tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 6));
+    let one_by_one_ids = items
+        .iter()
+        .cloned()
+        .map(|(object, mbr)| one_by_one_tree.insert(object, mbr))
+        .collect::<Vec<_>>();
+
+    let bulk_tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 6));
+    let bulk_ids = bulk_tree.insert_many(items.iter().cloned());
+
+    assert_eq!(bulk_ids, one_by_one_ids);
+
+    let mixed_tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 6));
+    let (head, tail) = items.split_at(300);
+    let mut mixed_ids = mixed_tree.insert_many(head.iter().cloned());
+    mixed_ids.extend(mixed_tree.insert_many(tail.iter().cloned()));
+
+    assert_eq!(mixed_ids, one_by_one_ids);
+
+    let found_objects = |tree: &LRTree<i32, usize>, area: &MBR<i32>| {
+        tree.search_objects(area, |&object, _| object)
+            .into_iter()
+            .collect::<HashSet<_>>()
+    };
+
+    for _ in 0..50 {
+        let x = next(1000);
+        let y = next(1000);
+        let area = mbr! { X = [x; x + next(200)], Y = [y; y + next(200)] };
+
+        let expected = found_objects(&one_by_one_tree, &area);
+
+        assert_eq!(found_objects(&bulk_tree, &area), expected);
+        assert_eq!(found_objects(&mixed_tree, &area), expected);
+    }
+
+    let everything = mbr! { X = [0; 2000], Y = [0; 2000] };
+    assert_eq!(found_objects(&bulk_tree, &everything).len(), 1000);
+    assert_eq!(found_objects(&mixed_tree, &everything).len(), 1000);
+}
+
+#[test]
+fn test_tree_len() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    assert_eq!(tree.len(), 0);
+    assert!(tree.is_empty());
+
+    let ids = (0..10)
+        .map(|i| tree.insert(i, mbr! { X = [i; i + 1], Y = [0; 1] }))
+        .collect::<Vec<_>>();
+
+    assert_eq!(tree.len(), 10);
+    assert!(!tree.is_empty());
+
+    tree.mark_as_removed(ids[..3].iter().cloned());
+    assert_eq!(tree.len(), 7);
+
+    tree.remove(ids[5]);
+    assert_eq!(tree.len(), 6);
+
+    tree.mark_as_removed(ids.iter().cloned());
+    assert_eq!(tree.len(), 0);
+    assert!(tree.is_empty());
+
+    tree.restore_many(ids[..2].iter().cloned());
+    assert_eq!(tree.len(), 2);
+}
+
+#[test]
+fn test_tree_clear() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    tree.clear();
+    assert!(tree.is_empty());
+
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                "old",
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    tree.mark_as_removed(vec![1, 2, 3].into_iter());
+    tree.clear();
+
+    {
+        let obj_space = tree.lock_obj_space();
+
+        assert!(obj_space.is_empty());
+        assert_eq!(obj_space.dimension, 2);
+        assert_eq!(obj_space.min_records, 2);
+        assert_eq!(obj_space.max_records, 3);
+        assert!(obj_space.get_root_mbr().is_undefined());
+    }
+
+    let everything = mbr! {
+        X = [0; 100],
+        Y = [0; 100]
+    };
+
+    assert!(tree.search(&everything).is_empty());
+
+    let first_id = tree.insert("new", mbr! { X = [0; 10], Y = [0; 10] });
+    let second_id = tree.insert("new", mbr! { X = [20; 30], Y = [20; 30] });
+
+    tree.restore_removed();
+
+    let mut found = tree.search(&everything);
+    found.sort_unstable();
+
+    assert_eq!(found, vec![first_id, second_id]);
+    assert_eq!(
+        tree.search_objects(&everything, |&object, _| object),
+        vec!["new", "new"]
+    );
+}
+
+#[test]
+fn test_tree_clear_reuses_ids() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    let old_ids = (0..5)
+        .map(|i| tree.insert("old", mbr! { X = [i; i + 1], Y = [0; 1] }))
+        .collect::<Vec<_>>();
+
+    tree.clear();
+
+    let new_id = tree.insert("new", mbr! { X = [50; 60], Y = [0; 1] });
+
+    // The old ID now refers to the new object
+    assert_eq!(new_id, old_ids[0]);
+    assert_eq!(tree.access_object(old_ids[0], |&object, _| object), "new");
+    assert!(tree.search(&mbr! { X = [0; 5], Y = [0; 1] }).is_empty());
+
+    // `remove` keeps the IDs unique
+    assert_eq!(tree.remove(new_id), Some("new"));
+    let next_id = tree.insert("next", mbr! { X = [50; 60], Y = [0; 1] });
+    assert_ne!(next_id, new_id);
+}
+
+#[test]
+fn test_tree_try_insert() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+
+    let ids = (0..5)
+        .map(|i| tree.try_insert(i, mbr! { X = [i; i + 1], Y = [0; 1] }))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let root_mbr = tree.lock_obj_space().get_root_mbr().clone();
+    let height = tree.height();
+
+    let result = tree.try_insert(5, mbr! { X = [0; 100], Y = [0; 100], Z = [0; 100] });
+
+    assert_eq!(
+        result,
+        Err(InsertError::DimensionMismatch {
+            expected: 2,
+            got: 3
+        })
+    );
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "unexpected dimension: the tree dimension is 2, the MBR dimension is 3"
+    );
+
+    assert_eq!(tree.len(), 5);
+    assert_eq!(tree.height(), height);
+    assert_eq!(*tree.lock_obj_space().get_root_mbr(), root_mbr);
+
+    let mut found = tree.search(&mbr! { X = [0; 100], Y = [0; 100] });
+    found.sort_unstable();
+    assert_eq!(found, ids);
+}
+
+#[test]
+#[should_panic(expected = "unexpected dimension")]
+fn test_panic_tree_insert() {
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    tree.insert(0, mbr! { X = [0; 1] });
+}
+
+#[test]
+fn test_tree_insert_transaction() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.insert(
+        1,
+        mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        },
+    );
+
+    tree.insert(
+        2,
+        mbr! {
+            X = [11; 21],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        3,
+        mbr! {
+            X = [22; 32],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        4,
+        mbr! {
+            X = [ 0; 10],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        5,
+        mbr! {
+            X = [11; 21],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        6,
+        mbr! {
+            X = [22; 32],
+            Y = [11; 21]
+        },
+    );
+
+    macro_rules! new_value {
+        () => {
+            7
+        };
+    }
+
+    struct Handler;
+
+    impl InsertHandler<i32, i32> for Handler {
+        fn before_insert(&mut self, obj_space: &ObjSpace<i32, i32>, new_id: usize) {
+            assert_eq!(*obj_space.get_data_payload(new_id), new_value![]);
+
+            let expected: HashSet<NodeId> = [0, 1, 2, 3, 4, 5].iter().cloned().collect();
+            let mut intersections = HashSet::new();
+
+            LRTree::search_access_obj_space(obj_space, obj_space.get_data_mbr(new_id), |_, id| {
+                intersections.insert(id);
+            });
+
+            assert_eq!(intersections, expected);
+        }
+    }
+
+    tree.insert_transaction(
+        new_value![],
+        mbr! {
+            X = [3; 25],
+            Y = [3; 15]
+        },
+        &mut Handler,
+    );
+}
+
+#[test]
+fn test_tree_count_matching() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+    assert_eq!(
+        tree.count_matching(&mbr! { X = [0; 100], Y = [0; 100] }, |_, _| true),
+        0
+    );
+
+    for x in 0..10 {
+        for y in 0..10 {
+            let kind = if (x + y) % 3 == 0 { "enemy" } else { "ally" };
+
+            tree.insert(
+                kind,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let is_enemy = |obj_space: &ObjSpace<i32, &str>, id| *obj_space.get_data_payload(id) == "enemy";
+
+    // Cells with x, y in [0; 4]: 25 in total, 8 of them with `(x + y) % 3 == 0`.
+    let quadrant = mbr! {
+        X = [0; 45],
+        Y = [0; 45]
+    };
+
+    assert_eq!(tree.count_matching(&quadrant, is_enemy), 8);
+    assert_eq!(tree.count_matching(&quadrant, |_, _| true), 25);
+    assert_eq!(tree.count_matching(&quadrant, |_, _| false), 0);
+
+    let everything = mbr! {
+        X = [0; 100],
+        Y = [0; 100]
+    };
+
+    assert_eq!(tree.count_matching(&everything, is_enemy), 34);
+}
+
+fn check_tree_structure(tree: &LRTree<i32, usize>) -> usize {
+    let obj_space = tree.lock_obj_space();
+    check_subtree(&obj_space, obj_space.root_id)
+}
+
+/// Checks the subtree invariants and returns the number of its nodes (except data ones).
+fn check_subtree(obj_space: &ObjSpace<i32, usize>, node_id: RecordId) -> usize {
+    let node = obj_space.get_node(node_id);
+    let children_mbr = mbr::common_mbr_from_iter(
+        node.payload
+            .iter()
+            .map(|&child_id| obj_space.get_mbr(child_id)),
+    );
+
+    assert_eq!(node.mbr, children_mbr, "{:?} MBR is not tight", node_id);
+
+    node.payload
+        .iter()
+        .map(|&child_id| match child_id {
+            RecordId::Data(id) => {
+                assert!(!obj_space.is_removed(&id), "removed #{} is in the tree", id);
+                assert_eq!(obj_space.get_data(id).parent_id, node_id);
+                0
+            }
+            _ => {
+                assert_eq!(obj_space.get_node(child_id).parent_id, node_id);
+                check_subtree(obj_space, child_id)
+            }
+        })
+        .sum::<usize>()
+        + 1
+}
+
+#[test]
+fn test_tree_remove() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let mut ids = vec![];
+
+    for x in 0..10 {
+        for y in 0..10 {
+            let id = tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+
+            ids.push(id);
+        }
+    }
+
+    let full_node_num = check_tree_structure(&tree);
+
+    let (removed_ids, kept_ids) = ids.split_at(80);
+    for (object, &id) in removed_ids.iter().enumerate() {
+        assert_eq!(tree.remove(id), Some(object));
+        check_tree_structure(&tree);
+    }
+
+    assert_eq!(tree.remove(removed_ids[0]), None);
+    assert!(check_tree_structure(&tree) < full_node_num);
+
+    let everything = mbr! {
+        X = [0; 100],
+        Y = [0; 100]
+    };
+
+    let found: HashSet<NodeId> = tree.search(&everything).into_iter().collect();
+    let expected: HashSet<NodeId> = kept_ids.iter().cloned().collect();
+
+    assert_eq!(found, expected);
+
+    let area = mbr! {
+        X = [ 0; 85],
+        Y = [30; 65]
+    };
+
+    let found: HashSet<usize> = tree
+        .search(&area)
+        .into_iter()
+        .map(|id| tree.access_object(id, |&object, _| object))
+        .collect();
+
+    assert_eq!(found, [83, 84, 85, 86].iter().cloned().collect());
+
+    for &id in kept_ids {
+        assert!(tree.remove(id).is_some());
+        check_tree_structure(&tree);
+    }
+
+    assert!(tree.search(&everything).is_empty());
+    assert_eq!(check_tree_structure(&tree), 1);
+
+    let id = tree.insert(100, mbr! { X = [1; 2], Y = [1; 2] });
+    assert_eq!(tree.search(&everything), vec![id]);
+}
+
+#[test]
+fn test_tree_retain_condensed() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let mut ids = vec![];
+
+    for x in 0..10 {
+        for y in 0..10 {
+            let id = tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+
+            ids.push(id);
+        }
+    }
+
+    let full_node_num = check_tree_structure(&tree);
+
+    // Keep only the objects on the diagonal of the lower left quadrant.
+    let quadrant = mbr! {
+        X = [0; 45],
+        Y = [0; 45]
+    };
+
+    tree.retain_condensed(&quadrant, |obj_space, id| {
+        let object = *obj_space.get_data_payload(id);
+        object / 10 == object % 10
+    });
+
+    assert!(check_tree_structure(&tree) < full_node_num);
+
+    let found: HashSet<usize> = tree
+        .search_objects(&quadrant, |&object, _| object)
+        .into_iter()
+        .collect();
+
+    assert_eq!(found, [0, 11, 22, 33, 44].iter().cloned().collect());
+
+    let everything = mbr! {
+        X = [0; 100],
+        Y = [0; 100]
+    };
+
+    assert_eq!(tree.search(&everything).len(), 100 - 20);
+
+    let expected: HashSet<NodeId> = ids
+        .iter()
+        .cloned()
+        .filter(|&id| !tree.lock_obj_space().is_removed(&id))
+        .collect();
+
+    assert_eq!(expected.len(), 100 - 20);
+    assert_eq!(
+        tree.search(&everything).into_iter().collect::<HashSet<_>>(),
+        expected
+    );
+
+    tree.restore_many(ids.iter().cloned());
+    check_tree_structure(&tree);
+
+    assert_eq!(tree.search(&everything).len(), 100);
+}
+
+#[test]
+fn test_tree_compact_fuzz() {
+    init_logger();
+
+    for mut seed in 0..30u64 {
+        let mut next = |max: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+
+        let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+        let mut live = HashMap::new();
+
+        for object in 0..300usize {
+            let x = next(1000) as i32;
+            let y = next(1000) as i32;
+            let id = tree.insert(object, mbr! { X = [x; x + 10], Y = [y; y + 10] });
+            live.insert(id, object);
+
+            let live_ids = live.keys().cloned().collect::<Vec<_>>();
+            let some_id = live_ids[next(live_ids.len() as u64) as usize];
+            match next(10) {
+                0..=2 => {
+                    assert_eq!(tree.remove(some_id), live.remove(&some_id));
+                }
+                3 => {
+                    tree.mark_as_removed(std::iter::once(some_id));
+                    live.remove(&some_id);
+                }
+                4 => {
+                    let id_map = tree.compact();
+                    live = live
+                        .into_iter()
+                        .map(|(id, object)| (id_map[&id], object))
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        let found = tree
+            .search(&mbr! { X = [0; 1010], Y = [0; 1010] })
+            .into_iter()
+            .filter(|id| !tree.lock_obj_space().is_removed(id))
+            .collect::<HashSet<_>>();
+        assert_eq!(found, live.keys().cloned().collect::<HashSet<_>>());
+        assert_eq!(tree.len(), live.len());
+
+        for (&id, &object) in live.iter() {
+            tree.access_object(id, |&found_object, _| assert_eq!(found_object, object));
+        }
+
+        tree.compact();
+        check_tree_structure(&tree);
+    }
+}
+
+#[test]
+fn test_tree_compact() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let mut ids = vec![];
+
+    for x in 0..10 {
+        for y in 0..10 {
+            let id = tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+
+            ids.push(id);
+        }
+    }
+
+    let removed_ids = ids
+        .iter()
+        .cloned()
+        .filter(|id| id % 3 == 0)
+        .collect::<Vec<_>>();
+    tree.mark_as_removed(removed_ids.iter().cloned());
+
+    let areas = [
+        mbr! { X = [ 0; 100], Y = [ 0; 100] },
+        mbr! { X = [ 0;  45], Y = [ 0;  45] },
+        mbr! { X = [33;  67], Y = [12;  88] },
+        mbr! { X = [95; 100], Y = [ 0; 100] },
+    ];
+
+    let found_objects = |area: &MBR<i32>| {
+        tree.search_objects(area, |&object, _| object)
+            .into_iter()
+            .filter(|&object| object % 3 != 0)
+            .collect::<HashSet<_>>()
+    };
+
+    let found_before = areas.iter().map(found_objects).collect::<Vec<_>>();
+    let ids_before = tree.search(&areas[0]);
+
+    let id_map = tree.compact();
+
+    assert_eq!(id_map.len(), 100 - removed_ids.len());
+    assert!(removed_ids.iter().all(|id| !id_map.contains_key(id)));
+
+    let new_ids = id_map.values().cloned().collect::<HashSet<_>>();
+    assert_eq!(new_ids, (0..id_map.len()).collect());
+
+    for (&old_id, &new_id) in id_map.iter() {
+        assert_eq!(tree.access_object(new_id, |&object, _| object), old_id);
+    }
+
+    let found_after = areas.iter().map(found_objects).collect::<Vec<_>>();
+    assert_eq!(found_after, found_before);
+
+    let expected_ids = ids_before
+        .into_iter()
+        .filter_map(|id| id_map.get(&id).cloned())
+        .collect::<HashSet<_>>();
+
+    assert_eq!(
+        tree.search(&areas[0]).into_iter().collect::<HashSet<_>>(),
+        expected_ids
+    );
+    assert_eq!(tree.lock_obj_space().data_num(), id_map.len());
+
+    check_tree_structure(&tree);
+
+    tree.restore_removed();
+    assert_eq!(tree.search(&areas[0]).len(), id_map.len());
+}
+
+#[test]
+fn test_tree_reuse_freed_nodes() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+
+    let mbr_of = |object: usize| {
+        let x = (object / 20) as i32;
+        let y = (object % 20) as i32;
+
+        mbr! {
+            X = [x * 10; x * 10 + 5],
+            Y = [y * 10; y * 10 + 5]
+        }
+    };
+
+    let mut ids = (0..400)
+        .map(|object| tree.insert(object, mbr_of(object)))
+        .collect::<Vec<_>>();
+
+    let mut nodes_len = vec![];
+    for _ in 0..10 {
+        // Remove a third of the objects and insert them back
+        for object in (0..400).step_by(3) {
+            assert_eq!(tree.remove(ids[object]), Some(object));
+        }
+
+        for object in (0..400).step_by(3) {
+            ids[object] = tree.insert(object, mbr_of(object));
+        }
+
+        check_tree_structure(&tree);
+        nodes_len.push(tree.lock_obj_space().nodes_len());
+    }
+
+    let found = tree
+        .search_objects(&mbr! { X = [0; 200], Y = [0; 200] }, |&object, _| object)
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(found, (0..400).collect::<HashSet<_>>());
+
+    // The freed slots are reused, so the nodes storage doesn't grow from cycle to cycle
+    assert_eq!(nodes_len.iter().max(), nodes_len.first());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_tree_serde() {
+    init_logger();
+
+    let mbr_of = |object: usize| {
+        let x = (object / 20) as i32;
+        let y = (object % 20) as i32;
+
+        mbr! {
+            X = [x * 10; x * 10 + 5],
+            Y = [y * 10; y * 10 + 5]
+        }
+    };
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for object in 0..200 {
+        tree.insert(object, mbr_of(object));
+    }
+
+    // Freed nodes and removed objects are a part of the snapshot too
+    for id in (0..200).step_by(3) {
+        tree.remove(id);
+    }
+    tree.mark_as_removed((1..200).step_by(3));
+
+    let json = serde_json::to_string(&tree).unwrap();
+    let restored_tree: LRTree<i32, usize> = serde_json::from_str(&json).unwrap();
+
+    for object in 200..250 {
+        assert_eq!(
+            restored_tree.insert(object, mbr_of(object)),
+            tree.insert(object, mbr_of(object))
+        );
+    }
+
+    assert_eq!(restored_tree.restore_removed(), 67);
+    assert_eq!(tree.restore_removed(), 67);
+    assert!(restored_tree.lock_obj_space().is_removed(&0));
+    check_tree_structure(&restored_tree);
+
+    let area = mbr! { X = [33; 67], Y = [12; 88] };
+    assert_eq!(
+        restored_tree
+            .search(&area)
+            .into_iter()
+            .collect::<HashSet<_>>(),
+        tree.search(&area).into_iter().collect::<HashSet<_>>()
+    );
+
+    let mut corrupted: serde_json::Value = serde_json::from_str(&json).unwrap();
+    corrupted["root_id"] = serde_json::json!({ "Internal": 1000000 });
+    assert!(serde_json::from_value::<LRTree<i32, usize>>(corrupted).is_err());
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn test_tree_save_load() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for x in 0..20 {
+        for y in 0..20 {
+            tree.insert(
+                (x * 20 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!("lr-tree-save-load-{}.bin", std::process::id()));
+
+    tree.save(&path).unwrap();
+    let loaded_tree = LRTree::<i32, usize>::load(&path).unwrap();
+
+    // The dimension is stored first
+    let mut data = std::fs::read(&path).unwrap();
+    data[..8].copy_from_slice(&0u64.to_le_bytes());
+    std::fs::write(&path, &data).unwrap();
+    let error = LRTree::<i32, usize>::load(&path).map(|_| ()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    std::fs::write(&path, &data[..data.len() / 2]).unwrap();
+    let error = LRTree::<i32, usize>::load(&path).map(|_| ()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    std::fs::remove_file(&path).unwrap();
+
+    let error = LRTree::<i32, usize>::load(&path).map(|_| ()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+    check_tree_structure(&loaded_tree);
+
+    for area in [
+        mbr! { X = [0; 200], Y = [0; 200] },
+        mbr! { X = [33; 67], Y = [12; 88] },
+        mbr! { X = [-10; -1], Y = [0; 200] },
+    ]
+    .iter()
+    {
+        assert_eq!(
+            loaded_tree
+                .search_objects(area, |&object, _| object)
+                .into_iter()
+                .collect::<HashSet<_>>(),
+            tree.search_objects(area, |&object, _| object)
+                .into_iter()
+                .collect::<HashSet<_>>()
+        );
+    }
+}
+
+#[cfg(feature = "geojson")]
+#[test]
+fn test_tree_export_geojson() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    tree.remove(42);
+
+    let geojson: serde_json::Value = serde_json::from_str(&tree.export_geojson()).unwrap();
+    assert_eq!(geojson["type"], "FeatureCollection");
+
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), 99);
+
+    let ids = features
+        .iter()
+        .map(|feature| feature["properties"]["id"].as_u64().unwrap() as usize)
+        .collect::<HashSet<_>>();
+    assert_eq!(ids.len(), 99);
+    assert!(!ids.contains(&42));
+
+    let feature = features
+        .iter()
+        .find(|feature| feature["properties"]["id"] == 23)
+        .unwrap();
+    assert_eq!(feature["geometry"]["type"], "Polygon");
+    assert_eq!(
+        feature["geometry"]["coordinates"],
+        serde_json::json!([[
+            [20.0, 30.0],
+            [25.0, 30.0],
+            [25.0, 35.0],
+            [20.0, 35.0],
+            [20.0, 30.0]
+        ]])
+    );
+
+    let tree_3d = LRTree::with_obj_space(ObjSpace::new(3, 2, 4));
+    tree_3d.insert(0, mbr! { X = [0; 1], Y = [0; 1], Z = [0; 1] });
+
+    let geojson: serde_json::Value = serde_json::from_str(&tree_3d.export_geojson()).unwrap();
+    assert_eq!(geojson["features"], serde_json::json!([]));
+}
+
+#[cfg(feature = "geojson")]
+#[test]
+fn test_tree_from_geojson() {
+    init_logger();
+
+    let json = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [1.5, 2.0] },
+                "properties": { "name": "point" }
+            },
+            {
+                "type": "Feature",
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[[10.0, 0.0], [20.0, 5.0], [15.0, 12.0], [10.0, 0.0]]]
+                },
+                "properties": { "name": "triangle" }
+            },
+            {
+                "type": "Feature",
+                "geometry": null,
+                "properties": { "name": "nowhere" }
+            }
+        ]
+    }"#;
+
+    let tree = LRTree::from_geojson(json, 2, 4).unwrap();
+    assert_eq!(tree.len(), 2);
+
+    let names = |area: &MBR<f64>| {
+        tree.search_objects(area, |object, _| {
+            object["name"].as_str().unwrap().to_string()
+        })
+        .into_iter()
+        .collect::<HashSet<_>>()
+    };
+
+    assert_eq!(
+        names(&mbr! { X = [1.5; 20.0], Y = [0.0; 12.0] }),
+        ["point", "triangle"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    );
+    assert_eq!(
+        names(&mbr! { X = [18.0; 19.0], Y = [11.0; 12.0] }),
+        std::iter::once("triangle".to_string()).collect()
+    );
+    assert!(names(&mbr! { X = [2.0; 9.0], Y = [0.0; 12.0] }).is_empty());
+
+    assert!(matches!(
+        LRTree::from_geojson("{", 2, 4).map(|_| ()),
+        Err(GeoJsonImportError::Json(_))
+    ));
+    assert!(matches!(
+        LRTree::from_geojson(r#"{ "type": "Feature" }"#, 2, 4).map(|_| ()),
+        Err(GeoJsonImportError::NotFeatureCollection)
+    ));
+    assert!(matches!(
+        LRTree::from_geojson(
+            r#"{ "type": "FeatureCollection", "features": [
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": ["x", 1] } }
+            ] }"#,
+            2,
+            4
+        )
+        .map(|_| ()),
+        Err(GeoJsonImportError::Geometry { feature: 0 })
+    ));
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_tree_from_csv() {
+    init_logger();
+
+    let csv = (0..100)
+        .map(|i| {
+            let (x, y) = (i / 10 * 10, i % 10 * 10);
+
+            format!("{}, {}, {}, {}, {}\n", i, x, x + 5, y, y + 5)
+        })
+        .collect::<String>();
+
+    let tree = LRTree::<i32, usize>::from_csv(csv.as_bytes(), 2, 2, 4).unwrap();
+    check_tree_structure(&tree);
+
+    let found = tree
+        .search_objects(&mbr! { X = [33; 67], Y = [12; 38] }, |&object, _| object)
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let expected = (3..=6)
+        .flat_map(|x| (1..=3).map(move |y| x * 10 + y))
+        .collect::<HashSet<_>>();
+    assert_eq!(found, expected);
+
+    let empty_tree = LRTree::<i32, usize>::from_csv("".as_bytes(), 2, 2, 4).unwrap();
+    assert!(empty_tree.lock_obj_space().is_empty());
+
+    let error = |csv: &str| {
+        LRTree::<i32, usize>::from_csv(csv.as_bytes(), 2, 2, 4)
+            .map(|_| ())
+            .unwrap_err()
+    };
+
+    assert!(matches!(
+        error("0,0,1,0,1\n1,0,1,0\n"),
+        CsvImportError::ColumnsNum {
+            row: 2,
+            expected: 5,
+            actual: 4
+        }
+    ));
+    assert!(matches!(
+        error("0,0,1,0,1\n1,0,one,0,1\n"),
+        CsvImportError::Value { row: 2, column: 2, ref value } if value == "one"
+    ));
+    assert!(matches!(
+        error("-1,0,1,0,1\n"),
+        CsvImportError::Value {
+            row: 1,
+            column: 0,
+            ..
+        }
+    ));
+    assert!(matches!(
+        error("0,0,1,5,1\n"),
+        CsvImportError::Bounds { row: 1, axis: 1 }
+    ));
+    assert_eq!(
+        error("0,0,1,5,1\n").to_string(),
+        "row 1, axis 1: a min bound must be less than a max bound"
+    );
+}
+
+/// Collects the depths of the objects.
+#[derive(Default)]
+struct DataDepthVisitor {
+    depths: HashSet<usize>,
+}
+
+impl DepthVisitor<i32, usize> for DataDepthVisitor {
+    fn enter_node(&mut self, _: RecordId, _: &InternalNode<i32>, _: usize) {}
+
+    fn leave_node(&mut self, _: RecordId, _: &InternalNode<i32>, _: usize) {}
+
+    fn visit_data(&mut self, _: RecordId, _: &DataNode<i32, usize>, depth: usize) {
+        self.depths.insert(depth);
+    }
+}
+
+#[test]
+fn test_tree_concurrent_readers() {
+    init_logger();
+
+    fn count_data(obj_space: &ObjSpace<i32, usize>, node_id: RecordId) -> usize {
+        match node_id {
+            RecordId::Data(_) => 1,
+            _ => obj_space
+                .get_node(node_id)
+                .payload
+                .iter()
+                .map(|&child_id| count_data(obj_space, child_id))
+                .sum(),
+        }
+    }
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let is_writing = std::sync::atomic::AtomicBool::new(true);
+    let whole_area = mbr! { X = [0; 400], Y = [0; 400] };
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                while is_writing.load(std::sync::atomic::Ordering::Acquire) {
+                    let obj_space = tree.lock_obj_space();
+
+                    // Every node is linked with its parent and has a tight MBR
+                    check_subtree(&obj_space, obj_space.root_id);
+                    assert_eq!(
+                        count_data(&obj_space, obj_space.root_id),
+                        obj_space.data_num()
+                    );
+                    drop(obj_space);
+
+                    let found = tree.search(&whole_area);
+                    assert_eq!(found.len(), found.iter().collect::<HashSet<_>>().len());
+
+                    // All the objects are at the same depth in a consistent tree
+                    let mut visitor = DataDepthVisitor::default();
+                    tree.visit_with_depth(&mut visitor);
+                    assert!(visitor.depths.len() <= 1, "{:?}", visitor.depths);
+                }
+            });
+        }
+
+        let mut ids = vec![];
+        for x in 0..40 {
+            for y in 0..40 {
+                ids.push(tree.insert(
+                    (x * 40 + y) as usize,
+                    mbr! {
+                        X = [x * 10; x * 10 + 5],
+                        Y = [y * 10; y * 10 + 5]
+                    },
+                ));
+            }
+
+            if x % 4 == 3 {
+                for &id in ids.iter().step_by(7) {
+                    tree.remove(id);
+                }
+            }
+        }
+
+        is_writing.store(false, std::sync::atomic::Ordering::Release);
+    });
+
+    check_tree_structure(&tree);
+}
+
+#[test]
+fn test_tree_visit_with_waiting_writer() {
+    struct SlowVisitor<'a> {
+        is_started: &'a std::sync::atomic::AtomicBool,
+        data_num: usize,
+    }
+
+    impl Visitor<i32, i32> for SlowVisitor<'_> {
+        fn enter_node(&mut self, _: RecordId, _: &InternalNode<i32>) {
+            self.is_started
+                .store(true, std::sync::atomic::Ordering::Release);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        fn leave_node(&mut self, _: RecordId, _: &InternalNode<i32>) {}
+
+        fn visit_data(&mut self, _: RecordId, _: &DataNode<i32, i32>) {
+            self.data_num += 1;
+        }
+    }
+
+    init_logger();
+
+    let tree = make_12_objects_tree();
+    let is_started = std::sync::atomic::AtomicBool::new(false);
+
+    // The writer waits for the whole visit, the visit doesn't wait for the writer
+    let data_num = std::thread::scope(|scope| {
+        let visit = scope.spawn(|| {
+            let mut visitor = SlowVisitor {
+                is_started: &is_started,
+                data_num: 0,
+            };
+            tree.visit(&mut visitor);
+
+            visitor.data_num
+        });
+
+        while !is_started.load(std::sync::atomic::Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+
+        tree.insert(13, mbr! { X = [0; 10], Y = [30; 40] });
+
+        visit.join().unwrap()
+    });
+
+    assert_eq!(data_num, 12);
+    assert_eq!(tree.len(), 13);
+}
+
+#[test]
+fn test_tree_try_search() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for i in 0..10 {
+        tree.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] });
+    }
+
+    let area = mbr! { X = [12; 38], Y = [0; 5] };
+    let expected = tree.search(&area).into_iter().collect::<HashSet<_>>();
+    assert_eq!(expected.len(), 3);
+
+    let found = tree.try_search(&area).unwrap();
+    assert_eq!(found.into_iter().collect::<HashSet<_>>(), expected);
+
+    // Readers don't block each other
+    let obj_space = tree.lock_obj_space();
+    assert!(tree.try_search(&area).is_some());
+    drop(obj_space);
+
+    let obj_space = tree.lock_obj_space_write();
+    std::thread::scope(|scope| {
+        let found = scope.spawn(|| tree.try_search(&area)).join().unwrap();
+        assert!(found.is_none());
+    });
+    drop(obj_space);
+
+    assert!(tree.try_search(&area).is_some());
+}
+
+#[cfg(feature = "parking_lot")]
+#[test]
+fn test_tree_access_object_panic_does_not_poison() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let id = tree.insert(0, mbr! { X = [0; 5], Y = [0; 5] });
+
+    // A panic under the write lock poisons the std lock
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        tree.access_object_mut(id, |object, _| {
+            *object = 1;
+            panic!("bad handler")
+        })
+    }));
+    assert!(result.is_err());
+
+    tree.access_object(id, |&object, _| assert_eq!(object, 1));
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        tree.transaction(|_| panic!("bad transaction"))
+    }));
+    assert!(result.is_err());
+
+    tree.access_object_mut(id, |object, _| *object = 0);
+    tree.access_object(id, |&object, _| assert_eq!(object, 0));
+
+    let new_id = tree.insert(1, mbr! { X = [10; 15], Y = [0; 5] });
+    assert_eq!(tree.search(&mbr! { X = [0; 15], Y = [0; 5] }).len(), 2);
+    assert_eq!(tree.remove(new_id), Some(1));
+    check_tree_structure(&tree);
+}
+
+#[test]
+fn test_tree_restore_after_remove() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let ids = (0..50)
+        .map(|i| tree.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+        .collect::<Vec<_>>();
+
+    assert_eq!(tree.remove(ids[3]), Some(3));
+    tree.mark_as_removed([ids[5], ids[7]].iter().cloned());
+
+    let mut restored_ids = tree.restore_removed_ids();
+    restored_ids.sort_unstable();
+    assert_eq!(restored_ids, vec![ids[5], ids[7]]);
+
+    let all = mbr! { X = [0; 500], Y = [0; 5] };
+    assert_eq!(tree.len(), 49);
+    assert_eq!(tree.search(&all).len(), 49);
+    assert!(tree.lock_obj_space().is_removed(&ids[3]));
+    check_tree_structure(&tree);
+
+    tree.restore_many(std::iter::once(ids[3]));
+    assert_eq!(tree.len(), 49);
+    assert!(!tree.search(&all).contains(&ids[3]));
+}
+
+#[test]
+fn test_tree_restore_removed_orphans() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let ids = (0..40)
+        .map(|i| tree.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+        .collect::<Vec<_>>();
+
+    let marked_ids = ids.iter().cloned().step_by(2).collect::<Vec<_>>();
+    tree.mark_as_removed(marked_ids.iter().cloned());
+
+    // Removing the live neighbours dissolves the leaves,
+    // so the marked objects are dropped from the tree structure.
+    for &id in ids.iter().skip(1).step_by(2).take(15) {
+        tree.remove(id);
+    }
+
+    let obj_space = tree.lock_obj_space();
+    assert!(marked_ids.iter().any(|&id| !obj_space.is_data_bound(id)));
+    drop(obj_space);
+
+    assert_eq!(tree.restore_removed(), marked_ids.len());
+    check_tree_structure(&tree);
+
+    let all = mbr! { X = [0; 400], Y = [0; 5] };
+    assert_eq!(tree.len(), 25);
+    assert_eq!(tree.search(&all).len(), 25);
+    for id in marked_ids {
+        assert!(tree.search(&all).contains(&id));
+    }
+}
+
+#[test]
+fn test_tree_transaction() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let first_id = tree.insert(100, mbr! { X = [0; 5], Y = [0; 5] });
+
+    let (ids, removed) = tree.transaction(|obj_space| {
+        let ids = (0..10)
+            .map(|i| obj_space.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+            .collect::<Vec<_>>();
+
+        let removed = obj_space.remove(first_id);
+        assert!(obj_space.remove(first_id).is_none());
+
+        (ids, removed)
+    });
+
+    assert_eq!(removed, Some(100));
+    assert!(tree.lock_obj_space().is_removed(&first_id));
+    assert_eq!(tree.lock_obj_space().data_num(), 10);
+    check_tree_structure(&tree);
+
+    for (i, &id) in ids.iter().enumerate() {
+        tree.access_object(id, |&object, _| assert_eq!(object, i));
+    }
+
+    let found = tree
+        .search(&mbr! { X = [0; 5], Y = [0; 5] })
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(found, std::iter::once(ids[0]).collect());
+}
+
+#[test]
+fn test_tree_shrink_to_fit() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+
+    for x in 0..40 {
+        for y in 0..40 {
+            tree.insert(
+                (x * 40 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let whole_area = mbr! { X = [0; 400], Y = [0; 400] };
+    tree.retain_condensed(&whole_area, |obj_space, id| {
+        obj_space.get_data_payload(id) % 10 == 0
+    });
+
+    let areas = [
+        whole_area,
+        mbr! { X = [ 0;  45], Y = [ 0;  45] },
+        mbr! { X = [33; 167], Y = [12; 388] },
+        mbr! { X = [95; 400], Y = [ 0; 100] },
+    ];
+
+    let found = |tree: &LRTree<i32, usize>| {
+        areas
+            .iter()
+            .map(|area| {
+                tree.search_objects(area, |&object, _| object)
+                    .into_iter()
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let leaves = |tree: &LRTree<i32, usize>| {
+        let mut leaves = vec![];
+        tree.for_each_leaf(|leaf_id, leaf| leaves.push((leaf_id, leaf.mbr.clone())));
+        leaves
+    };
+
+    let found_before = found(&tree);
+    let ids_before = tree.search(&areas[0]).into_iter().collect::<HashSet<_>>();
+    let leaves_before = leaves(&tree);
+    let capacity_before = tree.lock_obj_space().nodes_capacity();
+
+    tree.shrink_to_fit();
+
+    let nodes_num = check_tree_structure(&tree);
+    let obj_space = tree.lock_obj_space();
+    let capacity_after = obj_space.nodes_capacity();
+
+    assert!(capacity_after < capacity_before);
+    assert_eq!(capacity_after, obj_space.nodes_len());
+    assert_eq!(
+        obj_space.nodes_len(),
+        nodes_num + obj_space.free_nodes_len()
+    );
+    drop(obj_space);
+
+    // The node IDs are kept
+    assert_eq!(leaves(&tree), leaves_before);
+
+    assert_eq!(found(&tree), found_before);
+    assert_eq!(
+        tree.search(&areas[0]).into_iter().collect::<HashSet<_>>(),
+        ids_before
+    );
+
+    for id in ids_before {
+        assert_eq!(tree.access_object(id, |&object, _| object), id);
+    }
+
+    tree.insert(1, mbr! { X = [1; 2], Y = [1; 2] });
+    check_tree_structure(&tree);
+}
+
+#[test]
+fn test_tree_shrink_to_fit_truncates_free_nodes() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let ids = (0..100)
+        .map(|i| tree.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+        .collect::<Vec<_>>();
+
+    for &id in ids.iter().skip(3) {
+        tree.remove(id);
+    }
+
+    let root_id = tree.lock_obj_space().root_id;
+    let nodes_len_before = tree.lock_obj_space().nodes_len();
+
+    tree.shrink_to_fit();
+
+    let nodes_num = check_tree_structure(&tree);
+    let obj_space = tree.lock_obj_space();
+
+    assert!(obj_space.nodes_len() < nodes_len_before);
+    assert_eq!(
+        obj_space.nodes_len(),
+        nodes_num + obj_space.free_nodes_len()
+    );
+    assert_eq!(obj_space.nodes_capacity(), obj_space.nodes_len());
+    assert_eq!(obj_space.root_id, root_id);
+    drop(obj_space);
+
+    let mut found = tree.search(&mbr! { X = [0; 1000], Y = [0; 5] });
+    found.sort_unstable();
+    assert_eq!(found, ids[..3].to_vec());
+
+    // The free nodes left are reused
+    for i in 3..100 {
+        tree.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] });
+    }
+
+    check_tree_structure(&tree);
+    assert_eq!(tree.len(), 100);
+}
+
+#[test]
+fn test_tree_shrink_to_fit_keeps_removed_objects() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let ids = (0..50)
+        .map(|i| tree.insert(i as usize, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+        .collect::<Vec<_>>();
+
+    assert_eq!(tree.remove(ids[3]), Some(3));
+    tree.mark_as_removed([ids[5], ids[7]].iter().cloned());
+
+    tree.shrink_to_fit();
+
+    let mut restored_ids = tree.restore_removed_ids();
+    restored_ids.sort_unstable();
+    assert_eq!(restored_ids, vec![ids[5], ids[7]]);
+
+    let all = mbr! { X = [0; 500], Y = [0; 5] };
+    assert_eq!(tree.len(), 49);
+    assert_eq!(tree.search(&all).len(), 49);
+    assert!(tree.lock_obj_space().is_removed(&ids[3]));
+
+    // The data IDs are not reused
+    let new_id = tree.insert(50, mbr! { X = [500; 505], Y = [0; 5] });
+    assert!(!ids.contains(&new_id));
+
+    for (i, &id) in ids.iter().enumerate().filter(|&(i, _)| i != 3) {
+        assert_eq!(tree.access_object(id, |&object, _| object), i);
+    }
+
+    check_tree_structure(&tree);
+}
+
+#[test]
+fn test_tree_update() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let mut ids = vec![];
+
+    for x in 0..10 {
+        for y in 0..10 {
+            let id = tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+
+            ids.push(id);
+        }
+    }
+
+    let everything = mbr! {
+        X = [0; 200],
+        Y = [0; 200]
+    };
+
+    let objects_in = |area: &MBR<i32>| {
+        let mut objects = tree
+            .search(area)
+            .into_iter()
+            .map(|id| (id, tree.access_object(id, |&object, _| object)))
+            .collect::<Vec<_>>();
+
+        objects.sort_unstable();
+        objects
+    };
+
+    // Moving far away
+    let moved_id = ids[11];
+    tree.update(moved_id, mbr! { X = [150; 160], Y = [150; 160] });
+
+    assert!(objects_in(&mbr! { X = [10; 15], Y = [10; 15] }).is_empty());
+    assert_eq!(
+        objects_in(&mbr! { X = [140; 200], Y = [140; 200] }),
+        vec![(moved_id, 11)]
+    );
+    assert_eq!(tree.search(&everything).len(), 100);
+
+    // Moving inside the leaf MBR
+    let shrunk_id = ids[55];
+    tree.update(shrunk_id, mbr! { X = [51; 52], Y = [51; 52] });
+
+    assert_eq!(
+        objects_in(&mbr! { X = [51; 52], Y = [51; 52] }),
+        vec![(shrunk_id, 55)]
+    );
+    assert!(objects_in(&mbr! { X = [53; 55], Y = [53; 55] }).is_empty());
+    assert!(tree
+        .lock_obj_space()
+        .get_root_mbr()
+        .contains(&mbr! { X = [150; 160], Y = [150; 160] }));
+
+    tree.access_object(shrunk_id, |_, mbr| {
+        assert_eq!(*mbr, mbr! { X = [51; 52], Y = [51; 52] });
+    });
+
+    // Moving the far one back
+    tree.update(moved_id, mbr! { X = [10; 15], Y = [10; 15] });
+
+    assert_eq!(
+        objects_in(&mbr! { X = [10; 15], Y = [10; 15] }),
+        vec![(moved_id, 11)]
+    );
+    assert_eq!(
+        *tree.lock_obj_space().get_root_mbr(),
+        mbr! { X = [0; 95], Y = [0; 95] }
+    );
+    assert_eq!(tree.search(&everything).len(), 100);
+}
+
+#[test]
+#[should_panic]
+fn test_panic_tree_update() {
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let id = tree.insert(0, mbr! { X = [0; 1], Y = [0; 1] });
+
+    tree.update(id, mbr! { X = [0; 1] });
+}
+
+#[test]
+fn test_tree_retain() {
+    init_logger();
+
+    let mut remove_values = vec![];
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.insert(
+        1,
+        mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        },
+    );
+
+    let value = tree.insert(
+        2,
+        mbr! {
+            X = [11; 21],
+            Y = [ 0; 10]
+        },
+    );
+    remove_values.push(value);
+
+    tree.insert(
+        3,
+        mbr! {
+            X = [22; 32],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        4,
+        mbr! {
+            X = [ 0; 10],
+            Y = [11; 21]
+        },
+    );
+
+    let value = tree.insert(
+        5,
+        mbr! {
+            X = [11; 21],
+            Y = [11; 21]
+        },
+    );
+    remove_values.push(value);
+
+    tree.insert(
+        6,
+        mbr! {
+            X = [22; 32],
+            Y = [11; 21]
+        },
+    );
+
+    let removed_ids = tree.retain(
+        &mbr! {
+            X = [3; 25],
+            Y = [3; 15]
+        },
+        |obj_space, id| !remove_values.contains(&obj_space.get_data_payload(id)),
+    );
+
+    let mut removed_objects = removed_ids
+        .iter()
+        .map(|&id| tree.access_object(id, |&object, _| object))
+        .collect::<Vec<_>>();
+    removed_objects.sort_unstable();
+
+    assert_eq!(removed_objects, remove_values);
+    assert!(removed_ids
+        .iter()
+        .all(|id| tree.lock_obj_space().is_removed(id)));
+
+    let root_mbr = tree.lock_obj_space().get_root_mbr().clone();
+    let new_tree = LRTree::with_obj_space(tree.lock_obj_space().clone_shrinked());
+
+    new_tree.search_access(&root_mbr, |obj_space, id| {
+        assert!(!remove_values.contains(&obj_space.get_data_payload(id)))
+    });
+}
+
+#[test]
+fn test_tree_remove_matching() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    let mbrs = [
+        mbr! { X = [ 0; 10], Y = [ 0; 10] },
+        mbr! { X = [11; 21], Y = [ 0; 10] },
+        mbr! { X = [22; 32], Y = [ 0; 10] },
+        mbr! { X = [ 0; 10], Y = [11; 21] },
+        mbr! { X = [11; 21], Y = [11; 21] },
+        mbr! { X = [22; 32], Y = [11; 21] },
+    ];
+
+    let ids = mbrs
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(object, mbr)| tree.insert(object + 1, mbr))
+        .collect::<Vec<_>>();
+
+    let mut removed_ids = tree.remove_matching(|_, &object, _| object % 2 == 0);
+    removed_ids.sort_unstable();
+
+    assert_eq!(removed_ids, vec![ids[1], ids[3], ids[5]]);
+    assert_eq!(tree.len(), 3);
+
+    let mut kept_objects = vec![];
+    tree.for_each_object(|_, &object, _| kept_objects.push(object));
+    kept_objects.sort_unstable();
+
+    assert_eq!(kept_objects, vec![1, 3, 5]);
+
+    // Already removed objects are not matched again.
+    assert!(tree
+        .remove_matching(|_, &object, _| object % 2 == 0)
+        .is_empty());
+
+    let removed_ids = tree.remove_matching(|_, _, mbr| mbr.bounds(1).min > 10);
+    assert_eq!(removed_ids, vec![ids[4]]);
+}
+
+#[test]
+fn test_tree_tighten() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    let full_mbr = mbr! { X = [0; 95], Y = [0; 95] };
+    assert_eq!(*tree.lock_obj_space().get_root_mbr(), full_mbr);
+
+    // The last column and the last row
+    let removed_ids = tree.remove_matching(|_, &object, _| object / 10 == 9 || object % 10 == 9);
+    assert_eq!(removed_ids.len(), 19);
+
+    assert_eq!(*tree.lock_obj_space().get_root_mbr(), full_mbr);
+
+    tree.tighten();
+
+    assert_eq!(
+        *tree.lock_obj_space().get_root_mbr(),
+        mbr! { X = [0; 85], Y = [0; 85] }
+    );
+
+    let area = mbr! {
+        X = [40; 100],
+        Y = [80; 100]
+    };
+
+    let found: HashSet<usize> = tree
+        .search_objects(&area, |&object, _| object)
+        .into_iter()
+        .filter(|&object| object / 10 != 9 && object % 10 != 9)
+        .collect();
+
+    assert_eq!(found, [48, 58, 68, 78, 88].iter().cloned().collect());
+
+    tree.restore_removed();
+
+    assert_eq!(*tree.lock_obj_space().get_root_mbr(), full_mbr);
+    assert_eq!(tree.search(&area).len(), 6 * 2);
+}
+
+/// Tightened 2D tree where all the objects of the first leaf are marked as removed.
+///
+/// Returns the tree and the IDs of the removed objects.
+fn make_tree_with_removed_leaf() -> (LRTree<i32, usize>, Vec<NodeId>) {
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for i in 0..30 {
+        let (x, y) = (i % 6 * 10, i / 6 * 10);
+        tree.insert(i as usize, mbr! { X = [x; x + 5], Y = [y; y + 5] });
+    }
+
+    let mut leaves = vec![];
+    tree.for_each_leaf(|_, node| leaves.push(node.payload.clone()));
+    let removed_ids = leaves[0]
+        .iter()
+        .map(|child_id| child_id.as_node_id())
+        .collect::<Vec<_>>();
+
+    tree.mark_as_removed(removed_ids.iter().cloned());
+    tree.tighten();
+
+    (tree, removed_ids)
+}
+
+#[test]
+fn test_tree_tighten_removed_leaf() {
+    init_logger();
+
+    let (tree, removed_ids) = make_tree_with_removed_leaf();
+    check_tree_structure(&tree);
+
+    tree.for_each_leaf(|id, node| {
+        assert!(!node.payload.is_empty(), "{:?} is empty", id);
+        assert_eq!(node.mbr.dimension(), 2);
+    });
+
+    assert_eq!(tree.restore_removed(), removed_ids.len());
+    check_tree_structure(&tree);
+    assert_eq!(tree.search(&mbr! { X = [0; 55], Y = [0; 45] }).len(), 30);
+}
+
+#[test]
+fn test_tree_search_ray_after_tighten() {
+    init_logger();
+
+    let (tree, removed_ids) = make_tree_with_removed_leaf();
+
+    // Rays along every row and every column of the objects
+    let rays = (0..5)
+        .map(|row| ([-1, row * 10 + 2], [1, 0]))
+        .chain((0..6).map(|column| ([column * 10 + 2, -1], [0, 1])));
+
+    let mut found = HashSet::new();
+    for (origin, dir) in rays {
+        found.extend(tree.search_ray(&origin, &dir));
+    }
+
+    assert_eq!(found.len(), 30 - removed_ids.len());
+    assert!(found.iter().all(|id| !removed_ids.contains(id)));
+}
+
+#[test]
+fn test_tree_expected_node_accesses_after_tighten() {
+    init_logger();
+
+    let (tree, _) = make_tree_with_removed_leaf();
+
+    let accesses = tree.expected_node_accesses(1);
+    assert!(accesses.is_finite() && accesses >= 1.0);
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn test_tree_svg_visitor_after_tighten() {
+    init_logger();
+
+    let (tree, _) = make_tree_with_removed_leaf();
+
+    let mut svg_visitor = SvgVisitor::new(110.0, 90.0);
+    tree.visit(&mut svg_visitor);
+    let svg = svg_visitor.into_svg();
+
+    let nodes_num = check_tree_structure(&tree) + tree.len();
+    assert_eq!(svg.matches("<rect ").count(), nodes_num);
+}
+
+#[test]
+fn test_tree_same_delta() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    let mut obj_space = tree.obj_space.write();
+
+    let first_node_id = obj_space.make_node(RecordIdKind::Leaf);
+    let node = obj_space.get_node_mut(first_node_id);
+    node.mbr = mbr! {
+        X = [0; 5],
+        Y = [0; 5]
+    };
+
+    let second_node_id = obj_space.make_node(RecordIdKind::Leaf);
+    let node = obj_space.get_node_mut(second_node_id);
+    node.mbr = mbr! {
+        X = [12; 13],
+        Y = [-1; 4]
+    };
+
+    let root_id = RecordId::from_node_id(obj_space.root_id.as_node_id(), RecordIdKind::Internal);
+    obj_space.root_id = root_id;
+    bind!([obj_space] root_id => first_node_id);
+    bind!([obj_space] root_id => second_node_id);
+
+    let node_0_data_0 = obj_space.make_data_node(
+        0,
+        mbr! {
+            X = [0; 3],
+            Y = [0; 3]
+        },
+    );
+    obj_space.get_data_mut(node_0_data_0).parent_id = first_node_id;
+
+    let node_0_data_1 = obj_space.make_data_node(
+        1,
+        mbr! {
+            X = [4; 5],
+            Y = [4; 5]
+        },
+    );
+    obj_space.get_data_mut(node_0_data_1).parent_id = first_node_id;
 
     let node_1_data_0 = obj_space.make_data_node(
         0,
         mbr! {
-            X = [12; 13],
-            Y = [-1; 0]
+            X = [12; 13],
+            Y = [-1; 0]
+        },
+    );
+    obj_space.get_data_mut(node_1_data_0).parent_id = second_node_id;
+
+    let node_1_data_1 = obj_space.make_data_node(
+        1,
+        mbr! {
+            X = [12; 13],
+            Y = [3; 4]
+        },
+    );
+    obj_space.get_data_mut(node_1_data_1).parent_id = second_node_id;
+
+    let first = obj_space.get_node_mut(first_node_id);
+    first.payload.push(RecordId::Data(node_0_data_0));
+    first.payload.push(RecordId::Data(node_0_data_1));
+
+    let second = obj_space.get_node_mut(second_node_id);
+    second.payload.push(RecordId::Data(node_1_data_0));
+    second.payload.push(RecordId::Data(node_1_data_1));
+    std::mem::drop(obj_space);
+
+    let test_record_id = tree.insert(
+        2,
+        mbr! {
+            X = [8; 10],
+            Y = [3; 5]
+        },
+    );
+
+    let obj_space = tree.obj_space.read();
+    let test_leaf_id = obj_space.get_data(test_record_id).parent_id;
+    assert_eq!(test_leaf_id, second_node_id);
+}
+
+#[test]
+fn test_tree_choose_subtree() {
+    init_logger();
+
+    fn make_tree(
+        choose_subtree: impl ChooseSubtree<i32, usize> + 'static,
+    ) -> (LRTree<i32, usize>, Vec<RecordId>) {
+        let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+        tree.set_choose_subtree(choose_subtree);
+
+        let mut obj_space = tree.obj_space.write();
+
+        let root_id =
+            RecordId::from_node_id(obj_space.root_id.as_node_id(), RecordIdKind::Internal);
+        obj_space.root_id = root_id;
+
+        let leaves = [
+            [
+                mbr! { X = [0; 3], Y = [0; 3] },
+                mbr! { X = [7; 10], Y = [7; 10] },
+            ],
+            [
+                mbr! { X = [12; 15], Y = [0; 4] },
+                mbr! { X = [37; 40], Y = [0; 4] },
+            ],
+        ];
+
+        let leaf_ids = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, data_mbrs)| {
+                let leaf_id = obj_space.make_node(RecordIdKind::Leaf);
+                bind!([obj_space] root_id => leaf_id);
+
+                for (j, data_mbr) in data_mbrs.iter().enumerate() {
+                    let data_id = obj_space.make_data_node(i * 2 + j, data_mbr.clone());
+                    bind!([obj_space] leaf_id => RecordId::Data(data_id));
+                }
+
+                leaf_id
+            })
+            .collect::<Vec<_>>();
+
+        obj_space.set_mbr(root_id, mbr! { X = [0; 40], Y = [0; 10] });
+        std::mem::drop(obj_space);
+
+        (tree, leaf_ids)
+    }
+
+    // Expanding the first leaf is cheaper (40 vs 56),
+    // but it makes the first leaf overlap the second one.
+    let test_mbr = mbr! { X = [13; 14], Y = [5; 6] };
+
+    let (tree, leaf_ids) = make_tree(LeastEnlargement);
+    let id = tree.insert(4, test_mbr.clone());
+    assert_eq!(tree.lock_obj_space().get_data(id).parent_id, leaf_ids[0]);
+
+    let (tree, leaf_ids) = make_tree(LeastOverlap);
+    let id = tree.insert(4, test_mbr);
+    assert_eq!(tree.lock_obj_space().get_data(id).parent_id, leaf_ids[1]);
+
+    check_tree_structure(&tree);
+}
+
+#[test]
+fn test_tree_custom_choose_subtree() {
+    #[derive(Debug)]
+    struct FirstCandidate;
+
+    impl ChooseSubtree<i32, usize> for FirstCandidate {
+        fn choose(
+            &self,
+            _: &ObjSpace<i32, usize>,
+            candidates: &[RecordId],
+            _: &MBR<i32>,
+        ) -> RecordId {
+            candidates[0]
+        }
+    }
+
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.set_choose_subtree(FirstCandidate);
+
+    for x in 0..20 {
+        for y in 0..20 {
+            tree.insert(
+                (x * 20 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    check_tree_structure(&tree);
+
+    let area = mbr! { X = [33; 67], Y = [12; 88] };
+    let found = tree
+        .search_objects(&area, |&object, _| object)
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let expected = (3..=6)
+        .flat_map(|x| (1..=8).map(move |y| x * 20 + y))
+        .collect::<HashSet<_>>();
+
+    assert_eq!(found, expected);
+    assert_eq!(tree.len(), 20 * 20);
+}
+
+#[test]
+fn test_tree_insert_policy() {
+    init_logger();
+
+    let make_tree = |insert_policy| {
+        let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 6));
+        assert_eq!(tree.insert_policy(), InsertPolicy::Split);
+
+        tree.set_insert_policy(insert_policy);
+        assert_eq!(tree.insert_policy(), insert_policy);
+
+        for x in 0..20 {
+            for y in 0..20 {
+                tree.insert(
+                    (x * 20 + y) as usize,
+                    mbr! {
+                        X = [x * 10; x * 10 + 5],
+                        Y = [y * 10; y * 10 + 5]
+                    },
+                );
+            }
+        }
+
+        check_tree_structure(&tree);
+
+        tree
+    };
+
+    let leaves = |tree: &LRTree<i32, usize>| {
+        let obj_space = tree.lock_obj_space();
+        let mut leaves = vec![];
+
+        let mut stack = vec![obj_space.root_id];
+        while let Some(node_id) = stack.pop() {
+            let children = &obj_space.get_node(node_id).payload;
+
+            match node_id {
+                RecordId::Leaf(_) => {
+                    let mut objects = children
+                        .iter()
+                        .map(|child_id| *obj_space.get_data_payload(child_id.as_node_id()))
+                        .collect::<Vec<_>>();
+                    objects.sort_unstable();
+
+                    leaves.push(objects);
+                }
+                _ => stack.extend(children.iter().cloned()),
+            }
+        }
+
+        leaves.sort();
+        leaves
+    };
+
+    let split_tree = make_tree(InsertPolicy::Split);
+    let reinsert_tree = make_tree(InsertPolicy::ForcedReinsert { count: 2 });
+
+    assert_ne!(leaves(&split_tree), leaves(&reinsert_tree));
+
+    let areas = [
+        mbr! { X = [ 0; 200], Y = [ 0; 200] },
+        mbr! { X = [ 0;  45], Y = [ 0;  45] },
+        mbr! { X = [33;  67], Y = [12; 188] },
+        mbr! { X = [95; 200], Y = [ 0;  10] },
+    ];
+
+    for area in areas.iter() {
+        let found = |tree: &LRTree<i32, usize>| {
+            tree.search_objects(area, |&object, _| object)
+                .into_iter()
+                .collect::<HashSet<_>>()
+        };
+
+        assert_eq!(found(&reinsert_tree), found(&split_tree));
+    }
+
+    assert_eq!(reinsert_tree.len(), 20 * 20);
+}
+
+#[test]
+#[should_panic]
+fn test_panic_tree_insert_policy() {
+    let tree = LRTree::<i32, usize>::with_obj_space(ObjSpace::new(2, 2, 6));
+
+    tree.set_insert_policy(InsertPolicy::ForcedReinsert { count: 6 });
+}
+
+#[test]
+fn test_tree_rebuild_str() {
+    init_logger();
+
+    let objects = (0..20).flat_map(|x| {
+        (0..20).map(move |y| {
+            (
+                (x * 20 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            )
+        })
+    });
+
+    let tree = LRTree::with_obj_space(ObjSpace::with_data(2, 2, 6, objects));
+    tree.rebuild_str();
+
+    // 400 objects in 67 leaves, 12 internal nodes, 2 internal nodes and the root.
+    assert_eq!(check_tree_structure(&tree), 67 + 12 + 2 + 1);
+    assert_eq!(tree.height(), 4);
+
+    {
+        let obj_space = tree.lock_obj_space();
+        let mut stack = vec![obj_space.root_id];
+        while let Some(node_id) = stack.pop() {
+            let children = &obj_space.get_node(node_id).payload;
+            assert!(children.len() <= 6);
+
+            if node_id != obj_space.root_id {
+                assert!(children.len() >= 2);
+            }
+
+            if let RecordId::Internal(_) = node_id {
+                stack.extend(children.iter().cloned());
+            }
+        }
+    }
+
+    for id in 0..400 {
+        assert_eq!(tree.depth_of(id), Some(4));
+    }
+
+    let area = mbr! { X = [33; 67], Y = [12; 88] };
+    let found = tree
+        .search_objects(&area, |&object, _| object)
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let expected = (3..=6)
+        .flat_map(|x| (1..=8).map(move |y| x * 20 + y))
+        .collect::<HashSet<_>>();
+
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn test_tree_rebuild_large_coords() {
+    init_logger();
+
+    // `min + max` of these bounds overflows `i32`
+    let base = i32::MAX - 1000;
+    let objects = (0..100).map(|object| {
+        let x = base + (object / 10) * 100;
+        let y = base + (object % 10) * 100;
+
+        (object as usize, mbr! { X = [x; x + 50], Y = [y; y + 50] })
+    });
+
+    let tree = LRTree::with_obj_space(ObjSpace::with_data(2, 2, 6, objects));
+    tree.rebuild(0.25);
+
+    check_tree_structure(&tree);
+
+    let area = mbr! { X = [base; base + 150], Y = [base; base + 150] };
+    let found = tree.search(&area).into_iter().collect::<HashSet<_>>();
+
+    assert_eq!(
+        found,
+        [0, 1, 10, 11].iter().cloned().collect::<HashSet<_>>()
+    );
+}
+
+#[test]
+fn test_tree_quadratic_split() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    tree.set_split_strategy(QuadraticSplit);
+
+    let mbrs = [
+        mbr! { X = [  0;   1], Y = [  0;   1] },
+        mbr! { X = [100; 101], Y = [100; 101] },
+        mbr! { X = [  2;   3], Y = [  0;   1] },
+        mbr! { X = [102; 103], Y = [100; 101] },
+        mbr! { X = [  0;   1], Y = [  2;   3] },
+    ];
+
+    for (object, mbr) in mbrs.iter().enumerate() {
+        tree.insert(object, mbr.clone());
+    }
+
+    check_tree_structure(&tree);
+    assert_eq!(tree.height(), 2);
+
+    let obj_space = tree.lock_obj_space();
+    let mut leaves = obj_space
+        .get_node(obj_space.root_id)
+        .payload
+        .iter()
+        .map(|&leaf_id| {
+            let mut objects = obj_space
+                .get_node(leaf_id)
+                .payload
+                .iter()
+                .map(|child_id| *obj_space.get_data_payload(child_id.as_node_id()))
+                .collect::<Vec<_>>();
+            objects.sort_unstable();
+
+            objects
+        })
+        .collect::<Vec<_>>();
+    leaves.sort();
+
+    assert_eq!(leaves, vec![vec![0, 2, 4], vec![1, 3]]);
+}
+
+#[test]
+fn test_tree_custom_split_strategy() {
+    #[derive(Debug)]
+    struct HalfSplit;
+
+    impl SplitStrategy<i32, usize> for HalfSplit {
+        fn split(
+            &self,
+            _: &ObjSpace<i32, usize>,
+            children: &mut Vec<RecordId>,
+        ) -> (Vec<RecordId>, Vec<RecordId>) {
+            let rhs = children.split_off(children.len() / 2);
+            let lhs = std::mem::take(children);
+
+            (lhs, rhs)
+        }
+    }
+
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.set_split_strategy(HalfSplit);
+
+    for x in 0..20 {
+        for y in 0..20 {
+            tree.insert(
+                (x * 20 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    check_tree_structure(&tree);
+
+    let area = mbr! { X = [33; 67], Y = [12; 88] };
+    let found = tree
+        .search_objects(&area, |&object, _| object)
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let expected = (3..=6)
+        .flat_map(|x| (1..=8).map(move |y| x * 20 + y))
+        .collect::<HashSet<_>>();
+
+    assert_eq!(found, expected);
+    assert_eq!(tree.len(), 20 * 20);
+}
+
+/// The root and the leaves with the objects 1..=12.
+fn make_12_objects_tree() -> LRTree<i32, i32> {
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.insert(
+        1,
+        mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        },
+    );
+
+    tree.insert(
+        2,
+        mbr! {
+            X = [11; 21],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        3,
+        mbr! {
+            X = [22; 32],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        4,
+        mbr! {
+            X = [ 0; 10],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        5,
+        mbr! {
+            X = [11; 21],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        6,
+        mbr! {
+            X = [22; 32],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        7,
+        mbr! {
+            X = [32; 42],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        8,
+        mbr! {
+            X = [42; 52],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        9,
+        mbr! {
+            X = [52; 62],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        10,
+        mbr! {
+            X = [62; 72],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        11,
+        mbr! {
+            X = [82; 92],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        12,
+        mbr! {
+            X = [92; 102],
+            Y = [11; 21]
+        },
+    );
+
+    tree
+}
+
+#[test]
+fn test_tree_visitor() {
+    struct TestVisitor {
+        lvl: usize,
+    }
+
+    impl TestVisitor {
+        fn new() -> Self {
+            Self { lvl: 0 }
+        }
+    }
+
+    impl Visitor<i32, i32> for TestVisitor {
+        fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<i32>) {
+            match node.parent_id {
+                RecordId::Root => {
+                    assert_eq!(self.lvl, 0);
+                    assert_eq!(record_id, RecordId::Internal(2));
+                }
+                RecordId::Internal(_) => assert_eq!(self.lvl, 1),
+                RecordId::Leaf(_) => assert_eq!(self.lvl, 2),
+                _ => unreachable!(),
+            }
+
+            self.lvl += 1;
+        }
+
+        fn leave_node(&mut self, _: RecordId, _: &InternalNode<i32>) {
+            self.lvl -= 1;
+        }
+
+        fn visit_data(&mut self, record_id: RecordId, node: &DataNode<i32, i32>) {
+            assert!(matches!(node.payload, 1..=12));
+            assert!(matches!(record_id, RecordId::Data(_)));
+            assert_eq!(self.lvl, 2);
+        }
+    }
+
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.insert(
+        1,
+        mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        },
+    );
+
+    tree.insert(
+        2,
+        mbr! {
+            X = [11; 21],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        3,
+        mbr! {
+            X = [22; 32],
+            Y = [ 0; 10]
+        },
+    );
+
+    tree.insert(
+        4,
+        mbr! {
+            X = [ 0; 10],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        5,
+        mbr! {
+            X = [11; 21],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        6,
+        mbr! {
+            X = [22; 32],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        7,
+        mbr! {
+            X = [32; 42],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        8,
+        mbr! {
+            X = [42; 52],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        9,
+        mbr! {
+            X = [52; 62],
+            Y = [11; 21]
+        },
+    );
+
+    tree.insert(
+        10,
+        mbr! {
+            X = [62; 72],
+            Y = [11; 21]
         },
     );
-    obj_space.get_data_mut(node_1_data_0).parent_id = second_node_id;
 
-    let node_1_data_1 = obj_space.make_data_node(
-        1,
+    tree.insert(
+        11,
         mbr! {
-            X = [12; 13],
-            Y = [3; 4]
+            X = [82; 92],
+            Y = [11; 21]
         },
     );
-    obj_space.get_data_mut(node_1_data_1).parent_id = second_node_id;
 
-    let first = obj_space.get_node_mut(first_node_id);
-    first.payload.push(RecordId::Data(node_0_data_0));
-    first.payload.push(RecordId::Data(node_0_data_1));
+    tree.insert(
+        12,
+        mbr! {
+            X = [92; 102],
+            Y = [11; 21]
+        },
+    );
 
-    let second = obj_space.get_node_mut(second_node_id);
-    second.payload.push(RecordId::Data(node_1_data_0));
-    second.payload.push(RecordId::Data(node_1_data_1));
-    std::mem::drop(obj_space);
+    let mut visitor = TestVisitor::new();
+    tree.visit(&mut visitor);
+}
 
-    let test_record_id = tree.insert(
+#[test]
+fn test_tree_dot_visitor() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for i in 0..12 {
+        tree.insert(i, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] });
+    }
+
+    let mut dot_visitor = DotVisitor::new();
+    tree.visit(&mut dot_visitor);
+    let dot = dot_visitor.into_dot();
+
+    assert!(dot.starts_with("digraph lr_tree {\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let mut expected_edges = vec![];
+    {
+        let obj_space = tree.lock_obj_space();
+        let mut node_ids = vec![obj_space.root_id];
+        while let Some(node_id) = node_ids.pop() {
+            for &child_id in obj_space.get_node(node_id).payload.iter() {
+                expected_edges.push(format!(
+                    "    \"{}\" -> \"{}\";",
+                    node_id.to_string(),
+                    child_id.to_string()
+                ));
+
+                if !matches!(child_id, RecordId::Data(_)) {
+                    node_ids.push(child_id);
+                }
+            }
+        }
+    }
+
+    let edges = dot
+        .lines()
+        .filter(|line| line.contains("->"))
+        .collect::<HashSet<_>>();
+
+    assert_eq!(edges.len(), expected_edges.len());
+    for edge in expected_edges.iter() {
+        assert!(edges.contains(edge.as_str()), "{} is missing", edge);
+    }
+
+    let data_id = tree.search(&mbr! { X = 70, Y = 0 })[0];
+    assert!(dot.contains(&format!(
+        "\"Data({})\" [label=\"7\", shape=ellipse];",
+        data_id
+    )));
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn test_tree_svg_visitor() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for i in 0..30 {
+        let (x, y) = (i % 6 * 10, i / 6 * 10);
+        tree.insert(i as usize, mbr! { X = [x; x + 5], Y = [y; y + 5] });
+    }
+
+    let mut svg_visitor = SvgVisitor::new(110.0, 90.0);
+    tree.visit(&mut svg_visitor);
+    let svg = svg_visitor.into_svg();
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    let nodes_num = check_tree_structure(&tree) + tree.len();
+    assert_eq!(svg.matches("<rect ").count(), nodes_num);
+
+    // The root covers the whole viewport
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"110\" height=\"90\" fill=\"none\""));
+
+    // The object at the top-left corner, Y is directed upwards
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"10\" height=\"10\" fill=\"#808080\""));
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn test_tree_svg_visitor_bfs() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    for i in 0..30 {
+        let (x, y) = (i % 6 * 10, i / 6 * 10);
+        tree.insert(i as usize, mbr! { X = [x; x + 5], Y = [y; y + 5] });
+    }
+
+    let sorted_rects = |svg: String| {
+        let mut rects = svg
+            .lines()
+            .filter(|line| line.trim_start().starts_with("<rect "))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        rects.sort();
+        rects
+    };
+
+    let mut svg_visitor = SvgVisitor::new(110.0, 90.0);
+    tree.visit(&mut svg_visitor);
+    let dfs_rects = sorted_rects(svg_visitor.into_svg());
+
+    let mut svg_visitor = SvgVisitor::new(110.0, 90.0);
+    tree.visit_bfs(&mut svg_visitor);
+    let bfs_rects = sorted_rects(svg_visitor.into_svg());
+
+    // The same nodes of the same colors
+    assert_eq!(bfs_rects, dfs_rects);
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn test_tree_svg_visitor_empty_root() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let id = tree.insert(0, mbr! { X = [0; 5], Y = [0; 5] });
+    assert_eq!(tree.remove(id), Some(0));
+
+    // The root is visited explicitly, its MBR is undefined
+    let root_id = tree.lock_obj_space().root_id;
+    let mut svg_visitor = SvgVisitor::new(110.0, 90.0);
+    tree.visit_from(&mut svg_visitor, root_id);
+    let svg = svg_visitor.into_svg();
+
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(svg.matches("<rect ").count(), 0);
+}
+
+#[test]
+fn test_tree_depth_of() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    let first_id = tree.insert(1, mbr! { X = [0; 10], Y = [0; 10] });
+
+    assert_eq!(tree.depth_of(first_id), Some(1));
+
+    let mut ids = vec![first_id];
+    let mbrs = [
+        mbr! { X = [11;  21], Y = [ 0; 10] },
+        mbr! { X = [22;  32], Y = [ 0; 10] },
+        mbr! { X = [ 0;  10], Y = [11; 21] },
+        mbr! { X = [11;  21], Y = [11; 21] },
+        mbr! { X = [22;  32], Y = [11; 21] },
+        mbr! { X = [32;  42], Y = [11; 21] },
+        mbr! { X = [42;  52], Y = [11; 21] },
+        mbr! { X = [52;  62], Y = [11; 21] },
+        mbr! { X = [62;  72], Y = [11; 21] },
+        mbr! { X = [82;  92], Y = [11; 21] },
+        mbr! { X = [92; 102], Y = [11; 21] },
+    ];
+
+    for (object, mbr) in mbrs.iter().cloned().enumerate() {
+        ids.push(tree.insert(object + 2, mbr));
+    }
+
+    assert_eq!(tree.height(), 2);
+    assert!(ids.iter().all(|&id| tree.depth_of(id) == Some(2)));
+
+    tree.mark_as_removed(vec![ids[3]].into_iter());
+    assert_eq!(tree.depth_of(ids[3]), None);
+    assert_eq!(tree.depth_of(ids[4]), Some(2));
+
+    let unbound_tree = LRTree::with_obj_space(ObjSpace::with_data(
         2,
+        2,
+        5,
+        vec![(1, mbr! { X = [0; 10], Y = [0; 10] })],
+    ));
+
+    assert_eq!(unbound_tree.depth_of(0), Some(0));
+
+    unbound_tree.rebuild(0.25);
+    assert_eq!(unbound_tree.depth_of(0), Some(1));
+}
+
+#[test]
+fn test_tree_height() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(1, 2, 3));
+    assert_eq!(tree.height(), 0);
+
+    tree.insert(0, mbr! { X = [0; 1] });
+    assert_eq!(tree.height(), 1);
+
+    for i in 1..3 {
+        tree.insert(i, mbr! { X = [i * 10; i * 10 + 1] });
+    }
+
+    assert_eq!(tree.height(), 1);
+
+    tree.insert(3, mbr! { X = [30; 31] });
+    assert_eq!(tree.height(), 2);
+
+    for i in 4..100 {
+        tree.insert(i, mbr! { X = [i * 10; i * 10 + 1] });
+    }
+
+    // 100 objects: each node has at most 3 children and at least 2 (except the root).
+    assert!(tree.height() >= 5);
+    assert!(tree.height() <= 7);
+
+    tree.clear();
+    assert_eq!(tree.height(), 0);
+}
+
+#[test]
+fn test_tree_find_slivers() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+    tree.insert(
+        1,
         mbr! {
-            X = [8; 10],
-            Y = [3; 5]
+            X = [0; 10],
+            Y = [0; 10]
         },
     );
 
-    let obj_space = tree.obj_space.read().unwrap();
-    let test_leaf_id = obj_space.get_data(test_record_id).parent_id;
-    assert_eq!(test_leaf_id, second_node_id);
+    let sliver_id = tree.insert(
+        2,
+        mbr! {
+            X = [0; 100],
+            Y = [0;   1]
+        },
+    );
+
+    tree.insert(
+        3,
+        mbr! {
+            X = [20; 25],
+            Y = [20; 30]
+        },
+    );
+
+    assert_eq!(tree.find_slivers(10.0), vec![sliver_id]);
+    assert!(tree.find_slivers(100.0).is_empty());
 }
 
 #[test]
-fn test_tree_visitor() {
-    struct TestVisitor {
-        lvl: usize,
+fn test_tree_insert_capped() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    let first_id = tree
+        .insert_capped(
+            1,
+            mbr! {
+                X = [0; 10],
+                Y = [0; 10]
+            },
+            2,
+        )
+        .unwrap();
+
+    let second_id = tree
+        .insert_capped(
+            2,
+            mbr! {
+                X = [11; 21],
+                Y = [ 0; 10]
+            },
+            2,
+        )
+        .unwrap();
+
+    let result = tree.insert_capped(
+        3,
+        mbr! {
+            X = [22; 32],
+            Y = [ 0; 10]
+        },
+        2,
+    );
+
+    assert_eq!(result, Err(CapExceeded { cap: 2, len: 2 }));
+
+    let set: HashSet<NodeId> = tree
+        .search(&mbr! {
+            X = [0; 32],
+            Y = [0; 10]
+        })
+        .iter()
+        .cloned()
+        .collect();
+
+    let expected: HashSet<NodeId> = [first_id, second_id].iter().cloned().collect();
+
+    assert_eq!(set, expected);
+}
+
+#[test]
+fn test_tree_insert_impact() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+
+    assert_eq!(
+        tree.insert_impact(&mbr! {
+            X = [0; 10],
+            Y = [0; 10]
+        }),
+        0
+    );
+
+    for i in 0..4 {
+        let x = i * 100;
+        tree.insert(
+            i,
+            mbr! {
+                X = [x; x + 10],
+                Y = [0; 10]
+            },
+        );
     }
 
-    impl TestVisitor {
-        fn new() -> Self {
-            Self { lvl: 0 }
-        }
+    let leaves_num = {
+        let obj_space = tree.lock_obj_space();
+        let root = obj_space.get_node(obj_space.root_id);
+
+        assert!(matches!(obj_space.root_id, RecordId::Internal(_)));
+        root.payload.len()
+    };
+
+    assert_eq!(
+        tree.insert_impact(&mbr! {
+            X = [-10; 1000],
+            Y = [  0; 10]
+        }),
+        leaves_num
+    );
+
+    assert_eq!(
+        tree.insert_impact(&mbr! {
+            X = [0; 5],
+            Y = [0; 5]
+        }),
+        1
+    );
+
+    assert_eq!(
+        tree.insert_impact(&mbr! {
+            X = [0; 5],
+            Y = [20; 30]
+        }),
+        0
+    );
+
+    assert_eq!(tree.lock_obj_space().data_num(), 4);
+}
+
+#[test]
+fn test_tree_mark_as_removed_result() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+
+    let ids = (0..10)
+        .map(|i| tree.insert(i, mbr! { X = [i; i + 1], Y = [0; 1] }))
+        .collect::<Vec<_>>();
+
+    let removed = tree.mark_as_removed(vec![ids[1], ids[4]].into_iter());
+    assert_eq!(removed, vec![ids[1], ids[4]]);
+
+    // Already removed, duplicate and unknown IDs are skipped.
+    let removed =
+        tree.mark_as_removed(vec![ids[4], ids[7], ids[2], ids[7], 100, ids[1]].into_iter());
+    assert_eq!(removed, vec![ids[7], ids[2]]);
+
+    assert!(tree.mark_as_removed(vec![100, 1000].into_iter()).is_empty());
+    assert!(tree.mark_as_removed(std::iter::empty()).is_empty());
+    assert_eq!(tree.len(), 6);
+
+    tree.restore_removed();
+
+    let removed = tree.mark_as_removed(ids.iter().cloned().chain(ids.iter().cloned()));
+    assert_eq!(removed, ids);
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn test_tree_restore_removed_result() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 3));
+
+    let ids = (0..10)
+        .map(|i| tree.insert(i, mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+        .collect::<Vec<_>>();
+
+    assert_eq!(tree.restore_removed(), 0);
+
+    let everything = mbr! {
+        X = [0; 100],
+        Y = [0; 5]
+    };
+
+    tree.mark_as_removed(vec![ids[2], ids[5], ids[8]].into_iter());
+    assert_eq!(tree.restore_removed(), 3);
+
+    assert_eq!(tree.len(), 10);
+    assert_eq!(
+        tree.search(&everything).into_iter().collect::<HashSet<_>>(),
+        ids.iter().cloned().collect()
+    );
+
+    tree.mark_as_removed(vec![ids[0], ids[9]].into_iter());
+
+    let mut restored_ids = tree.restore_removed_ids();
+    restored_ids.sort_unstable();
+
+    assert_eq!(restored_ids, vec![ids[0], ids[9]]);
+    assert!(tree.restore_removed_ids().is_empty());
+}
+
+#[test]
+fn test_tree_restore_many() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
+    let ids = (0..6)
+        .map(|i| {
+            let x = i * 11;
+            tree.insert(
+                i,
+                mbr! {
+                    X = [x; x + 10],
+                    Y = [0; 10]
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    tree.mark_as_removed(vec![ids[1], ids[4]].into_iter());
+    tree.rebuild(0.3);
+
+    let area = mbr! {
+        X = [0; 100],
+        Y = [0; 10]
+    };
+
+    assert_eq!(tree.search(&area).len(), 4);
+
+    tree.restore_many(vec![ids[4]].into_iter());
+
+    {
+        let obj_space = tree.lock_obj_space();
+        assert!(obj_space.is_removed(&ids[1]));
+        assert!(!obj_space.is_removed(&ids[4]));
     }
 
-    impl Visitor<i32, i32> for TestVisitor {
-        fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<i32>) {
-            match node.parent_id {
-                RecordId::Root => {
-                    assert_eq!(self.lvl, 0);
-                    assert_eq!(record_id, RecordId::Internal(2));
-                }
-                RecordId::Internal(_) => assert_eq!(self.lvl, 1),
-                RecordId::Leaf(_) => assert_eq!(self.lvl, 2),
-                _ => unreachable!(),
-            }
+    let set: HashSet<NodeId> = tree.search(&area).iter().cloned().collect();
+    let expected: HashSet<NodeId> = [ids[0], ids[2], ids[3], ids[4], ids[5]]
+        .iter()
+        .cloned()
+        .collect();
+
+    assert_eq!(set, expected);
+}
 
-            self.lvl += 1;
-        }
+#[test]
+fn test_tree_expected_node_accesses() {
+    init_logger();
 
-        fn leave_node(&mut self, _: RecordId, _: &InternalNode<i32>) {
-            self.lvl -= 1;
-        }
+    let tree = LRTree::with_obj_space(ObjSpace::<f64, usize>::new(2, 2, 5));
 
-        fn visit_data(&mut self, record_id: RecordId, node: &DataNode<i32, i32>) {
-            assert!(matches!(node.payload, 1..=12));
-            assert!(matches!(record_id, RecordId::Data(_)));
-            assert_eq!(self.lvl, 2);
-        }
+    assert_eq!(tree.expected_node_accesses(1.0), 0.0);
+
+    tree.insert(
+        0,
+        mbr! {
+            X = [0.0; 10.0],
+            Y = [0.0; 10.0]
+        },
+    );
+
+    // The root is the only node and it always covers the whole space.
+    assert_eq!(tree.expected_node_accesses(1.0), 1.0);
+
+    for i in 1..12 {
+        let x = i as f64 * 10.0;
+        tree.insert(
+            i,
+            mbr! {
+                X = [x; x + 10.0],
+                Y = [0.0; 10.0]
+            },
+        );
     }
 
+    let small_query = tree.expected_node_accesses(1.0);
+    let large_query = tree.expected_node_accesses(100.0 * 100.0);
+
+    assert!(small_query >= 1.0);
+    assert!(small_query < large_query);
+}
+
+#[test]
+fn test_tree_grid_histogram() {
     init_logger();
 
     let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 5));
+
     tree.insert(
         1,
         mbr! {
-            X = [0; 10],
-            Y = [0; 10]
+            X = [0; 2],
+            Y = [0; 2]
         },
     );
 
     tree.insert(
         2,
         mbr! {
-            X = [11; 21],
-            Y = [ 0; 10]
+            X = [6; 8],
+            Y = [2; 4]
         },
     );
 
+    // Spans several cells, but its center is in the cell [0, 0]
     tree.insert(
         3,
         mbr! {
-            X = [22; 32],
-            Y = [ 0; 10]
+            X = [-5; 13],
+            Y = [-4; 12]
         },
     );
 
     tree.insert(
         4,
         mbr! {
-            X = [ 0; 10],
-            Y = [11; 21]
+            X = [-12; -11],
+            Y = [ 11;  12]
         },
     );
 
-    tree.insert(
-        5,
-        mbr! {
-            X = [11; 21],
-            Y = [11; 21]
-        },
-    );
+    let histogram = tree.grid_histogram(&[10, 10]);
 
-    tree.insert(
-        6,
-        mbr! {
-            X = [22; 32],
-            Y = [11; 21]
-        },
-    );
+    assert_eq!(histogram.len(), 2);
+    assert_eq!(histogram[&vec![0, 0]], 3);
+    assert_eq!(histogram[&vec![-2, 1]], 1);
+}
 
-    tree.insert(
-        7,
-        mbr! {
-            X = [32; 42],
-            Y = [11; 21]
-        },
-    );
+#[test]
+fn test_tree_large_integer_volumes() {
+    init_logger();
 
-    tree.insert(
-        8,
-        mbr! {
-            X = [42; 52],
-            Y = [11; 21]
-        },
-    );
+    let length = i32::MAX / 3;
+    let tree = LRTree::with_obj_space(ObjSpace::new(3, 2, 3));
 
-    tree.insert(
-        9,
-        mbr! {
-            X = [52; 62],
-            Y = [11; 21]
-        },
-    );
+    for i in 0..10 {
+        let offset = i * 1000;
+        tree.insert(
+            i,
+            mbr! {
+                X = [offset; offset + length],
+                Y = [offset; offset + length],
+                Z = [offset; offset + length]
+            },
+        );
+    }
 
-    tree.insert(
-        10,
-        mbr! {
-            X = [62; 72],
-            Y = [11; 21]
-        },
+    let found = tree.search(&mbr! {
+        X = [0; 1],
+        Y = [0; 1],
+        Z = [0; 1]
+    });
+
+    assert_eq!(found.len(), 1);
+}
+
+#[test]
+fn test_tree_collinear_points() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+
+    for &i in &[0, 9, 1, 8, 2, 7, 3, 6, 4, 5] {
+        let x = i * 10;
+        tree.insert(
+            i,
+            mbr! {
+                X = [x; x],
+                Y = [0; 0]
+            },
+        );
+    }
+
+    let obj_space = tree.lock_obj_space();
+    let leaves = obj_space
+        .get_node(obj_space.root_id)
+        .payload
+        .iter()
+        .map(|&leaf_id| obj_space.get_mbr(leaf_id).bounds(0).clone())
+        .collect::<Vec<_>>();
+
+    assert!(leaves.len() > 1);
+
+    let overlap = leaves
+        .iter()
+        .enumerate()
+        .flat_map(|(i, lhs)| {
+            leaves
+                .iter()
+                .skip(i + 1)
+                .map(move |rhs| lhs.overlap_length(rhs))
+        })
+        .sum::<i32>();
+
+    assert_eq!(overlap, 0);
+}
+
+#[test]
+fn test_tree_collinear_points_without_margin_tie_break() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    assert!(tree.margin_tie_break());
+
+    tree.set_margin_tie_break(false);
+    assert!(!tree.margin_tie_break());
+
+    for &i in &[0usize, 9, 1, 8, 2, 7, 3, 6, 4, 5] {
+        let x = i as i32 * 10;
+        tree.insert(
+            i,
+            mbr! {
+                X = [x; x],
+                Y = [0; 0]
+            },
+        );
+    }
+
+    check_tree_structure(&tree);
+    assert_eq!(tree.search(&mbr! { X = [0; 90], Y = [0; 0] }).len(), 10);
+    assert_eq!(tree.search(&mbr! { X = [25; 45], Y = [0; 0] }).len(), 2);
+}
+
+#[test]
+fn test_tree_from_iter() {
+    init_logger();
+
+    let tree = (0..10)
+        .flat_map(|x| (0..10).map(move |y| (x, y)))
+        .map(|(x, y)| {
+            (
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            )
+        })
+        .collect::<LRTree<_, _>>();
+
+    assert_eq!(tree.len(), 100);
+    check_tree_structure(&tree);
+
+    let obj_space = tree.lock_obj_space();
+    assert_eq!(obj_space.dimension, 2);
+    assert_eq!(obj_space.min_records, 2);
+    assert_eq!(obj_space.max_records, 8);
+    drop(obj_space);
+
+    let mut found = tree
+        .search(&mbr! { X = [12; 38], Y = [0; 5] })
+        .into_iter()
+        .map(|id| tree.access_object(id, |&object, _| object))
+        .collect::<Vec<_>>();
+    found.sort_unstable();
+    assert_eq!(found, vec![10, 20, 30]);
+}
+
+#[test]
+fn test_tree_from_empty_iter() {
+    init_logger();
+
+    let tree = std::iter::empty().collect::<LRTree<i32, usize>>();
+    assert!(tree.is_empty());
+
+    let obj_space = tree.lock_obj_space();
+    assert_eq!(obj_space.dimension, 2);
+    assert_eq!(obj_space.min_records, 2);
+    assert_eq!(obj_space.max_records, 8);
+    drop(obj_space);
+
+    let id = tree.insert(0, mbr! { X = [0; 1], Y = [0; 1] });
+    assert_eq!(tree.search(&mbr! { X = [0; 5], Y = [0; 5] }), vec![id]);
+    check_tree_structure(&tree);
+}
+
+#[test]
+#[should_panic(expected = "item #1 dimension differs from the first item one")]
+fn test_panic_tree_from_iter_dimension() {
+    let _ = vec![
+        (0, mbr! { X = [0; 1], Y = [0; 1] }),
+        (1, mbr! { X = [0; 1] }),
+    ]
+    .into_iter()
+    .collect::<LRTree<i32, usize>>();
+}
+
+#[test]
+fn test_tree_with_config() {
+    init_logger();
+
+    let tree =
+        LRTree::with_config(TreeConfig::new().dimension(3).max_records(5).min_records(3)).unwrap();
+    tree.insert(0usize, mbr! { X = [0; 1], Y = [0; 1], Z = [0; 1] });
+
+    let obj_space = tree.lock_obj_space();
+    assert_eq!(obj_space.dimension, 3);
+    assert_eq!(obj_space.min_records, 3);
+    assert_eq!(obj_space.max_records, 5);
+    drop(obj_space);
+
+    let tree = LRTree::<i32, usize>::with_config(TreeConfig::default()).unwrap();
+    let obj_space = tree.lock_obj_space();
+    assert_eq!(obj_space.dimension, 2);
+    assert_eq!(obj_space.min_records, 2);
+    assert_eq!(obj_space.max_records, 8);
+}
+
+#[test]
+fn test_tree_config_errors() {
+    let build = |config: TreeConfig| config.build::<i32, usize>().map(|_| ());
+
+    assert_eq!(
+        build(TreeConfig::new().dimension(0)),
+        Err(ConfigError::ZeroDimension)
+    );
+    assert_eq!(
+        build(TreeConfig::new().min_records(1)),
+        Err(ConfigError::MinRecordsTooSmall { min_records: 1 })
+    );
+    assert_eq!(
+        build(TreeConfig::new().min_records(4).max_records(7)),
+        Ok(())
+    );
+    assert_eq!(
+        build(TreeConfig::new().min_records(5).max_records(8)),
+        Err(ConfigError::MinRecordsTooLarge {
+            min_records: 5,
+            max_records: 8
+        })
     );
 
-    tree.insert(
-        11,
-        mbr! {
-            X = [82; 92],
-            Y = [11; 21]
-        },
+    let error = LRTree::<i32, usize>::with_config(TreeConfig::new().min_records(0)).unwrap_err();
+    assert_eq!(error.to_string(), "min_records must be at least 2, got 0");
+}
+
+#[test]
+fn test_tree_display() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    assert_eq!(
+        tree.to_string(),
+        "LRTree { dim: 2, objects: 0, height: 0, root_mbr: MBR { /undefined/ } }"
     );
 
-    tree.insert(
-        12,
-        mbr! {
-            X = [92; 102],
-            Y = [11; 21]
-        },
+    for x in 0..10 {
+        for y in 0..10 {
+            tree.insert(
+                (x * 10 + y) as usize,
+                mbr! {
+                    X = [x * 10; x * 10 + 5],
+                    Y = [y * 10; y * 10 + 5]
+                },
+            );
+        }
+    }
+
+    assert_eq!(
+        tree.to_string(),
+        format!(
+            "LRTree {{ dim: 2, objects: 100, height: {}, root_mbr: MBR {{ x1: [0; 95] x2: [0; 95] }} }}",
+            tree.height()
+        )
     );
+}
 
-    let mut visitor = TestVisitor::new();
-    tree.visit(&mut visitor);
+#[test]
+fn test_tree_for_each_leaf() {
+    init_logger();
+
+    let tree = make_12_objects_tree();
+    let obj_space = tree.lock_obj_space();
+    let root_children_num = obj_space.get_node(obj_space.root_id).payload.len();
+    drop(obj_space);
+
+    let mut leaf_ids = HashSet::new();
+    let mut data_num = 0;
+    tree.for_each_leaf(|id, node| {
+        assert!(matches!(id, RecordId::Leaf(_)));
+        assert!(node
+            .payload
+            .iter()
+            .all(|child_id| matches!(child_id, RecordId::Data(_))));
+
+        assert!(leaf_ids.insert(id));
+        data_num += node.payload.len();
+    });
+
+    // All the root children are the leaves.
+    assert_eq!(leaf_ids.len(), root_children_num);
+    assert_eq!(leaf_ids.len(), 4);
+    assert_eq!(data_num, 12);
+
+    let empty_tree = LRTree::<i32, i32>::with_obj_space(ObjSpace::new(2, 2, 5));
+    empty_tree.for_each_leaf(|_, _| panic!("the tree is empty"));
+}
+
+#[test]
+fn test_tree_visit_with_depth() {
+    #[derive(Default)]
+    struct TestVisitor {
+        lvl: usize,
+        data_num: usize,
+    }
+
+    impl DepthVisitor<i32, i32> for TestVisitor {
+        fn enter_node(&mut self, _: RecordId, node: &InternalNode<i32>, depth: usize) {
+            assert_eq!(depth, self.lvl);
+            match node.parent_id {
+                RecordId::Root => assert_eq!(depth, 0),
+                _ => assert_eq!(depth, 1),
+            }
+
+            self.lvl += 1;
+        }
+
+        fn leave_node(&mut self, _: RecordId, _: &InternalNode<i32>, depth: usize) {
+            self.lvl -= 1;
+            assert_eq!(depth, self.lvl);
+        }
+
+        fn visit_data(&mut self, _: RecordId, _: &DataNode<i32, i32>, depth: usize) {
+            assert_eq!(depth, self.lvl);
+            assert_eq!(depth, 2);
+            self.data_num += 1;
+        }
+    }
+
+    init_logger();
+
+    let tree = make_12_objects_tree();
+
+    let mut visitor = TestVisitor::default();
+    tree.visit_with_depth(&mut visitor);
+
+    assert_eq!(visitor.lvl, 0);
+    assert_eq!(visitor.data_num, 12);
+}
+
+#[test]
+fn test_tree_access_object_mut() {
+    init_logger();
+
+    let tree = LRTree::with_obj_space(ObjSpace::new(2, 2, 4));
+    let ids = (0..10)
+        .map(|i| tree.insert(vec![i], mbr! { X = [i * 10; i * 10 + 5], Y = [0; 5] }))
+        .collect::<Vec<_>>();
+
+    let len = tree.access_object_mut(ids[3], |object, mbr| {
+        assert_eq!(*mbr, mbr! { X = [30; 35], Y = [0; 5] });
+
+        object.push(100);
+        object.len()
+    });
+    assert_eq!(len, 2);
+
+    tree.access_object(ids[3], |object, mbr| {
+        assert_eq!(*object, vec![3, 100]);
+        assert_eq!(*mbr, mbr! { X = [30; 35], Y = [0; 5] });
+    });
+
+    let found = tree.search(&mbr! { X = [30; 35], Y = [0; 5] });
+    assert_eq!(found, vec![ids[3]]);
+}
+
+/// Records the visited IDs in the visiting order.
+#[derive(Default)]
+struct RecordingVisitor {
+    entered: Vec<RecordId>,
+    left: Vec<RecordId>,
+    data: Vec<RecordId>,
+}
+
+impl Visitor<i32, i32> for RecordingVisitor {
+    fn enter_node(&mut self, record_id: RecordId, _: &InternalNode<i32>) {
+        self.entered.push(record_id);
+    }
+
+    fn leave_node(&mut self, record_id: RecordId, _: &InternalNode<i32>) {
+        self.left.push(record_id);
+    }
+
+    fn visit_data(&mut self, record_id: RecordId, _: &DataNode<i32, i32>) {
+        self.data.push(record_id);
+    }
+}
+
+#[test]
+fn test_tree_visit_from() {
+    init_logger();
+
+    let tree = make_12_objects_tree();
+
+    let mut leaves = vec![];
+    tree.for_each_leaf(|id, node| leaves.push((id, node.payload.clone())));
+    let (leaf_id, leaf_children) = leaves[1].clone();
+
+    let mut visitor = RecordingVisitor::default();
+    tree.visit_from(&mut visitor, leaf_id);
+    assert_eq!(visitor.entered, vec![leaf_id]);
+    assert_eq!(visitor.left, vec![leaf_id]);
+    assert_eq!(visitor.data, leaf_children.to_vec());
+
+    let mut visitor = RecordingVisitor::default();
+    tree.visit_from(&mut visitor, leaf_children[0]);
+    assert!(visitor.entered.is_empty());
+    assert_eq!(visitor.data, vec![leaf_children[0]]);
+
+    let mut visitor = RecordingVisitor::default();
+    tree.visit_from(&mut visitor, RecordId::Root);
+    let mut full_visitor = RecordingVisitor::default();
+    tree.visit(&mut full_visitor);
+    assert_eq!(visitor.entered, full_visitor.entered);
+    assert_eq!(visitor.data, full_visitor.data);
+    assert_eq!(visitor.data.len(), 12);
+}
+
+#[test]
+fn test_tree_visit_in_area() {
+    init_logger();
+
+    let tree = make_12_objects_tree();
+
+    let areas = [
+        mbr! { X = [5; 25], Y = [5; 15] },
+        mbr! { X = [75; 80], Y = [0; 30] },
+        mbr! { X = [-10; 200], Y = [-10; 200] },
+        mbr! { X = [500; 600], Y = [0; 10] },
+    ];
+
+    for area in areas.iter() {
+        let mut visitor = RecordingVisitor::default();
+        tree.visit_in_area(&mut visitor, area);
+
+        let visited = visitor
+            .data
+            .iter()
+            .map(|id| id.as_node_id())
+            .collect::<HashSet<_>>();
+        let found = tree.search(area).into_iter().collect::<HashSet<_>>();
+        assert_eq!(visited, found);
+        assert_eq!(visitor.data.len(), found.len());
+
+        let obj_space = tree.lock_obj_space();
+        for &id in visitor.entered.iter() {
+            assert!(mbr::intersects(obj_space.get_mbr(id), area));
+        }
+    }
+}
+
+#[test]
+fn test_tree_visit_bfs() {
+    init_logger();
+
+    let tree = make_12_objects_tree();
+
+    let mut visitor = RecordingVisitor::default();
+    tree.visit_bfs(&mut visitor);
+
+    let obj_space = tree.lock_obj_space();
+    let root_id = obj_space.root_id;
+    let leaf_ids = obj_space.get_node(root_id).payload.clone();
+
+    let mut expected_entered = vec![root_id];
+    expected_entered.extend(leaf_ids.iter().cloned());
+    assert_eq!(visitor.entered, expected_entered);
+
+    let expected_data = leaf_ids
+        .iter()
+        .flat_map(|&leaf_id| obj_space.get_node(leaf_id).payload.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(visitor.data, expected_data);
+    assert_eq!(visitor.data.len(), 12);
+
+    assert!(visitor.left.is_empty());
 }
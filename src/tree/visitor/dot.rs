@@ -0,0 +1,76 @@
+use {
+    crate::{
+        tree::{visitor::Visitor, CoordTrait, DataNode, InternalNode},
+        RecordId,
+    },
+    std::fmt::{Debug, Write},
+};
+
+/// Collects the tree structure as a GraphViz DOT digraph.
+///
+/// Internal nodes are labeled with their `RecordId`, data nodes with the `Debug` of their objects.
+#[derive(Debug, Default)]
+pub struct DotVisitor {
+    nodes: Vec<String>,
+    edges: Vec<String>,
+}
+
+impl DotVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_dot(self) -> String {
+        let mut dot = "digraph lr_tree {\n".to_string();
+
+        for line in self.nodes.iter().chain(self.edges.iter()) {
+            writeln!(dot, "    {};", line).expect("write to String is expected to succeed");
+        }
+
+        dot.push('}');
+        dot.push('\n');
+
+        dot
+    }
+
+    fn add_node(&mut self, record_id: RecordId, parent_id: RecordId, label: &str, shape: &str) {
+        self.nodes.push(format!(
+            "{} [label={}, shape={}]",
+            quote(&record_id.to_string()),
+            quote(label),
+            shape
+        ));
+
+        if parent_id != RecordId::Root {
+            self.edges.push(format!(
+                "{} -> {}",
+                quote(&parent_id.to_string()),
+                quote(&record_id.to_string())
+            ));
+        }
+    }
+}
+
+impl<CoordT: CoordTrait, ObjectT: Clone + Debug> Visitor<CoordT, ObjectT> for DotVisitor {
+    fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>) {
+        self.add_node(record_id, node.parent_id, &record_id.to_string(), "box");
+    }
+
+    fn leave_node(&mut self, _: RecordId, _: &InternalNode<CoordT>) {
+        // do nothing
+    }
+
+    fn visit_data(&mut self, record_id: RecordId, node: &DataNode<CoordT, ObjectT>) {
+        self.add_node(
+            record_id,
+            node.parent_id,
+            &format!("{:?}", node.payload),
+            "ellipse",
+        );
+    }
+}
+
+/// Makes a DOT quoted string.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -2,6 +2,9 @@ use super::{CoordTrait, DataNode, InternalNode, RecordId};
 
 #[cfg(feature = "with-dbg-vis")]
 pub mod dbg_vis;
+pub mod dot;
+#[cfg(feature = "svg")]
+pub mod svg;
 
 pub trait Visitor<CoordT: CoordTrait, ObjectT: Clone> {
     fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>);
@@ -10,3 +13,33 @@ pub trait Visitor<CoordT: CoordTrait, ObjectT: Clone> {
 
     fn visit_data(&mut self, record_id: RecordId, node: &DataNode<CoordT, ObjectT>);
 }
+
+/// Like `Visitor`, but also gets the depth of the record: 0 for the root.
+///
+/// Every `Visitor` is a `DepthVisitor` ignoring the depth, see `LRTree::visit_with_depth`.
+pub trait DepthVisitor<CoordT: CoordTrait, ObjectT: Clone> {
+    fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>, depth: usize);
+
+    fn leave_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>, depth: usize);
+
+    fn visit_data(&mut self, record_id: RecordId, node: &DataNode<CoordT, ObjectT>, depth: usize);
+}
+
+impl<CoordT, ObjectT, V> DepthVisitor<CoordT, ObjectT> for V
+where
+    CoordT: CoordTrait,
+    ObjectT: Clone,
+    V: Visitor<CoordT, ObjectT>,
+{
+    fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>, _: usize) {
+        Visitor::enter_node(self, record_id, node)
+    }
+
+    fn leave_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>, _: usize) {
+        Visitor::leave_node(self, record_id, node)
+    }
+
+    fn visit_data(&mut self, record_id: RecordId, node: &DataNode<CoordT, ObjectT>, _: usize) {
+        Visitor::visit_data(self, record_id, node)
+    }
+}
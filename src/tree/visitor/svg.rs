@@ -0,0 +1,124 @@
+use {
+    crate::{
+        tree::{visitor::Visitor, CoordTrait, DataNode, InternalNode},
+        RecordId, MBR,
+    },
+    std::{collections::HashMap, fmt::Write},
+};
+
+/// Stroke colors of the internal nodes, the color is chosen by the node depth.
+const DEPTH_COLORS: [&str; 6] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4",
+];
+
+/// Fill color of the data nodes.
+const DATA_COLOR: &str = "#808080";
+
+/// Draws a 2D tree as an SVG image of the given viewport size.
+///
+/// The tree is scaled to the whole viewport by the root MBR,
+/// the Y axis is directed upwards like in the tree space.
+/// The colors don't depend on the visiting order, so `visit_bfs` draws the same image as `visit`.
+/// Records with undefined MBRs (e.g. an empty root) are skipped.
+#[derive(Debug)]
+pub struct SvgVisitor {
+    width: f64,
+    height: f64,
+    area: Option<[(f64, f64); 2]>,
+
+    /// Depths of the entered nodes, a node is one level below its parent.
+    depths: HashMap<RecordId, usize>,
+    rects: String,
+}
+
+impl SvgVisitor {
+    pub fn new(width: f64, height: f64) -> Self {
+        assert!(width > 0.0 && height > 0.0, "viewport must be non-empty");
+
+        Self {
+            width,
+            height,
+            area: None,
+            depths: HashMap::new(),
+            rects: String::new(),
+        }
+    }
+
+    pub fn into_svg(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{rects}</svg>\n",
+            w = self.width,
+            h = self.height,
+            rects = self.rects
+        )
+    }
+
+    fn add_rect<CoordT: CoordTrait>(&mut self, mbr: &MBR<CoordT>, style: &str) {
+        if mbr.is_undefined() {
+            return;
+        }
+
+        assert_eq!(mbr.dimension(), 2, "unexpected dimension");
+
+        let to_f64 = |value: &CoordT| {
+            value
+                .to_f64()
+                .expect("CoordT is expected to be convertible to f64")
+        };
+
+        let (x, y) = (mbr.bounds(0), mbr.bounds(1));
+        let (min_x, max_x) = (to_f64(&x.min), to_f64(&x.max));
+        let (min_y, max_y) = (to_f64(&y.min), to_f64(&y.max));
+
+        let [(area_x, area_width), (area_y, area_height)] = *self
+            .area
+            .get_or_insert([(min_x, max_x - min_x), (min_y, max_y - min_y)]);
+
+        let scale = |length: f64, area_length: f64, viewport_length: f64| {
+            if area_length > 0.0 {
+                length / area_length * viewport_length
+            } else {
+                0.0
+            }
+        };
+
+        writeln!(
+            self.rects,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+            scale(min_x - area_x, area_width, self.width),
+            self.height - scale(max_y - area_y, area_height, self.height),
+            scale(max_x - min_x, area_width, self.width),
+            scale(max_y - min_y, area_height, self.height),
+            style
+        )
+        .expect("write to String is expected to succeed");
+    }
+}
+
+impl<CoordT: CoordTrait, ObjectT: Clone> Visitor<CoordT, ObjectT> for SvgVisitor {
+    fn enter_node(&mut self, record_id: RecordId, node: &InternalNode<CoordT>) {
+        // The parent is unknown for the root and for the start of `visit_from`
+        let depth = self
+            .depths
+            .get(&node.parent_id)
+            .map_or(0, |parent_depth| parent_depth + 1);
+        self.depths.insert(record_id, depth);
+
+        let color = DEPTH_COLORS[depth % DEPTH_COLORS.len()];
+        self.add_rect(
+            &node.mbr,
+            &format!("fill=\"none\" stroke=\"{}\" stroke-width=\"1\"", color),
+        );
+    }
+
+    fn leave_node(&mut self, record_id: RecordId, _: &InternalNode<CoordT>) {
+        self.depths.remove(&record_id);
+    }
+
+    fn visit_data(&mut self, _: RecordId, node: &DataNode<CoordT, ObjectT>) {
+        self.add_rect(
+            &node.mbr,
+            &format!("fill=\"{}\" fill-opacity=\"0.5\"", DATA_COLOR),
+        );
+    }
+}